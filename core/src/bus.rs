@@ -0,0 +1,216 @@
+use crate::component::Component;
+use crate::event::{get_inner, Event};
+use crate::logging::Level;
+use crate::sim_log;
+use crate::sim_manager::SimManager;
+use crate::task::Task;
+use crate::types::{ComponentId, EventId, Input, Output, SimTime};
+use crossbeam_channel::{unbounded, Sender};
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Values that can be put on a `Bus`
+pub trait BusValue: Copy + PartialEq + Debug + Send + Sync + 'static {
+    /// What a driver that lets go of the bus drives, and what the bus reads as with nobody driving it
+    fn high_z() -> Self;
+
+    /// Wired-or of two drivers, bits that are not driven do not pull the bus up
+    fn wired_or(self, other: Self) -> Self;
+
+    /// Bits driven by both sides are unknown unless both drive the same known value,
+    /// the flag is set if any bit was driven to different or unknown values
+    fn tri_state(self, other: Self) -> (Self, bool);
+
+    /// Wraps the value in the event the readers of the bus expect
+    fn into_event(self, scheduled_time: SimTime, event_id: EventId) -> Box<dyn Event>;
+}
+
+/// Result of a resolution function
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Resolved<T> {
+    pub value: T,
+    pub contention: bool,
+}
+
+/// Resolves the values of every driver, in the order the drivers were added
+pub type ResolutionFn<T> = Arc<dyn Fn(&[T]) -> Resolved<T> + Send + Sync>;
+
+/// How a `Bus` combines its drivers
+#[derive(Clone)]
+pub enum Resolution<T> {
+    WiredOr,
+    TriState,
+    /// Whoever drove the bus last wins, drivers updating at the same time win in the order they were added
+    LastWriter,
+    Custom(ResolutionFn<T>),
+}
+
+/// A net with any number of drivers and readers.
+///
+/// Drivers send to the outputs handed out by `add_driver`, like they would to any input port.
+/// Every time some drivers update, the bus resolves the values of all drivers and sends the result to every reader.
+/// Drivers that never drove the bus count as high impedance.
+/// Contention makes the bus log a warning, what the bus reads as is up to the resolution function.
+pub struct Bus<T: BusValue> {
+    component_id: ComponentId,
+    sim_manager: Arc<SimManager>,
+    ack_sender: Sender<EventId>,
+    resolution: Resolution<T>,
+    drivers: Vec<(Input, T)>,
+    readers: Vec<Output>,
+    last_driver: Option<usize>,
+    value: T,
+    contention_count: u64,
+}
+
+impl<T: BusValue> Bus<T> {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        resolution: Resolution<T>,
+    ) -> Self {
+        Bus {
+            component_id,
+            sim_manager,
+            ack_sender,
+            resolution,
+            drivers: Vec::new(),
+            readers: Vec::new(),
+            last_driver: None,
+            value: T::high_z(),
+            contention_count: 0,
+        }
+    }
+
+    /// The returned output goes to the driving component
+    pub fn add_driver(&mut self) -> Output {
+        let channel = unbounded();
+        self.drivers.push((channel.1, T::high_z()));
+        channel.0
+    }
+
+    /// The returned input goes to the reading component
+    pub fn add_reader(&mut self) -> Input {
+        let channel = unbounded();
+        self.readers.push(channel.0);
+        channel.1
+    }
+
+    /// Resolved value as of the last update
+    pub fn get_value(&self) -> T {
+        self.value
+    }
+
+    pub fn get_contention_count(&self) -> u64 {
+        self.contention_count
+    }
+
+    fn resolve(&self) -> Resolved<T> {
+        let values: Vec<T> = self.drivers.iter().map(|(_, value)| *value).collect();
+        match &self.resolution {
+            Resolution::WiredOr => Resolved {
+                value: values.into_iter().fold(T::high_z(), T::wired_or),
+                contention: false,
+            },
+            Resolution::TriState => values.into_iter().fold(
+                Resolved {
+                    value: T::high_z(),
+                    contention: false,
+                },
+                |resolved, value| {
+                    let (value, contention) = resolved.value.tri_state(value);
+                    Resolved {
+                        value,
+                        contention: resolved.contention || contention,
+                    }
+                },
+            ),
+            Resolution::LastWriter => Resolved {
+                value: self
+                    .last_driver
+                    .map_or(T::high_z(), |driver| values[driver]),
+                contention: false,
+            },
+            Resolution::Custom(resolution) => resolution(&values),
+        }
+    }
+}
+
+impl<T: BusValue> Component for Bus<T> {
+    fn init(&mut self) {}
+
+    fn reset(&mut self) {
+        self.drivers
+            .iter_mut()
+            .for_each(|(_, value)| *value = T::high_z());
+        self.last_driver = None;
+        self.value = T::high_z();
+    }
+
+    /// Drains every driver before resolving, so drivers updating at the same time are resolved together
+    fn poll_recv(&mut self) {
+        let mut updated = Vec::new();
+        for (driver, (input, value)) in self.drivers.iter_mut().enumerate() {
+            while let Ok(event) = input.try_recv() {
+                *value = get_inner::<T>(&*event);
+                updated.push((driver, event.get_event_id()));
+            }
+        }
+        if updated.is_empty() {
+            return;
+        }
+        self.last_driver = updated.last().map(|(driver, _)| *driver);
+
+        let resolved = self.resolve();
+        self.value = resolved.value;
+        if resolved.contention {
+            self.contention_count += 1;
+            let drivers: Vec<T> = self.drivers.iter().map(|(_, value)| *value).collect();
+            sim_log!(
+                self,
+                Level::Warn,
+                "contention, drivers {:?} resolved to {:?}",
+                drivers,
+                self.value
+            );
+        }
+
+        for reader in self.readers.iter() {
+            let event = self.value.into_event(
+                self.sim_manager.get_curr_time(),
+                self.sim_manager.request_new_event_id(),
+            );
+            let source = self
+                .sim_manager
+                .request_task_source(self.component_id, "output");
+            self.sim_manager
+                .enq_event(Task::with_source(event, reader.clone(), source));
+        }
+        // Acked after the readers' events are queued, so they go out in the next batch of this time step
+        for (_, event_id) in updated {
+            self.ack_sender.send(event_id).unwrap();
+        }
+    }
+
+    fn get_component_id(&self) -> ComponentId {
+        self.component_id
+    }
+
+    fn get_component_name(&self) -> &'static str {
+        "Bus"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// `value` is the resolved value
+    fn get_port(&self, port_name: &str) -> Option<&dyn Any> {
+        match port_name {
+            "value" => Some(&self.value),
+            _ => None,
+        }
+    }
+}
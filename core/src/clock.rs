@@ -0,0 +1,96 @@
+use crate::types::{Output, SimTime};
+
+pub type ClockId = usize;
+
+/// The clock every sim manager starts with, `get_curr_cycle` counts its edges
+pub const DEFAULT_CLOCK: ClockId = 0;
+
+/// Unit of `SimTime`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Timescale {
+    /// One tick per cycle of the default clock, time and cycles are the same thing
+    Cycle,
+    Nanosecond,
+    Picosecond,
+    Femtosecond,
+}
+
+impl Timescale {
+    /// None for `Cycle`, which is not tied to wall time
+    pub fn ticks_per_second(&self) -> Option<u128> {
+        match self {
+            Timescale::Cycle => None,
+            Timescale::Nanosecond => Some(1_000_000_000),
+            Timescale::Picosecond => Some(1_000_000_000_000),
+            Timescale::Femtosecond => Some(1_000_000_000_000_000),
+        }
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A clock with a rational period of `period_num / period_den` ticks.
+///
+/// Edge `k` is at `k * period_num / period_den` rounded down, so periods that are not a whole
+/// number of ticks, e.g. 3 GHz in picoseconds, do not drift over time.
+/// The first edge is one period after time 0.
+#[derive(Debug)]
+pub struct Clock {
+    period_num: u128,
+    period_den: u128,
+    edge_count: u128,
+    /// (registration order, clock tick handler)
+    pub(crate) outputs: Vec<(u64, Output)>,
+}
+
+impl Clock {
+    pub fn from_period(period: SimTime) -> Self {
+        assert!(period > 0, "clock period has to be at least one tick");
+        Clock {
+            period_num: period,
+            period_den: 1,
+            edge_count: 0,
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn from_frequency(timescale: Timescale, frequency_hz: u128) -> Self {
+        let ticks_per_second = timescale
+            .ticks_per_second()
+            .expect("clocks defined by frequency need a timescale in seconds");
+        assert!(
+            frequency_hz > 0 && frequency_hz <= ticks_per_second,
+            "clock period has to be at least one tick"
+        );
+        let divisor = gcd(ticks_per_second, frequency_hz);
+        Clock {
+            period_num: ticks_per_second / divisor,
+            period_den: frequency_hz / divisor,
+            edge_count: 0,
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn edge_time(&self, edge: u128) -> SimTime {
+        edge * self.period_num / self.period_den
+    }
+
+    pub fn next_edge_time(&self) -> SimTime {
+        self.edge_time(self.edge_count + 1)
+    }
+
+    /// Number of edges so far
+    pub fn get_edge_count(&self) -> u128 {
+        self.edge_count
+    }
+
+    pub(crate) fn advance(&mut self) {
+        self.edge_count += 1;
+    }
+}
@@ -1,5 +1,5 @@
 use crate::event::Event;
-use crate::types::{Cycle, EventId};
+use crate::types::{EventId, SimTime};
 use std::any::Any;
 
 /// `NullEvent` is used in the case without an actual event payload.
@@ -32,7 +32,7 @@ impl Event for ClockEvent {
         self.scheduled_time
     }
 
-    fn set_scheduled_time(&mut self, scheduled_time: Cycle) {
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
         self.scheduled_time = scheduled_time
     }
 
@@ -15,5 +15,11 @@ pub trait Component: Send + Sync {
 
     fn get_component_id(&self) -> ComponentId;
 
+    /// Name of the component's type, used to tag and filter log messages
+    fn get_component_name(&self) -> &'static str;
+
     fn as_any(&self) -> &dyn Any;
+
+    /// Returns the current value of the input port named `port_name`, if there is one.
+    fn get_port(&self, port_name: &str) -> Option<&dyn Any>;
 }
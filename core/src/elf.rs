@@ -0,0 +1,229 @@
+//! A loader for little endian ELF32 RISC-V executables, enough for bare metal programs:
+//! the PT_LOAD segments, the entry point and the symbol table.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+const MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const CLASS_32: u8 = 1;
+const DATA_LE: u8 = 1;
+const MACHINE_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const STT_FUNC: u8 = 2;
+const STT_SECTION: u8 = 3;
+const STT_FILE: u8 = 4;
+const SHN_UNDEF: u16 = 0;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ElfError {
+    NotElf,
+    /// Only ELF32 little endian RISC-V is supported
+    Unsupported,
+    /// A header, segment or section points past the end of the file
+    Truncated,
+    /// A PT_LOAD segment smaller in memory than in the file, or running past the end of the address space
+    BadSegment,
+}
+
+impl Display for ElfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElfError::NotElf => write!(f, "not an ELF file"),
+            ElfError::Unsupported => write!(f, "not a little endian ELF32 RISC-V file"),
+            ElfError::Truncated => write!(f, "truncated ELF file"),
+            ElfError::BadSegment => write!(f, "a segment does not fit in the address space"),
+        }
+    }
+}
+
+impl Error for ElfError {}
+
+/// A PT_LOAD segment, the bytes from the file and then `zeros` zero bytes for `.bss`
+/// and anything else past the file size
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub addr: u32,
+    pub data: Vec<u8>,
+    pub zeros: u32,
+}
+
+impl Segment {
+    /// The size in memory, `p_memsz`
+    pub fn mem_size(&self) -> u32 {
+        self.data.len() as u32 + self.zeros
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u32,
+    pub size: u32,
+    pub is_function: bool,
+}
+
+/// Symbols by name, e.g. `tohost` and `begin_signature`, and functions by address
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    /// The first one wins when a name is defined twice
+    pub fn insert(&mut self, symbol: Symbol) {
+        if !self.by_name.contains_key(&symbol.name) {
+            self.by_name.insert(symbol.name.clone(), self.symbols.len());
+            self.symbols.push(symbol);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&idx| &self.symbols[idx])
+    }
+
+    /// Address of a symbol
+    pub fn lookup(&self, name: &str) -> Option<u32> {
+        self.get(name).map(|symbol| symbol.addr)
+    }
+
+    /// The function `addr` is in
+    pub fn function_at(&self, addr: u32) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| {
+            symbol.is_function && addr >= symbol.addr && (addr - symbol.addr) < symbol.size.max(1)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Elf {
+    pub entry: u32,
+    pub segments: Vec<Segment>,
+    pub symbols: SymbolTable,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ElfError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ElfError::Truncated)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ElfError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ElfError::Truncated)
+}
+
+fn slice(data: &[u8], offset: u32, size: u32) -> Result<&[u8], ElfError> {
+    let start = offset as usize;
+    data.get(start..start + size as usize)
+        .ok_or(ElfError::Truncated)
+}
+
+/// The NUL terminated string at `offset` of a string table
+fn read_str(strtab: &[u8], offset: u32) -> Result<String, ElfError> {
+    let bytes = strtab.get(offset as usize..).ok_or(ElfError::Truncated)?;
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or(ElfError::Truncated)?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+impl Elf {
+    pub fn is_elf(data: &[u8]) -> bool {
+        data.starts_with(&MAGIC)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Elf, ElfError> {
+        if !Self::is_elf(data) {
+            return Err(ElfError::NotElf);
+        }
+        if data.len() < 52 {
+            return Err(ElfError::Truncated);
+        }
+        if data[4] != CLASS_32 || data[5] != DATA_LE || read_u16(data, 18)? != MACHINE_RISCV {
+            return Err(ElfError::Unsupported);
+        }
+        let entry = read_u32(data, 24)?;
+        let phoff = read_u32(data, 28)? as usize;
+        let shoff = read_u32(data, 32)? as usize;
+        let phentsize = read_u16(data, 42)? as usize;
+        let phnum = read_u16(data, 44)? as usize;
+        let shentsize = read_u16(data, 46)? as usize;
+        let shnum = read_u16(data, 48)? as usize;
+
+        let mut segments = vec![];
+        for i in 0..phnum {
+            let header = phoff + i * phentsize;
+            if read_u32(data, header)? != PT_LOAD {
+                continue;
+            }
+            let offset = read_u32(data, header + 4)?;
+            // the physical address, where a bare metal program is loaded
+            let addr = read_u32(data, header + 12)?;
+            let filesz = read_u32(data, header + 16)?;
+            let memsz = read_u32(data, header + 20)?;
+            if memsz < filesz || addr as u64 + memsz as u64 > 1 << 32 {
+                return Err(ElfError::BadSegment);
+            }
+            segments.push(Segment {
+                addr,
+                data: slice(data, offset, filesz)?.to_vec(),
+                zeros: memsz - filesz,
+            });
+        }
+
+        let mut symbols = SymbolTable::default();
+        for i in 0..shnum {
+            let header = shoff + i * shentsize;
+            if read_u32(data, header + 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let symtab = slice(
+                data,
+                read_u32(data, header + 16)?,
+                read_u32(data, header + 20)?,
+            )?;
+            let entsize = read_u32(data, header + 36)?.max(16) as usize;
+            // sh_link is the section of the names
+            let strtab_header = shoff + read_u32(data, header + 24)? as usize * shentsize;
+            let strtab = slice(
+                data,
+                read_u32(data, strtab_header + 16)?,
+                read_u32(data, strtab_header + 20)?,
+            )?;
+            for symbol in symtab.chunks_exact(entsize) {
+                let kind = symbol[12] & 0xF;
+                if read_u16(symbol, 14)? == SHN_UNDEF || kind == STT_SECTION || kind == STT_FILE {
+                    continue;
+                }
+                let name = read_str(strtab, read_u32(symbol, 0)?)?;
+                if name.is_empty() {
+                    continue;
+                }
+                symbols.insert(Symbol {
+                    name,
+                    addr: read_u32(symbol, 4)?,
+                    size: read_u32(symbol, 8)?,
+                    is_function: kind == STT_FUNC,
+                });
+            }
+        }
+
+        Ok(Elf {
+            entry,
+            segments,
+            symbols,
+        })
+    }
+}
@@ -1,6 +1,7 @@
-use crate::types::{Cycle, EventId};
+use crate::types::{EventId, SimTime};
 use std::any::Any;
 use std::fmt::Debug;
+use std::hash::Hasher;
 use std::ops::Deref;
 
 pub trait Event: Send + Sync + Debug {
@@ -8,9 +9,12 @@ pub trait Event: Send + Sync + Debug {
         false
     }
     fn get_event_id(&self) -> EventId;
-    fn get_scheduled_time(&self) -> Cycle;
-    fn set_scheduled_time(&mut self, scheduled_time: Cycle);
+    fn get_scheduled_time(&self) -> SimTime;
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime);
     fn get_inner(&self) -> Box<dyn Any>;
+    /// Feeds the payload into the trace hash, see `SimManager::enable_trace_hash`.
+    /// Events that do not implement this only contribute their timing and source.
+    fn hash_payload(&self, _state: &mut dyn Hasher) {}
 }
 
 pub fn get_inner<T: Copy + 'static>(event: &dyn Event) -> T {
@@ -0,0 +1,80 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Mutex;
+
+pub type HookId = usize;
+
+/// Points in the simulation a hook can be attached to
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum HookPoint {
+    /// Before the clock edge of the cycle is processed
+    CycleStart,
+    /// Once every event of the cycle has been acked
+    CycleEnd,
+    /// Fired by models that have a notion of instructions, once per retired instruction
+    InstructionRetired,
+    /// The sim is done, fired once
+    SimEnd,
+}
+
+type Hook<C> = Box<dyn FnMut(&C) + Send>;
+
+/// Callbacks run at fixed points of the simulation, with read access to `C`.
+///
+/// This is where monitors, loggers and the like plug in, so the owner of the registry does not have to
+/// know about them. Hooks run in the order they were added, on the thread driving the sim.
+/// A hook must not add or remove hooks of the registry it is running from.
+pub struct HookRegistry<C: ?Sized> {
+    hooks: Mutex<Vec<(HookId, HookPoint, Hook<C>)>>,
+    next_hook_id: Mutex<HookId>,
+}
+
+impl<C: ?Sized> Default for HookRegistry<C> {
+    fn default() -> Self {
+        HookRegistry {
+            hooks: Mutex::new(Vec::new()),
+            next_hook_id: Mutex::new(0),
+        }
+    }
+}
+
+impl<C: ?Sized> Debug for HookRegistry<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let hooks = self.hooks.lock().unwrap();
+        let points: Vec<_> = hooks.iter().map(|(_, point, _)| point).collect();
+        write!(f, "HookRegistry {{hooks: {:?}}}", points)
+    }
+}
+
+impl<C: ?Sized> HookRegistry<C> {
+    pub fn add(&self, point: HookPoint, hook: impl FnMut(&C) + Send + 'static) -> HookId {
+        let mut next_hook_id = self.next_hook_id.lock().unwrap();
+        let hook_id = *next_hook_id;
+        *next_hook_id += 1;
+        self.hooks
+            .lock()
+            .unwrap()
+            .push((hook_id, point, Box::new(hook)));
+        hook_id
+    }
+
+    /// Returns false if there is no hook with that id
+    pub fn remove(&self, hook_id: HookId) -> bool {
+        let mut hooks = self.hooks.lock().unwrap();
+        let len = hooks.len();
+        hooks.retain(|(id, _, _)| *id != hook_id);
+        hooks.len() != len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.lock().unwrap().is_empty()
+    }
+
+    /// Runs every hook attached to `point`
+    pub fn run(&self, point: HookPoint, context: &C) {
+        for (_, hook_point, hook) in self.hooks.lock().unwrap().iter_mut() {
+            if *hook_point == point {
+                hook(context);
+            }
+        }
+    }
+}
@@ -1,10 +1,18 @@
+pub mod bus;
+pub mod clock;
 pub mod clock_event;
 pub mod component;
+pub mod elf;
 pub mod error;
 pub mod event;
+pub mod hooks;
+pub mod logging;
 pub mod macros;
+pub mod pdes;
 pub mod sim_dispatcher;
 pub mod sim_manager;
 pub mod simple_component;
+pub mod stats;
 pub mod task;
 pub mod types;
+pub mod watchpoint;
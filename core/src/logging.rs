@@ -0,0 +1,182 @@
+use crate::types::{ComponentId, Cycle};
+use std::collections::HashMap;
+use std::fmt::{Arguments, Debug, Display, Formatter};
+use std::io::Write;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        write!(f, "{}", level)
+    }
+}
+
+/// A log message along with where in the simulation it came from
+#[derive(Debug)]
+pub struct LogRecord<'a> {
+    pub level: Level,
+    pub cycle: Option<Cycle>,
+    pub component_id: Option<ComponentId>,
+    pub component_name: &'a str,
+    pub message: Arguments<'a>,
+}
+
+impl Display for LogRecord<'_> {
+    /// `[cycle 12] INFO SimpleSender(1): message`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(cycle) = self.cycle {
+            write!(f, "[cycle {}] ", cycle)?;
+        }
+        write!(f, "{} {}", self.level, self.component_name)?;
+        if let Some(component_id) = self.component_id {
+            write!(f, "({})", component_id)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+pub trait LogSink: Send + Sync {
+    fn write(&self, record: &LogRecord);
+}
+
+/// Writes records to any writer, e.g. stdout or a file.
+/// `plain` sinks write the bare message, for output that has a format of its own such as commit logs.
+pub struct WriterSink<W: Write + Send> {
+    writer: Mutex<W>,
+    plain: bool,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        WriterSink {
+            writer: Mutex::new(writer),
+            plain: false,
+        }
+    }
+
+    pub fn plain(writer: W) -> Self {
+        WriterSink {
+            writer: Mutex::new(writer),
+            plain: true,
+        }
+    }
+}
+
+impl<W: Write + Send> LogSink for WriterSink<W> {
+    fn write(&self, record: &LogRecord) {
+        let mut writer = self.writer.lock().unwrap();
+        let _ = if self.plain {
+            writeln!(writer, "{}", record.message)
+        } else {
+            writeln!(writer, "{}", record)
+        };
+    }
+}
+
+/// Keeps the formatted records around, mostly useful for tests
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    lines: Mutex<Vec<String>>,
+}
+
+impl MemorySink {
+    pub fn take_lines(&self) -> Vec<String> {
+        std::mem::take(&mut *self.lines.lock().unwrap())
+    }
+}
+
+impl LogSink for MemorySink {
+    fn write(&self, record: &LogRecord) {
+        self.lines.lock().unwrap().push(record.to_string());
+    }
+}
+
+/// Filters records by level, per component if needed, and hands them to every sink.
+///
+/// By default everything up to `Level::Info` goes to stdout.
+pub struct Logger {
+    level: RwLock<Level>,
+    component_levels: RwLock<HashMap<String, Level>>,
+    sinks: RwLock<Vec<Arc<dyn LogSink>>>,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Logger {
+            level: RwLock::new(Level::Info),
+            component_levels: RwLock::new(HashMap::new()),
+            sinks: RwLock::new(vec![Arc::new(WriterSink::new(std::io::stdout()))]),
+        }
+    }
+}
+
+impl Debug for Logger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Logger {{level: {:?}, component_levels: {:?}, sinks: {}}}",
+            self.level.read().unwrap(),
+            self.component_levels.read().unwrap(),
+            self.sinks.read().unwrap().len()
+        )
+    }
+}
+
+impl Logger {
+    /// Most verbose level logged for components without a level of their own
+    pub fn set_level(&self, level: Level) {
+        *self.level.write().unwrap() = level;
+    }
+
+    /// Most verbose level logged for components named `component_name`
+    pub fn set_component_level(&self, component_name: &str, level: Level) {
+        self.component_levels
+            .write()
+            .unwrap()
+            .insert(component_name.to_string(), level);
+    }
+
+    /// Replaces every sink, an empty list silences the logger
+    pub fn set_sinks(&self, sinks: Vec<Arc<dyn LogSink>>) {
+        *self.sinks.write().unwrap() = sinks;
+    }
+
+    pub fn add_sink(&self, sink: Arc<dyn LogSink>) {
+        self.sinks.write().unwrap().push(sink);
+    }
+
+    pub fn enabled(&self, level: Level, component_name: &str) -> bool {
+        let max_level = self
+            .component_levels
+            .read()
+            .unwrap()
+            .get(component_name)
+            .copied()
+            .unwrap_or(*self.level.read().unwrap());
+        level <= max_level
+    }
+
+    pub fn log(&self, record: &LogRecord) {
+        if !self.enabled(record.level, record.component_name) {
+            return;
+        }
+        for sink in self.sinks.read().unwrap().iter() {
+            sink.write(record);
+        }
+    }
+}
@@ -12,10 +12,31 @@ macro_rules! enq {
     };
 }
 
+/// Logs through the sim manager's logger, tagged with the current cycle and the component
+/// e.g. `sim_log!(self, Level::Debug, "sent {}", packet_id)`
+#[macro_export]
+macro_rules! sim_log {
+    ($self:ident, $level:expr, $($arg:tt)+) => {
+        $self.sim_manager.get_logger().log(&$crate::logging::LogRecord {
+            level: $level,
+            cycle: Some($self.sim_manager.get_curr_cycle()),
+            component_id: Some($self.component_id),
+            component_name: $self.get_component_name(),
+            message: format_args!($($arg)+),
+        })
+    };
+}
+
 #[macro_export]
 macro_rules! send {
     ($self:ident, $output:expr, $event:expr) => {
-        let task = Task::new(Box::new($event), $output.clone());
+        let task = Task::with_source(
+            Box::new($event),
+            $output.clone(),
+            $self
+                .sim_manager
+                .request_task_source($self.component_id, stringify!($output)),
+        );
         enq!($self, task);
     };
 }
@@ -0,0 +1,266 @@
+use crate::component::Component;
+use crate::event::Event;
+use crate::sim_dispatcher::SimDispatcher;
+use crate::sim_manager::SimManager;
+use crate::task::Task;
+use crate::types::{ComponentId, EventId, Input, Output, SimTime};
+use crossbeam_channel::{unbounded, Sender};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub type PartitionId = usize;
+
+/// Held in the do not end set of every partition until all partitions are done,
+/// so that a partition that finishes early keeps its dispatchers around for late events.
+const PARTITION_HOLD_ID: ComponentId = ComponentId::MAX;
+
+/// Each partition gets its own event id range, ids are not shared between sim managers
+const PARTITION_EVENT_ID_SHIFT: u32 = 96;
+
+/// Forwards events from the partition it lives in to another partition, `delay` ticks later.
+///
+/// The delay is the link's lookahead: anything sent over the link at time `t` cannot be
+/// observed by the destination before `t + delay`, which is what lets the destination run ahead.
+pub struct PartitionLink {
+    component_id: ComponentId,
+    ack_sender: Sender<EventId>,
+    input_receiver: Input,
+    output: Output,
+    dest_sim_manager: Arc<SimManager>,
+    delay: SimTime,
+}
+
+impl PartitionLink {
+    fn forward(&self, mut event: Box<dyn Event>) {
+        let event_id = event.get_event_id();
+        event.set_scheduled_time(event.get_scheduled_time() + self.delay);
+        let source = self
+            .dest_sim_manager
+            .request_task_source(self.component_id, "output");
+        self.dest_sim_manager
+            .enq_event(Task::with_source(event, self.output.clone(), source));
+        // The source sim manager can only move past this time once the event is in the destination queue
+        self.ack_sender.send(event_id).unwrap();
+    }
+}
+
+impl Component for PartitionLink {
+    fn init(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn poll_recv(&mut self) {
+        while let Ok(event) = self.input_receiver.try_recv() {
+            self.forward(event);
+        }
+    }
+
+    fn get_component_id(&self) -> ComponentId {
+        self.component_id
+    }
+
+    fn get_component_name(&self) -> &'static str {
+        "PartitionLink"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_port(&self, _port_name: &str) -> Option<&dyn Any> {
+        None
+    }
+}
+
+struct Partition {
+    sim_manager: Arc<SimManager>,
+    ack_sender: Sender<EventId>,
+    components: Vec<Arc<Mutex<dyn Component>>>,
+    /// (source partition, delay) of every link going into this partition
+    incoming: Vec<(PartitionId, SimTime)>,
+    /// The last time this partition finished, it sends nothing more in it
+    finished: Mutex<Option<SimTime>>,
+}
+
+/// Conservative parallel discrete event simulation.
+///
+/// Components are split into partitions, each with its own sim manager, event queue and dispatchers.
+/// Partitions only talk through links created by `connect`, which carry a fixed delay of at least one tick.
+/// A partition may dispatch time `t` once every partition feeding it has reached `t - delay + 1`,
+/// or finished `t - delay`. The current time of a partition and whether it finished it thus double
+/// as the null message promising nothing earlier will be sent. Finishing a time needs nothing from
+/// the other partitions, so a cycle of links with a delay of one still moves a tick per round.
+#[derive(Default)]
+pub struct ParallelSim {
+    partitions: Vec<Partition>,
+    next_link_id: ComponentId,
+}
+
+impl ParallelSim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_partition(&mut self) -> PartitionId {
+        let partition_id = self.partitions.len();
+        let ack_channel = unbounded();
+        let sim_manager = SimManager::with_event_id_base(
+            ack_channel.1,
+            (partition_id as EventId) << PARTITION_EVENT_ID_SHIFT,
+        );
+        sim_manager.register_do_not_end(PARTITION_HOLD_ID);
+        self.partitions.push(Partition {
+            sim_manager,
+            ack_sender: ack_channel.0,
+            components: Vec::new(),
+            incoming: Vec::new(),
+            finished: Mutex::new(None),
+        });
+        partition_id
+    }
+
+    /// Components of the partition should be built with this sim manager
+    pub fn get_sim_manager(&self, partition_id: PartitionId) -> Arc<SimManager> {
+        self.partitions[partition_id].sim_manager.clone()
+    }
+
+    pub fn get_ack_sender(&self, partition_id: PartitionId) -> Sender<EventId> {
+        self.partitions[partition_id].ack_sender.clone()
+    }
+
+    pub fn add_component(
+        &mut self,
+        partition_id: PartitionId,
+        component: Arc<Mutex<dyn Component>>,
+    ) {
+        self.partitions[partition_id].components.push(component);
+    }
+
+    /// Creates a link from `src` to `dest` with the given delay.
+    /// The returned output goes to the sending component, the input to the receiving component.
+    pub fn connect(
+        &mut self,
+        src: PartitionId,
+        dest: PartitionId,
+        delay: SimTime,
+    ) -> (Output, Input) {
+        assert!(
+            delay > 0,
+            "links between partitions need a lookahead of at least one tick"
+        );
+        assert_ne!(
+            src, dest,
+            "components in the same partition should be connected directly"
+        );
+
+        let src_channel = unbounded();
+        let dest_channel = unbounded();
+        let link = PartitionLink {
+            component_id: PARTITION_HOLD_ID - 1 - self.next_link_id,
+            ack_sender: self.partitions[src].ack_sender.clone(),
+            input_receiver: src_channel.1,
+            output: dest_channel.0,
+            dest_sim_manager: self.partitions[dest].sim_manager.clone(),
+            delay,
+        };
+        self.next_link_id += 1;
+
+        self.partitions[src]
+            .components
+            .push(Arc::new(Mutex::new(link)));
+        self.partitions[dest].incoming.push((src, delay));
+        (src_channel.0, dest_channel.1)
+    }
+
+    /// First time `partition_id` may not run yet, the sources can still send events due at it.
+    /// A source at `t` may still be sending in `t`, which lands at `t + delay`, unless it finished `t`.
+    /// The current time is read first, a source that moved on since is only further ahead
+    fn horizon(partitions: &[Partition], partition_id: PartitionId) -> Option<SimTime> {
+        partitions[partition_id]
+            .incoming
+            .iter()
+            .map(|(src, delay)| {
+                let src = &partitions[*src];
+                let curr_time = src.sim_manager.get_curr_time();
+                let finished = *src.finished.lock().unwrap() == Some(curr_time);
+                curr_time + finished as SimTime + delay
+            })
+            .min()
+    }
+
+    fn all_done(partitions: &[Partition]) -> bool {
+        partitions
+            .iter()
+            .all(|partition| partition.sim_manager.sim_can_end_except(PARTITION_HOLD_ID))
+    }
+
+    /// Runs every partition on its own thread, plus `threads_per_partition` dispatcher threads each,
+    /// until all partitions can end.
+    pub fn run(&self, threads_per_partition: usize) {
+        let done = AtomicBool::new(false);
+
+        // Everything is initialized before any partition starts checking whether the others are done
+        let sim_dispatchers: Vec<_> = self
+            .partitions
+            .iter()
+            .flat_map(|partition| {
+                let chunk_size = partition
+                    .components
+                    .len()
+                    .div_ceil(threads_per_partition.max(1))
+                    .max(1);
+                partition
+                    .components
+                    .chunks(chunk_size)
+                    .map(|components| {
+                        SimDispatcher::new(
+                            Arc::downgrade(&partition.sim_manager),
+                            components.to_vec(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        sim_dispatchers.iter().for_each(|s| s.init());
+
+        thread::scope(|scope| {
+            for sim_dispatcher in sim_dispatchers {
+                scope.spawn(move || sim_dispatcher.run());
+            }
+
+            for (partition_id, partition) in self.partitions.iter().enumerate() {
+                let partitions = &self.partitions;
+                let done = &done;
+                scope.spawn(move || {
+                    let sim_manager = &partition.sim_manager;
+                    while !done.load(Ordering::Acquire) {
+                        // Always safe, nothing can arrive below the horizon any more
+                        let _ = sim_manager.finish_time_step();
+                        *partition.finished.lock().unwrap() = Some(sim_manager.get_curr_time());
+
+                        match Self::horizon(partitions, partition_id) {
+                            None => {
+                                let _ = sim_manager.run_step();
+                            }
+                            // Strictly below the horizon, `run_step_until` dispatches the limit itself
+                            Some(horizon) if sim_manager.get_curr_time() + 1 < horizon => {
+                                let _ = sim_manager.run_step_until(horizon - 1);
+                            }
+                            _ => thread::yield_now(),
+                        }
+
+                        if sim_manager.sim_can_end_except(PARTITION_HOLD_ID)
+                            && Self::all_done(partitions)
+                        {
+                            done.store(true, Ordering::Release);
+                        }
+                    }
+                    // Lets the dispatchers of this partition exit
+                    sim_manager.register_can_end(PARTITION_HOLD_ID);
+                });
+            }
+        });
+    }
+}
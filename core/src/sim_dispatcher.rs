@@ -29,10 +29,14 @@ impl SimDispatcher {
 
     pub fn run(self: &Arc<Self>) {
         loop {
-            for component in self.components.iter() {
-                component.lock().unwrap().poll_recv()
+            let sim_manager = self.sim_manager.upgrade().unwrap();
+            {
+                let _delivery = sim_manager.hold_delivery();
+                for component in self.components.iter() {
+                    component.lock().unwrap().poll_recv()
+                }
             }
-            if self.sim_manager.upgrade().unwrap().sim_can_end() {
+            if sim_manager.sim_can_end() {
                 break;
             }
         }
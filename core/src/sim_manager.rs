@@ -1,37 +1,112 @@
+use crate::clock::{Clock, ClockId, Timescale, DEFAULT_CLOCK};
 use crate::clock_event::ClockEvent;
+use crate::component::Component;
 use crate::error::SimError;
 use crate::event::Event;
-use crate::task::Task;
+use crate::hooks::{HookPoint, HookRegistry};
+use crate::logging::Logger;
+use crate::stats::{StatsRegistry, StatsReport};
+use crate::task::{Task, TaskSource};
 use crate::types::Output;
-use crate::types::{ComponentId, Cycle, EventId};
+use crate::types::{ComponentId, Cycle, EventId, SimTime};
+use crate::watchpoint::{WatchCondition, Watchpoint, WatchpointHit, WatchpointId};
 use crossbeam_channel::{Receiver, Sender};
 use std::collections::binary_heap::BinaryHeap;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+
+/// Source of the clock tick tasks, the seq is the order in which the clocks got registered
+const CLOCK_SOURCE_ID: ComponentId = ComponentId::MAX;
 
 #[derive(Debug)]
 pub struct SimManager {
-    curr_cycle: Mutex<Cycle>,
+    timescale: Timescale,
+    curr_time: Mutex<SimTime>,
     event_q: Mutex<BinaryHeap<Task>>,
-    clock_tick_q: Mutex<Vec<Output>>,
+    clocks: Mutex<Vec<Clock>>,
+    // clock domain of the components not on the default clock, see `assign_clock`
+    component_clocks: Mutex<HashMap<ComponentId, ClockId>>,
+    next_clock_tick_seq: Mutex<u64>,
     rob: Mutex<HashSet<EventId>>,
     next_event_id: Mutex<EventId>,
     ack_recv: Receiver<EventId>,
     component_do_not_end_set: Mutex<HashSet<ComponentId>>,
     event_processed: Mutex<u128>,
+    watchpoints: Mutex<Vec<Watchpoint>>,
+    next_watchpoint_id: Mutex<WatchpointId>,
+    watchpoint_hits: Mutex<Vec<WatchpointHit>>,
+    // lets `check_watchpoints` skip locking when nothing is being watched
+    has_watchpoints: AtomicBool,
+    paused: AtomicBool,
+    next_task_seq: Mutex<HashMap<ComponentId, u64>>,
+    // taken for write while a batch of events is being sent, see `hold_delivery`
+    delivery_gate: RwLock<()>,
+    trace_hasher: Mutex<Option<DefaultHasher>>,
+    stats: StatsRegistry,
+    logger: Logger,
+    hooks: HookRegistry<SimManager>,
+    sim_end_fired: AtomicBool,
 }
 
 impl SimManager {
     pub fn new(ack_recv: Receiver<EventId>) -> Arc<Self> {
+        Self::build(ack_recv, 0, Timescale::Cycle, Clock::from_period(1))
+    }
+
+    /// Event ids are handed out starting from `event_id_base`.
+    /// Sim managers that forward events to each other need disjoint id ranges, see `crate::pdes`.
+    pub fn with_event_id_base(ack_recv: Receiver<EventId>, event_id_base: EventId) -> Arc<Self> {
+        Self::build(
+            ack_recv,
+            event_id_base,
+            Timescale::Cycle,
+            Clock::from_period(1),
+        )
+    }
+
+    /// Time is kept in units of `timescale`, `default_clock` is the clock `get_curr_cycle` counts.
+    /// e.g. `with_timescale(ack_recv, Timescale::Picosecond, Clock::from_frequency(Timescale::Picosecond, 1_000_000_000))`
+    pub fn with_timescale(
+        ack_recv: Receiver<EventId>,
+        timescale: Timescale,
+        default_clock: Clock,
+    ) -> Arc<Self> {
+        Self::build(ack_recv, 0, timescale, default_clock)
+    }
+
+    fn build(
+        ack_recv: Receiver<EventId>,
+        event_id_base: EventId,
+        timescale: Timescale,
+        default_clock: Clock,
+    ) -> Arc<Self> {
         Arc::new(SimManager {
-            curr_cycle: Mutex::new(0),
+            timescale,
+            curr_time: Mutex::new(0),
             event_q: Mutex::new(BinaryHeap::new()),
-            clock_tick_q: Mutex::new(Vec::new()),
+            clocks: Mutex::new(vec![default_clock]),
+            component_clocks: Mutex::new(HashMap::new()),
+            next_clock_tick_seq: Mutex::new(0),
             rob: Mutex::new(HashSet::new()),
-            next_event_id: Mutex::new(0),
+            next_event_id: Mutex::new(event_id_base),
             ack_recv,
             component_do_not_end_set: Mutex::new(HashSet::new()),
             event_processed: Mutex::new(0),
+            watchpoints: Mutex::new(Vec::new()),
+            next_watchpoint_id: Mutex::new(0),
+            watchpoint_hits: Mutex::new(Vec::new()),
+            has_watchpoints: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            next_task_seq: Mutex::new(HashMap::new()),
+            delivery_gate: RwLock::new(()),
+            trace_hasher: Mutex::new(None),
+            stats: StatsRegistry::default(),
+            logger: Logger::default(),
+            hooks: HookRegistry::default(),
+            sim_end_fired: AtomicBool::new(false),
         })
     }
 
@@ -39,15 +114,39 @@ impl SimManager {
         let _ = self.event_q.lock().map(|mut event_q| event_q.push(event));
     }
 
+    pub fn get_timescale(&self) -> Timescale {
+        self.timescale
+    }
+
+    /// Current time in units of the timescale, this is what events should be scheduled against
+    pub fn get_curr_time(&self) -> SimTime {
+        *self.curr_time.lock().unwrap()
+    }
+
+    /// Number of default clock edges so far, the same as `get_curr_time` with the `Cycle` timescale
     pub fn get_curr_cycle(&self) -> Cycle {
-        *self.curr_cycle.lock().unwrap()
+        self.clocks.lock().unwrap()[DEFAULT_CLOCK].get_edge_count()
     }
 
-    fn increment_cycle(&self) {
-        let _ = self
-            .curr_cycle
+    /// Time of the `cycles`th default clock edge
+    pub fn cycles_to_time(&self, cycles: Cycle) -> SimTime {
+        self.clocks.lock().unwrap()[DEFAULT_CLOCK].edge_time(cycles)
+    }
+
+    /// Adds a clock domain, components are put on it with `assign_clock`
+    pub fn add_clock(&self, clock: Clock) -> ClockId {
+        let mut clocks = self.clocks.lock().unwrap();
+        clocks.push(clock);
+        clocks.len() - 1
+    }
+
+    /// Moves the clock tick handler of `component_id` onto `clock_id`.
+    /// Needs to be called before the component's `init`.
+    pub fn assign_clock(&self, component_id: ComponentId, clock_id: ClockId) {
+        self.component_clocks
             .lock()
-            .map(|mut curr_cycle| *curr_cycle += 1);
+            .unwrap()
+            .insert(component_id, clock_id);
     }
 
     pub fn request_new_event_id(&self) -> EventId {
@@ -57,8 +156,63 @@ impl SimManager {
         ret
     }
 
+    /// Tags a task sent by `component_id` through `port`, used by `send!`
+    pub fn request_task_source(&self, component_id: ComponentId, port: &'static str) -> TaskSource {
+        let mut next_task_seq = self.next_task_seq.lock().unwrap();
+        let seq = next_task_seq.entry(component_id).or_insert(0);
+        let ret = TaskSource {
+            component_id,
+            port,
+            seq: *seq,
+        };
+        *seq += 1;
+        ret
+    }
+
+    /// Dispatchers poll their components while holding this.
+    /// Since events of the same batch are sent while the gate is held for write,
+    /// a component sees either none or all of the batch, no matter how threads get scheduled.
+    pub fn hold_delivery(&self) -> RwLockReadGuard<'_, ()> {
+        self.delivery_gate.read().unwrap()
+    }
+
+    /// Starts hashing every event sent from now on, in send order.
+    /// Two runs of the same model should end up with the same hash regardless of the thread count.
+    pub fn enable_trace_hash(&self) {
+        *self.trace_hasher.lock().unwrap() = Some(DefaultHasher::new());
+    }
+
+    pub fn get_trace_hash(&self) -> Option<u64> {
+        self.trace_hasher
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|hasher| hasher.finish())
+    }
+
+    /// Registers a clock tick handler on the default clock
     pub fn register_clock_tick(&self, sender: Output) {
-        self.clock_tick_q.lock().unwrap().push(sender)
+        self.register_clock_tick_on(DEFAULT_CLOCK, sender)
+    }
+
+    pub fn register_clock_tick_on(&self, clock_id: ClockId, sender: Output) {
+        let mut next_clock_tick_seq = self.next_clock_tick_seq.lock().unwrap();
+        self.clocks.lock().unwrap()[clock_id]
+            .outputs
+            .push((*next_clock_tick_seq, sender));
+        *next_clock_tick_seq += 1;
+    }
+
+    /// Registers the clock tick handler of `component_id` on the clock it got assigned to
+    pub fn register_component_clock_tick(&self, component_id: ComponentId, sender: Output) {
+        let clock_id = self
+            .component_clocks
+            .lock()
+            .unwrap()
+            .get(&component_id)
+            .copied()
+            .unwrap_or(DEFAULT_CLOCK);
+        self.register_clock_tick_on(clock_id, sender)
     }
 
     pub fn register_do_not_end(&self, component_id: ComponentId) {
@@ -82,6 +236,14 @@ impl SimManager {
             .unwrap_or(false)
     }
 
+    /// Same as `sim_can_end`, but ignores `component_id` holding the sim
+    pub fn sim_can_end_except(&self, component_id: ComponentId) -> bool {
+        self.component_do_not_end_set
+            .lock()
+            .map(|set| set.iter().all(|id| *id == component_id))
+            .unwrap_or(false)
+    }
+
     fn recv_ack(&self) {
         while let Ok(ack_id) = self.ack_recv.try_recv() {
             if let Ok(mut rob) = self.rob.lock() {
@@ -97,14 +259,53 @@ impl SimManager {
         *self.event_processed.lock().unwrap()
     }
 
+    /// Components register their counters, averages and histograms here
+    pub fn get_stats(&self) -> &StatsRegistry {
+        &self.stats
+    }
+
+    /// Log level and sinks are configured here, components log through `sim_log!`
+    pub fn get_logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Cycle hooks fire from `run_cycle`, the sim end hook from `run`
+    pub fn get_hooks(&self) -> &HookRegistry<SimManager> {
+        &self.hooks
+    }
+
+    /// Simulator totals plus every registered stat
+    pub fn stats_report(&self) -> StatsReport {
+        StatsReport::new(
+            &self.stats,
+            self.get_curr_cycle(),
+            self.get_curr_time(),
+            self.timescale,
+            self.get_event_processed(),
+        )
+    }
+
+    /// Events are sent in batches, a batch only goes out once the previous one has been fully acked.
+    /// Whatever the components send in reaction to a batch ends up in the next one,
+    /// this keeps the send order down to the task ordering, see `crate::task::Task`.
     fn send_events(&self) {
+        if !self.rob.lock().unwrap().is_empty() {
+            return;
+        }
+        let _delivery = self.delivery_gate.write().unwrap();
+        let mut trace_hasher = self.trace_hasher.lock().unwrap();
         let _ = self.event_q.lock().map(|mut locked_event_q| {
             while let Some(task) = locked_event_q.peek() {
-                if task.event.get_scheduled_time() <= self.get_curr_cycle() {
-                    if task.event.get_scheduled_time() < self.get_curr_cycle() {
+                if task.event.get_scheduled_time() <= self.get_curr_time() {
+                    if task.event.get_scheduled_time() < self.get_curr_time() {
                         panic!("Time fault detected!");
                     }
                     if let Some(task) = locked_event_q.pop() {
+                        if let Some(hasher) = trace_hasher.as_mut() {
+                            task.event.get_scheduled_time().hash(hasher);
+                            task.source.hash(hasher);
+                            task.event.hash_payload(hasher);
+                        }
                         let _ = self
                             .rob
                             .lock()
@@ -118,20 +319,51 @@ impl SimManager {
         });
     }
 
+    /// Sends a tick to every clock with an edge at the current time
     fn schedule_clock_tasks(&self) {
-        if let Ok(clock_tick_q) = self.clock_tick_q.lock() {
-            for clock_tick_task in clock_tick_q.iter() {
-                let clock_event =
-                    ClockEvent::new(self.get_curr_cycle(), self.request_new_event_id());
-                self.event_q
-                    .lock()
-                    .unwrap()
-                    .push(Task::new(Box::new(clock_event), clock_tick_task.clone()));
+        let curr_time = self.get_curr_time();
+        if let Ok(mut clocks) = self.clocks.lock() {
+            for clock in clocks.iter_mut() {
+                if clock.next_edge_time() != curr_time {
+                    continue;
+                }
+                clock.advance();
+                for (seq, clock_tick_task) in clock.outputs.iter() {
+                    let clock_event = ClockEvent::new(curr_time, self.request_new_event_id());
+                    let source = TaskSource {
+                        component_id: CLOCK_SOURCE_ID,
+                        port: "clock",
+                        seq: *seq,
+                    };
+                    self.event_q.lock().unwrap().push(Task::with_source(
+                        Box::new(clock_event),
+                        clock_tick_task.clone(),
+                        source,
+                    ));
+                }
             }
         }
     }
 
-    fn can_increase_cycle(&self) -> Result<bool, SimError> {
+    /// Next clock edge or event, whichever comes first
+    fn next_time(&self) -> SimTime {
+        let next_edge = self
+            .clocks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|clock| clock.next_edge_time())
+            .min()
+            .unwrap();
+        self.event_q
+            .lock()
+            .unwrap()
+            .peek()
+            .map(|task| task.event.get_scheduled_time().min(next_edge))
+            .unwrap_or(next_edge)
+    }
+
+    fn can_increase_time(&self) -> Result<bool, SimError> {
         Ok(self.rob.lock()?.is_empty()
             && (self.event_q.lock()?.is_empty()
                 || self
@@ -141,48 +373,143 @@ impl SimManager {
                     .ok_or(SimError::SimManagerError)?
                     .event
                     .get_scheduled_time()
-                    > self.get_curr_cycle()))
+                    > self.get_curr_time()))
     }
 
     /// For testing comb logics, I don't see what else this is useful for
     pub fn run_cycle_end(&self) -> Result<(), SimError> {
+        self.finish_time_step()
+    }
+
+    /// Dispatches what is left of the current time, including what the components send in reaction,
+    /// without moving on. Nothing is sent at the current time afterwards
+    pub fn finish_time_step(&self) -> Result<(), SimError> {
         loop {
             self.recv_ack();
             self.send_events();
 
-            if self.can_increase_cycle()? {
+            if self.can_increase_time()? {
                 return Ok(());
             }
         }
     }
 
-    pub fn run_cycle(&self) -> Result<(), SimError> {
-        loop {
+    /// Finishes the current time step and moves on to the next clock edge or event, but not past `limit`
+    fn step(&self, limit: Option<SimTime>) -> Result<(), SimError> {
+        self.finish_time_step()?;
+
+        // Time to move on
+        let next_time = limit.map_or(self.next_time(), |limit| self.next_time().min(limit));
+        *self.curr_time.lock()? = next_time;
+        self.schedule_clock_tasks();
+        self.send_events();
+        while !self.rob.lock().unwrap().is_empty() && !self.sim_can_end() {
+            // !self.sim_can_end() is needed, not sure why
             self.recv_ack();
-            self.send_events();
+        }
+        Ok(())
+    }
 
-            // Time to move on to the next cycle
-            if self.can_increase_cycle()? {
-                self.increment_cycle();
-                self.schedule_clock_tasks();
-                self.send_events();
-                while !self.rob.lock().unwrap().is_empty() && !self.sim_can_end() {
-                    // !self.sim_can_end() is needed, not sure why
-                    self.recv_ack();
-                }
-                return Ok(());
-            }
+    /// Runs up to the next point in time with something scheduled
+    pub fn run_step(&self) -> Result<(), SimError> {
+        self.step(None)
+    }
+
+    /// Same as `run_step`, but stops at `limit` even if nothing is scheduled there
+    pub fn run_step_until(&self, limit: SimTime) -> Result<(), SimError> {
+        self.step(Some(limit))
+    }
+
+    /// Runs up to and including the next edge of the default clock
+    pub fn run_cycle(&self) -> Result<(), SimError> {
+        self.hooks.run(HookPoint::CycleStart, self);
+        let next_edge = self.cycles_to_time(self.get_curr_cycle() + 1);
+        while self.get_curr_time() < next_edge {
+            self.step(None)?;
         }
+        self.hooks.run(HookPoint::CycleEnd, self);
+        Ok(())
     }
 
+    /// Runs until the sim can end, or until a watchpoint pauses the sim.
+    /// Calling `run` again after `resume` picks up from the next cycle.
     pub fn run(&self) {
         loop {
             let _ = self.run_cycle();
 
-            if self.sim_can_end() {
+            if self.sim_can_end() || self.is_paused() {
                 break;
             }
         }
+
+        if self.sim_can_end() && !self.sim_end_fired.swap(true, Ordering::AcqRel) {
+            self.hooks.run(HookPoint::SimEnd, self);
+        }
+    }
+
+    /// Registers a watchpoint on the input ports of `component_id`.
+    /// The condition is evaluated every time one of the component's input ports gets updated,
+    /// a match pauses the sim at the end of the current cycle.
+    pub fn add_watchpoint(
+        &self,
+        component_id: ComponentId,
+        condition: WatchCondition,
+    ) -> WatchpointId {
+        let mut next_watchpoint_id = self.next_watchpoint_id.lock().unwrap();
+        let watchpoint_id = *next_watchpoint_id;
+        *next_watchpoint_id += 1;
+
+        let mut watchpoints = self.watchpoints.lock().unwrap();
+        watchpoints.push(Watchpoint::new(watchpoint_id, component_id, condition));
+        self.has_watchpoints.store(true, Ordering::Release);
+        watchpoint_id
+    }
+
+    pub fn remove_watchpoint(&self, watchpoint_id: WatchpointId) {
+        let mut watchpoints = self.watchpoints.lock().unwrap();
+        watchpoints.retain(|watchpoint| watchpoint.watchpoint_id != watchpoint_id);
+        self.has_watchpoints
+            .store(!watchpoints.is_empty(), Ordering::Release);
+    }
+
+    /// Called by the generated `poll_recv` after an input port has been updated, prior to acking the event
+    pub fn check_watchpoints(&self, component: &dyn Component, port_name: &str, event_id: EventId) {
+        if !self.has_watchpoints.load(Ordering::Acquire) {
+            return;
+        }
+
+        let component_id = component.get_component_id();
+        let mut watchpoints = self.watchpoints.lock().unwrap();
+        for watchpoint in watchpoints
+            .iter_mut()
+            .filter(|watchpoint| watchpoint.component_id == component_id)
+        {
+            if watchpoint.evaluate(component) {
+                self.watchpoint_hits.lock().unwrap().push(WatchpointHit {
+                    watchpoint_id: watchpoint.watchpoint_id,
+                    component_id,
+                    port_name: port_name.to_string(),
+                    event_id,
+                    cycle: self.get_curr_cycle(),
+                    time: self.get_curr_time(),
+                });
+                self.paused.store(true, Ordering::Release);
+            }
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Clears the pause set by a watchpoint, the recorded hits are kept
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+    }
+
+    /// Returns and clears the watchpoint hits recorded so far
+    pub fn take_watchpoint_hits(&self) -> Vec<WatchpointHit> {
+        std::mem::take(&mut *self.watchpoint_hits.lock().unwrap())
     }
 
     pub fn proxy_event(&self, event: Box<dyn Event>, callback: Sender<Box<dyn Event>>) {
@@ -1,17 +1,18 @@
 use crate::event::Event;
-use crate::types::{Cycle, EventId};
+use crate::types::{EventId, SimTime};
 use std::any::Any;
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug)]
 pub struct SimpleEvent {
-    scheduled_time: Cycle,
+    scheduled_time: SimTime,
     event_id: EventId,
     packet_id: u128,
     is_last: bool,
 }
 
 impl SimpleEvent {
-    pub fn new(scheduled_time: Cycle, packet_id: u128, is_last: bool, event_id: EventId) -> Self {
+    pub fn new(scheduled_time: SimTime, packet_id: u128, is_last: bool, event_id: EventId) -> Self {
         SimpleEvent {
             scheduled_time,
             event_id,
@@ -26,15 +27,19 @@ impl Event for SimpleEvent {
         self.event_id
     }
 
-    fn get_scheduled_time(&self) -> Cycle {
+    fn get_scheduled_time(&self) -> SimTime {
         self.scheduled_time
     }
 
-    fn set_scheduled_time(&mut self, scheduled_time: Cycle) {
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
         self.scheduled_time = scheduled_time
     }
 
     fn get_inner(&self) -> Box<dyn Any> {
         Box::new((self.packet_id, self.is_last))
     }
+
+    fn hash_payload(&self, mut state: &mut dyn Hasher) {
+        (self.packet_id, self.is_last).hash(&mut state);
+    }
 }
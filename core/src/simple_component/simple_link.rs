@@ -56,7 +56,7 @@ impl SimpleLink {
 
     fn on_comb(&mut self) {
         let event = SimpleEvent::new(
-            self.sim_manager.get_curr_cycle(),
+            self.sim_manager.get_curr_time(),
             self.input.0,
             self.input.1,
             self.sim_manager.request_new_event_id(),
@@ -1,6 +1,6 @@
 use crate::component::Component;
 use crate::event::get_inner;
-use crate::send;
+use crate::logging::Level;
 use crate::sim_manager::SimManager;
 use crate::simple_component::simple_event::SimpleEvent;
 use crate::task::Task;
@@ -8,6 +8,7 @@ use crate::types::ComponentId;
 use crate::types::Input;
 use crate::types::{EventId, Output};
 use crate::{ack, enq};
+use crate::{send, sim_log};
 use crossbeam_channel::{unbounded, Sender};
 use rsim_macro::ComponentAttribute;
 use std::sync::{Arc, Mutex};
@@ -65,21 +66,18 @@ impl SimpleLoopback {
     fn poll_impl(&mut self) {}
 
     fn on_clock(&mut self) {
-        let recv_time = self.sim_manager.get_curr_cycle();
+        let recv_time = self.sim_manager.get_curr_time();
 
         if self.sent_count < self.num_packets {
             let is_last = self.sent_count == self.num_packets - 1;
             let event = SimpleEvent::new(
-                recv_time + 1,
+                recv_time + self.sim_manager.cycles_to_time(1),
                 self.sent_count,
                 is_last,
                 self.sim_manager.request_new_event_id(),
             );
             send!(self, self.output, event);
-            println!(
-                "SimpleLoopback sent event: {:?} @ {:?}",
-                self.sent_count, recv_time
-            );
+            sim_log!(self, Level::Debug, "sent event: {:?}", self.sent_count);
         } else {
             self.sim_manager.register_can_end(self.component_id);
         }
@@ -88,10 +86,6 @@ impl SimpleLoopback {
     }
 
     fn on_comb(&mut self) {
-        println!(
-            "SimpleLoopback received {} @ {}",
-            self.input.0,
-            self.sim_manager.get_curr_cycle()
-        );
+        sim_log!(self, Level::Debug, "received {}", self.input.0);
     }
 }
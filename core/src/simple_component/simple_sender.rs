@@ -1,6 +1,6 @@
 use crate::component::Component;
 use crate::event::Event;
-use crate::send;
+use crate::logging::Level;
 use crate::sim_manager::SimManager;
 use crate::simple_component::simple_event::SimpleEvent;
 use crate::task::Task;
@@ -8,6 +8,7 @@ use crate::types::Input;
 use crate::types::Output;
 use crate::types::{ComponentId, EventId};
 use crate::{ack, enq};
+use crate::{send, sim_log};
 use crossbeam_channel::{unbounded, Sender};
 use rsim_macro::ComponentAttribute;
 use std::sync::{Arc, Mutex};
@@ -59,21 +60,22 @@ impl SimpleSender {
     fn poll_impl(&mut self) {}
 
     fn on_clock(&mut self) {
-        let recv_time = self.sim_manager.get_curr_cycle();
+        let recv_time = self.sim_manager.get_curr_time();
 
         if self.sent_count < self.num_packets {
             let is_last = self.sent_count == self.num_packets - 1;
 
             let event = SimpleEvent::new(
-                recv_time + 10,
+                recv_time + self.sim_manager.cycles_to_time(10),
                 self.sent_count,
                 is_last,
                 self.sim_manager.request_new_event_id(),
             );
-            println!(
-                "SimpleSender sent event: {:?} @ {:?} eid {}",
+            sim_log!(
+                self,
+                Level::Debug,
+                "sent event: {:?} eid {}",
                 self.sent_count,
-                recv_time,
                 event.get_event_id()
             );
             send!(self, self.output, event);
@@ -0,0 +1,347 @@
+use crate::clock::Timescale;
+use crate::types::{Cycle, SimTime};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Handle to a named counter, cheap to clone and update from any thread
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+struct AverageData {
+    sum: f64,
+    count: u64,
+}
+
+/// Handle to a named running average
+#[derive(Debug, Clone, Default)]
+pub struct Average(Arc<Mutex<AverageData>>);
+
+impl Average {
+    pub fn sample(&self, value: f64) {
+        let mut data = self.0.lock().unwrap();
+        data.sum += value;
+        data.count += 1;
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.0.lock().unwrap().count
+    }
+
+    /// 0 if nothing has been sampled
+    pub fn get_mean(&self) -> f64 {
+        let data = self.0.lock().unwrap();
+        if data.count == 0 {
+            0.0
+        } else {
+            data.sum / data.count as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HistogramData {
+    bucket_width: u64,
+    /// The last bucket also holds everything past the end
+    buckets: Vec<u64>,
+    sum: u128,
+    count: u64,
+}
+
+/// Handle to a named histogram with fixed width buckets starting at 0
+#[derive(Debug, Clone)]
+pub struct Histogram(Arc<Mutex<HistogramData>>);
+
+impl Histogram {
+    fn new(bucket_width: u64, num_buckets: usize) -> Self {
+        assert!(bucket_width > 0 && num_buckets > 0);
+        Histogram(Arc::new(Mutex::new(HistogramData {
+            bucket_width,
+            buckets: vec![0; num_buckets],
+            sum: 0,
+            count: 0,
+        })))
+    }
+
+    pub fn sample(&self, value: u64) {
+        let mut data = self.0.lock().unwrap();
+        let bucket = ((value / data.bucket_width) as usize).min(data.buckets.len() - 1);
+        data.buckets[bucket] += 1;
+        data.sum += value as u128;
+        data.count += 1;
+    }
+
+    pub fn get_buckets(&self) -> Vec<u64> {
+        self.0.lock().unwrap().buckets.clone()
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.0.lock().unwrap().count
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Stat {
+    Counter(Counter),
+    Average(Average),
+    Histogram(Histogram),
+}
+
+/// Named statistics, grouped by the component (or any other owner) that registered them.
+///
+/// Registering the same name twice returns the same stat, so a component can look its stats up again after a reset.
+#[derive(Debug)]
+pub struct StatsRegistry {
+    stats: Mutex<BTreeMap<(String, String), Stat>>,
+    start: Mutex<Instant>,
+}
+
+impl Default for StatsRegistry {
+    fn default() -> Self {
+        StatsRegistry {
+            stats: Mutex::new(BTreeMap::new()),
+            start: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl StatsRegistry {
+    fn register(&self, owner: &str, name: &str, stat: impl FnOnce() -> Stat) -> Stat {
+        self.stats
+            .lock()
+            .unwrap()
+            .entry((owner.to_string(), name.to_string()))
+            .or_insert_with(stat)
+            .clone()
+    }
+
+    /// Panics if `name` is already registered as something other than a counter
+    pub fn counter(&self, owner: &str, name: &str) -> Counter {
+        match self.register(owner, name, || Stat::Counter(Counter::default())) {
+            Stat::Counter(counter) => counter,
+            _ => panic!("{}.{} is not a counter", owner, name),
+        }
+    }
+
+    /// Panics if `name` is already registered as something other than an average
+    pub fn average(&self, owner: &str, name: &str) -> Average {
+        match self.register(owner, name, || Stat::Average(Average::default())) {
+            Stat::Average(average) => average,
+            _ => panic!("{}.{} is not an average", owner, name),
+        }
+    }
+
+    /// Panics if `name` is already registered as something other than a histogram
+    pub fn histogram(
+        &self,
+        owner: &str,
+        name: &str,
+        bucket_width: u64,
+        num_buckets: usize,
+    ) -> Histogram {
+        match self.register(owner, name, || {
+            Stat::Histogram(Histogram::new(bucket_width, num_buckets))
+        }) {
+            Stat::Histogram(histogram) => histogram,
+            _ => panic!("{}.{} is not a histogram", owner, name),
+        }
+    }
+
+    /// Wall time is measured from the creation of the registry, or the last call to this
+    pub fn restart_wall_clock(&self) {
+        *self.start.lock().unwrap() = Instant::now();
+    }
+
+    pub fn get_wall_time(&self) -> Duration {
+        self.start.lock().unwrap().elapsed()
+    }
+
+    fn entries(&self) -> Vec<StatEntry> {
+        self.stats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((owner, name), stat)| {
+                let value = match stat {
+                    Stat::Counter(counter) => StatValue::Counter {
+                        value: counter.get(),
+                    },
+                    Stat::Average(average) => StatValue::Average {
+                        mean: average.get_mean(),
+                        count: average.get_count(),
+                    },
+                    Stat::Histogram(histogram) => {
+                        let data = histogram.0.lock().unwrap();
+                        StatValue::Histogram {
+                            bucket_width: data.bucket_width,
+                            buckets: data.buckets.clone(),
+                            mean: if data.count == 0 {
+                                0.0
+                            } else {
+                                data.sum as f64 / data.count as f64
+                            },
+                            count: data.count,
+                        }
+                    }
+                };
+                StatEntry {
+                    owner: owner.clone(),
+                    name: name.clone(),
+                    value,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatValue {
+    Counter {
+        value: u64,
+    },
+    Average {
+        mean: f64,
+        count: u64,
+    },
+    Histogram {
+        bucket_width: u64,
+        buckets: Vec<u64>,
+        mean: f64,
+        count: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatEntry {
+    pub owner: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub value: StatValue,
+}
+
+/// Simulator wide totals
+#[derive(Debug, Clone, Serialize)]
+pub struct SimTotals {
+    pub cycles: Cycle,
+    pub time: SimTime,
+    pub timescale: String,
+    pub events_processed: u128,
+    pub wall_time_seconds: f64,
+    pub events_per_second: f64,
+}
+
+/// Snapshot of the simulator totals and every registered stat, see `SimManager::stats_report`
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsReport {
+    pub sim: SimTotals,
+    pub stats: Vec<StatEntry>,
+}
+
+impl StatsReport {
+    pub fn new(
+        registry: &StatsRegistry,
+        cycles: Cycle,
+        time: SimTime,
+        timescale: Timescale,
+        events_processed: u128,
+    ) -> Self {
+        let wall_time_seconds = registry.get_wall_time().as_secs_f64();
+        StatsReport {
+            sim: SimTotals {
+                cycles,
+                time,
+                timescale: format!("{:?}", timescale),
+                events_processed,
+                wall_time_seconds,
+                events_per_second: if wall_time_seconds > 0.0 {
+                    events_processed as f64 / wall_time_seconds
+                } else {
+                    0.0
+                },
+            },
+            stats: registry.entries(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// One `owner,name,value` row per number, histogram buckets are named after their lower bound.
+    /// Owners and names are quoted as RFC 4180 wants when they need it
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("owner,name,value\n");
+        let sim = &self.sim;
+        for (name, value) in [
+            ("cycles", sim.cycles.to_string()),
+            ("time", sim.time.to_string()),
+            ("events_processed", sim.events_processed.to_string()),
+            ("wall_time_seconds", sim.wall_time_seconds.to_string()),
+            ("events_per_second", sim.events_per_second.to_string()),
+        ] {
+            csv.push_str(&format!("sim,{},{}\n", name, value));
+        }
+
+        for entry in self.stats.iter() {
+            let mut row = |name: String, value: String| {
+                csv.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&entry.owner),
+                    csv_field(&name),
+                    value
+                ))
+            };
+            match &entry.value {
+                StatValue::Counter { value } => row(entry.name.clone(), value.to_string()),
+                StatValue::Average { mean, count } => {
+                    row(format!("{}.mean", entry.name), mean.to_string());
+                    row(format!("{}.count", entry.name), count.to_string());
+                }
+                StatValue::Histogram {
+                    bucket_width,
+                    buckets,
+                    mean,
+                    count,
+                } => {
+                    row(format!("{}.mean", entry.name), mean.to_string());
+                    row(format!("{}.count", entry.name), count.to_string());
+                    for (i, bucket) in buckets.iter().enumerate() {
+                        row(
+                            format!("{}.bucket_{}", entry.name, i as u64 * bucket_width),
+                            bucket.to_string(),
+                        );
+                    }
+                }
+            }
+        }
+        csv
+    }
+}
+
+/// Quoted, with its quotes doubled, if it has a comma, a quote or a line break
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
@@ -1,11 +1,22 @@
 use crate::event::Event;
-use crate::types::Output;
+use crate::types::{ComponentId, Output};
 use std::cmp::Ordering;
 
+/// Where a task came from, used to order tasks scheduled for the same cycle.
+/// Unlike event ids, these do not depend on how components are spread across threads.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct TaskSource {
+    pub component_id: ComponentId,
+    pub port: &'static str,
+    /// Counts the tasks sent by the component so far, see `SimManager::request_task_source`
+    pub seq: u64,
+}
+
 #[derive(Debug)]
 pub struct Task {
     pub event: Box<dyn Event>,
     pub event_callback: Output,
+    pub source: Option<TaskSource>,
 }
 
 impl Task {
@@ -13,6 +24,15 @@ impl Task {
         Task {
             event,
             event_callback,
+            source: None,
+        }
+    }
+
+    pub fn with_source(event: Box<dyn Event>, event_callback: Output, source: TaskSource) -> Task {
+        Task {
+            event,
+            event_callback,
+            source: Some(source),
         }
     }
 }
@@ -31,24 +51,19 @@ impl PartialOrd<Self> for Task {
     }
 }
 
-/// While the crossbeam channels are FIFOs with stable ordering,
-/// rust's binary heap does not.
+/// Tasks are ordered by scheduled time, then by their source.
 ///
-/// This means while events from the component to the sim manager will arrive with the same order,
-/// the event q may not order them in arrival order.
-///
-/// This is combated using an event_id, originally for the rob.
-/// Since the event_id always increases from the perspective of a single component,
+/// Event ids are handed out from a counter shared by all dispatcher threads,
+/// so they only break ties between tasks without a source.
+/// Since the seq always increases from the perspective of a single component,
 /// later events will overwrite earlier events.
 impl Ord for Task {
     fn cmp(&self, other: &Self) -> Ordering {
-        if self.event.get_scheduled_time() == other.event.get_scheduled_time() {
-            other.event.get_event_id().cmp(&self.event.get_event_id())
-        } else {
-            other
-                .event
-                .get_scheduled_time()
-                .cmp(&self.event.get_scheduled_time())
-        }
+        other
+            .event
+            .get_scheduled_time()
+            .cmp(&self.event.get_scheduled_time())
+            .then_with(|| other.source.cmp(&self.source))
+            .then_with(|| other.event.get_event_id().cmp(&self.event.get_event_id()))
     }
 }
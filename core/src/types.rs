@@ -4,5 +4,7 @@ use crossbeam_channel::{Receiver, Sender};
 pub type ComponentId = u64;
 pub type EventId = u128;
 pub type Cycle = u128;
+/// Time in units of the sim manager's timescale, see `crate::clock::Timescale`
+pub type SimTime = u128;
 pub type Input = Receiver<Box<dyn Event>>;
 pub type Output = Sender<Box<dyn Event>>;
@@ -0,0 +1,92 @@
+use crate::component::Component;
+use crate::types::{ComponentId, Cycle, EventId, SimTime};
+use std::fmt::{Debug, Formatter};
+
+pub type WatchpointId = u64;
+
+/// A condition is evaluated against the component whose input port just got updated.
+/// Port values can be fetched by name through `Component::get_port`.
+pub type WatchCondition = Box<dyn Fn(&dyn Component) -> bool + Send + Sync>;
+
+pub struct Watchpoint {
+    pub watchpoint_id: WatchpointId,
+    pub component_id: ComponentId,
+    condition: WatchCondition,
+    /// Watchpoints are edge triggered, a condition that stays true does not keep pausing the sim
+    matched: bool,
+}
+
+impl Watchpoint {
+    pub fn new(
+        watchpoint_id: WatchpointId,
+        component_id: ComponentId,
+        condition: WatchCondition,
+    ) -> Self {
+        Watchpoint {
+            watchpoint_id,
+            component_id,
+            condition,
+            matched: false,
+        }
+    }
+
+    /// Returns true if the condition went from unmatched to matched
+    pub fn evaluate(&mut self, component: &dyn Component) -> bool {
+        let matched = (self.condition)(component);
+        let triggered = matched && !self.matched;
+        self.matched = matched;
+        triggered
+    }
+}
+
+impl Debug for Watchpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Watchpoint {{id: {}, component_id: {}, matched: {}}}",
+            self.watchpoint_id, self.component_id, self.matched
+        )
+    }
+}
+
+/// Records what triggered a watchpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchpointHit {
+    pub watchpoint_id: WatchpointId,
+    pub component_id: ComponentId,
+    pub port_name: String,
+    pub event_id: EventId,
+    pub cycle: Cycle,
+    pub time: SimTime,
+}
+
+/// Matches when the input port `port_name` satisfies `predicate`.
+/// Evaluates to false if the port does not exist or is not of type `T`.
+pub fn port_matches<T: 'static>(
+    port_name: &str,
+    predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+) -> WatchCondition {
+    let port_name = port_name.to_string();
+    Box::new(move |component| {
+        component
+            .get_port(&port_name)
+            .and_then(|value| value.downcast_ref::<T>())
+            .map(&predicate)
+            .unwrap_or(false)
+    })
+}
+
+/// Matches when the input port `port_name` equals `value`
+pub fn port_eq<T: PartialEq + Send + Sync + 'static>(port_name: &str, value: T) -> WatchCondition {
+    port_matches(port_name, move |port: &T| *port == value)
+}
+
+/// Matches when every condition matches
+pub fn all_of(conditions: Vec<WatchCondition>) -> WatchCondition {
+    Box::new(move |component| conditions.iter().all(|condition| condition(component)))
+}
+
+/// Matches when any condition matches
+pub fn any_of(conditions: Vec<WatchCondition>) -> WatchCondition {
+    Box::new(move |component| conditions.iter().any(|condition| condition(component)))
+}
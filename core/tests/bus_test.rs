@@ -0,0 +1,239 @@
+use crossbeam_channel::{unbounded, Sender};
+use rsim_core::bus::{Bus, BusValue, Resolution, Resolved};
+use rsim_core::component::Component;
+use rsim_core::event::{get_inner, Event};
+use rsim_core::logging::MemorySink;
+use rsim_core::sim_dispatcher::SimDispatcher;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::types::{ComponentId, EventId, Input, SimTime};
+use std::any::Any;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A single wire
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+enum Wire {
+    Z,
+    X,
+    Low,
+    High,
+}
+
+impl BusValue for Wire {
+    fn high_z() -> Self {
+        Wire::Z
+    }
+
+    fn wired_or(self, other: Self) -> Self {
+        match (self, other) {
+            (Wire::High, _) | (_, Wire::High) => Wire::High,
+            (Wire::X, _) | (_, Wire::X) => Wire::X,
+            (Wire::Low, _) | (_, Wire::Low) => Wire::Low,
+            _ => Wire::Z,
+        }
+    }
+
+    fn tri_state(self, other: Self) -> (Self, bool) {
+        match (self, other) {
+            (Wire::Z, wire) | (wire, Wire::Z) => (wire, false),
+            (Wire::Low, Wire::Low) => (Wire::Low, false),
+            (Wire::High, Wire::High) => (Wire::High, false),
+            _ => (Wire::X, true),
+        }
+    }
+
+    fn into_event(self, scheduled_time: SimTime, event_id: EventId) -> Box<dyn Event> {
+        Box::new(WireEvent {
+            scheduled_time,
+            event_id,
+            wire: self,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct WireEvent {
+    scheduled_time: SimTime,
+    event_id: EventId,
+    wire: Wire,
+}
+
+impl Event for WireEvent {
+    fn get_event_id(&self) -> EventId {
+        self.event_id
+    }
+
+    fn get_scheduled_time(&self) -> SimTime {
+        self.scheduled_time
+    }
+
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
+        self.scheduled_time = scheduled_time
+    }
+
+    fn get_inner(&self) -> Box<dyn Any> {
+        Box::new(self.wire)
+    }
+
+    fn hash_payload(&self, mut state: &mut dyn Hasher) {
+        self.wire.hash(&mut state);
+    }
+}
+
+/// Records everything the bus sends it
+struct Probe {
+    component_id: ComponentId,
+    sim_manager: Arc<SimManager>,
+    input: Input,
+    ack_sender: Sender<EventId>,
+    seen: Vec<Wire>,
+}
+
+impl Component for Probe {
+    fn init(&mut self) {
+        self.sim_manager.register_do_not_end(self.component_id);
+    }
+
+    fn reset(&mut self) {
+        self.seen.clear();
+    }
+
+    fn poll_recv(&mut self) {
+        while let Ok(event) = self.input.try_recv() {
+            self.seen.push(get_inner::<Wire>(&*event));
+            self.ack_sender.send(event.get_event_id()).unwrap();
+        }
+    }
+
+    fn get_component_id(&self) -> ComponentId {
+        self.component_id
+    }
+
+    fn get_component_name(&self) -> &'static str {
+        "Probe"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_port(&self, _port_name: &str) -> Option<&dyn Any> {
+        None
+    }
+}
+
+/// Drives `(driver, value)` one at a time on a bus with two drivers,
+/// returns what the reader saw, the number of contentions and the log
+fn run_bus(
+    resolution: Resolution<Wire>,
+    drives: &[(usize, Wire)],
+) -> (Vec<Wire>, u64, Vec<String>) {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    let sink = Arc::new(MemorySink::default());
+    sim_manager.get_logger().set_sinks(vec![sink.clone()]);
+
+    let mut bus = Bus::new(0, sim_manager.clone(), ack_channel.0.clone(), resolution);
+    let drivers = [bus.add_driver(), bus.add_driver()];
+    let probe = Arc::new(Mutex::new(Probe {
+        component_id: 1,
+        sim_manager: sim_manager.clone(),
+        input: bus.add_reader(),
+        ack_sender: ack_channel.0,
+        seen: Vec::new(),
+    }));
+    let bus = Arc::new(Mutex::new(bus));
+
+    let sim_dispatcher = SimDispatcher::new(
+        Arc::downgrade(&sim_manager),
+        vec![bus.clone(), probe.clone()],
+    );
+    sim_dispatcher.init();
+    let thread_handler = thread::spawn(move || sim_dispatcher.run());
+
+    for (driver, wire) in drives {
+        sim_manager.proxy_event(
+            wire.into_event(
+                sim_manager.get_curr_time(),
+                sim_manager.request_new_event_id(),
+            ),
+            drivers[*driver].clone(),
+        );
+        sim_manager.run_cycle().unwrap();
+    }
+    sim_manager.register_can_end(1);
+    thread_handler.join().unwrap();
+
+    let seen = probe.lock().unwrap().seen.clone();
+    let contention_count = bus.lock().unwrap().get_contention_count();
+    (seen, contention_count, sink.take_lines())
+}
+
+#[test]
+fn bus_tri_state_test() {
+    let (seen, contention_count, lines) = run_bus(
+        Resolution::TriState,
+        &[
+            (0, Wire::High),
+            (1, Wire::High),
+            (1, Wire::Low),
+            (0, Wire::Z),
+            (1, Wire::Z),
+        ],
+    );
+    assert_eq!(
+        seen,
+        vec![Wire::High, Wire::High, Wire::X, Wire::Low, Wire::Z]
+    );
+    assert_eq!(contention_count, 1);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("[cycle 2] WARN Bus(0): contention"));
+    assert!(lines[0].ends_with("drivers [High, Low] resolved to X"));
+}
+
+#[test]
+fn bus_wired_or_test() {
+    let (seen, contention_count, lines) = run_bus(
+        Resolution::WiredOr,
+        &[
+            (0, Wire::Low),
+            (1, Wire::High),
+            (1, Wire::Low),
+            (0, Wire::Z),
+        ],
+    );
+    assert_eq!(seen, vec![Wire::Low, Wire::High, Wire::Low, Wire::Low]);
+    assert_eq!(contention_count, 0);
+    assert!(lines.is_empty());
+}
+
+#[test]
+fn bus_last_writer_test() {
+    let (seen, contention_count, _) = run_bus(
+        Resolution::LastWriter,
+        &[(0, Wire::Low), (1, Wire::High), (0, Wire::X), (1, Wire::Z)],
+    );
+    assert_eq!(seen, vec![Wire::Low, Wire::High, Wire::X, Wire::Z]);
+    assert_eq!(contention_count, 0);
+}
+
+#[test]
+fn bus_custom_test() {
+    // Open drain, anyone driving low pulls the wire low, pulled up otherwise
+    let open_drain = Resolution::Custom(Arc::new(|wires: &[Wire]| Resolved {
+        value: if wires.contains(&Wire::Low) {
+            Wire::Low
+        } else {
+            Wire::High
+        },
+        contention: wires.contains(&Wire::X),
+    }));
+    let (seen, contention_count, lines) = run_bus(
+        open_drain,
+        &[(0, Wire::Z), (1, Wire::Low), (0, Wire::X), (1, Wire::Z)],
+    );
+    assert_eq!(seen, vec![Wire::High, Wire::Low, Wire::Low, Wire::High]);
+    assert_eq!(contention_count, 2);
+    assert_eq!(lines.len(), 2);
+}
@@ -0,0 +1,166 @@
+use rsim_core::elf::{Elf, ElfError};
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// A `.text` segment at 0x40000000 and a `.data` one with 8 bytes of `.bss` after it,
+/// the symbol table names `_start`, `tohost` and a section symbol that should be skipped
+fn build_elf() -> Vec<u8> {
+    let text: [u32; 2] = [0x00500093, 0x00000063];
+    let text: Vec<u8> = text.iter().flat_map(|inst| inst.to_le_bytes()).collect();
+    let data = [1u8, 2, 3, 4];
+    let strtab = b"\0_start\0tohost\0".to_vec();
+    // name, value, size, info, shndx
+    let symbols: [(u32, u32, u32, u8, u16); 4] = [
+        (0, 0, 0, 0, 0),
+        (1, 0x40000000, 8, 0x12, 1),
+        (8, 0x40001000, 4, 0x11, 2),
+        (0, 0x40001000, 0, 0x03, 2),
+    ];
+
+    let phoff = 52;
+    let text_offset = phoff + 2 * 32;
+    let data_offset = text_offset + text.len() as u32;
+    let symtab_offset = data_offset + data.len() as u32;
+    let strtab_offset = symtab_offset + 16 * symbols.len() as u32;
+    let shoff = strtab_offset + strtab.len() as u32;
+
+    let mut out = vec![0x7F, b'E', b'L', b'F', 1, 1, 1];
+    out.resize(16, 0);
+    push_u16(&mut out, 2); // ET_EXEC
+    push_u16(&mut out, 243); // RISC-V
+    push_u32(&mut out, 1);
+    push_u32(&mut out, 0x40000004); // entry
+    push_u32(&mut out, phoff);
+    push_u32(&mut out, shoff);
+    push_u32(&mut out, 0);
+    push_u16(&mut out, 52);
+    push_u16(&mut out, 32);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 40);
+    push_u16(&mut out, 3);
+    push_u16(&mut out, 0);
+
+    for (offset, addr, filesz, memsz) in [
+        (
+            text_offset,
+            0x40000000,
+            text.len() as u32,
+            text.len() as u32,
+        ),
+        (
+            data_offset,
+            0x40001000,
+            data.len() as u32,
+            data.len() as u32 + 8,
+        ),
+    ] {
+        push_u32(&mut out, 1); // PT_LOAD
+        push_u32(&mut out, offset);
+        push_u32(&mut out, addr);
+        push_u32(&mut out, addr);
+        push_u32(&mut out, filesz);
+        push_u32(&mut out, memsz);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, 4);
+    }
+    out.extend_from_slice(&text);
+    out.extend_from_slice(&data);
+    for (name, value, size, info, shndx) in symbols {
+        push_u32(&mut out, name);
+        push_u32(&mut out, value);
+        push_u32(&mut out, size);
+        out.push(info);
+        out.push(0);
+        push_u16(&mut out, shndx);
+    }
+    out.extend_from_slice(&strtab);
+
+    // null, .symtab linked to .strtab, .strtab
+    out.extend_from_slice(&[0; 40]);
+    for (kind, offset, size, link, entsize) in [
+        (2, symtab_offset, 16 * symbols.len() as u32, 2, 16),
+        (3, strtab_offset, strtab.len() as u32, 0, 0),
+    ] {
+        push_u32(&mut out, 0);
+        push_u32(&mut out, kind);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, offset);
+        push_u32(&mut out, size);
+        push_u32(&mut out, link);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, 1);
+        push_u32(&mut out, entsize);
+    }
+    out
+}
+
+#[test]
+fn elf_test() {
+    let data = build_elf();
+    assert!(Elf::is_elf(&data));
+    let elf = Elf::parse(&data).unwrap();
+    assert_eq!(elf.entry, 0x40000004);
+
+    assert_eq!(elf.segments.len(), 2);
+    assert_eq!(elf.segments[0].addr, 0x40000000);
+    assert_eq!(
+        elf.segments[0].data,
+        [0x93, 0x00, 0x50, 0x00, 0x63, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(elf.segments[0].zeros, 0);
+    // .bss is kept as a length
+    assert_eq!(elf.segments[1].addr, 0x40001000);
+    assert_eq!(elf.segments[1].data, [1, 2, 3, 4]);
+    assert_eq!(elf.segments[1].zeros, 8);
+    assert_eq!(elf.segments[1].mem_size(), 12);
+
+    assert_eq!(elf.symbols.lookup("_start"), Some(0x40000000));
+    assert_eq!(elf.symbols.lookup("tohost"), Some(0x40001000));
+    assert_eq!(elf.symbols.lookup("begin_signature"), None);
+    assert_eq!(elf.symbols.iter().count(), 2);
+    assert_eq!(
+        elf.symbols.function_at(0x40000004).map(|f| f.name.as_str()),
+        Some("_start")
+    );
+    assert!(elf.symbols.function_at(0x40000008).is_none());
+    // tohost is an object
+    assert!(elf.symbols.function_at(0x40001000).is_none());
+}
+
+#[test]
+fn elf_error_test() {
+    let data = build_elf();
+    assert_eq!(Elf::parse(&[0u8; 64]).unwrap_err(), ElfError::NotElf);
+
+    let mut elf64 = data.clone();
+    elf64[4] = 2;
+    assert_eq!(Elf::parse(&elf64).unwrap_err(), ElfError::Unsupported);
+
+    let mut x86 = data.clone();
+    x86[18] = 62;
+    assert_eq!(Elf::parse(&x86).unwrap_err(), ElfError::Unsupported);
+
+    assert_eq!(Elf::parse(&data[..100]).unwrap_err(), ElfError::Truncated);
+
+    // p_memsz of the .data segment
+    let memsz = 52 + 32 + 20;
+    let mut smaller = data.clone();
+    smaller[memsz..memsz + 4].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(Elf::parse(&smaller).unwrap_err(), ElfError::BadSegment);
+
+    let mut huge = data.clone();
+    huge[memsz..memsz + 4].copy_from_slice(&0xFFFF_0000u32.to_le_bytes());
+    assert_eq!(Elf::parse(&huge).unwrap_err(), ElfError::BadSegment);
+
+    // up to the last byte of the address space is fine
+    let mut top = data.clone();
+    top[memsz..memsz + 4].copy_from_slice(&0xBFFF_F000u32.to_le_bytes());
+    assert_eq!(Elf::parse(&top).unwrap().segments[1].zeros, 0xBFFF_EFFC);
+}
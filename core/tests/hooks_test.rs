@@ -0,0 +1,53 @@
+use crossbeam_channel::unbounded;
+use rsim_core::hooks::HookPoint;
+use rsim_core::sim_manager::SimManager;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn hooks_test() {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let hooks = sim_manager.get_hooks();
+    for point in [
+        HookPoint::CycleStart,
+        HookPoint::CycleEnd,
+        HookPoint::SimEnd,
+    ] {
+        let seen = seen.clone();
+        hooks.add(point, move |sim_manager: &SimManager| {
+            seen.lock()
+                .unwrap()
+                .push((point, sim_manager.get_curr_cycle()))
+        });
+    }
+    let removed = hooks.add(HookPoint::CycleEnd, |_| {
+        panic!("removed hooks should not run")
+    });
+    assert!(hooks.remove(removed));
+    assert!(!hooks.remove(removed));
+
+    sim_manager.register_do_not_end(0);
+    sim_manager.run_cycle().unwrap();
+    sim_manager.run_cycle().unwrap();
+    sim_manager.register_can_end(0);
+    sim_manager.run();
+    // The sim end hook only fires once
+    sim_manager.run();
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            (HookPoint::CycleStart, 0),
+            (HookPoint::CycleEnd, 1),
+            (HookPoint::CycleStart, 1),
+            (HookPoint::CycleEnd, 2),
+            (HookPoint::CycleStart, 2),
+            (HookPoint::CycleEnd, 3),
+            (HookPoint::SimEnd, 3),
+            (HookPoint::CycleStart, 3),
+            (HookPoint::CycleEnd, 4),
+        ]
+    );
+}
@@ -0,0 +1,97 @@
+use crossbeam_channel::unbounded;
+use rsim_core::logging::{Level, LogRecord, MemorySink};
+use rsim_core::sim_dispatcher::SimDispatcher;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::simple_component::simple_loopback::SimpleLoopback;
+use rsim_core::simple_component::simple_receiver::SimpleReceiver;
+use rsim_core::simple_component::simple_sender::SimpleSender;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn logging_test() {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+
+    let sink = Arc::new(MemorySink::default());
+    let logger = sim_manager.get_logger();
+    logger.set_sinks(vec![sink.clone()]);
+    // Only the sender is verbose, the loopback stays at the default info level
+    logger.set_component_level("SimpleSender", Level::Debug);
+
+    let sender_pair = unbounded();
+    let loopback_pair = unbounded();
+    let sender = SimpleSender::new(
+        1,
+        sim_manager.clone(),
+        3,
+        sender_pair.0,
+        ack_channel.0.clone(),
+    );
+    let loopback = SimpleLoopback::new(
+        2,
+        sim_manager.clone(),
+        3,
+        sender_pair.1,
+        loopback_pair.0,
+        ack_channel.0.clone(),
+    );
+
+    let receiver = SimpleReceiver::new(3, sim_manager.clone(), loopback_pair.1, ack_channel.0);
+
+    let sim_dispatchers = vec![SimDispatcher::new(
+        Arc::downgrade(&sim_manager),
+        vec![sender, loopback, receiver],
+    )];
+    sim_dispatchers.iter().for_each(|s| s.init());
+    let thread_handlers: Vec<_> = sim_dispatchers
+        .into_iter()
+        .map(|sim_dispatcher| thread::spawn(move || sim_dispatcher.run()))
+        .collect();
+    sim_manager.run();
+    thread_handlers.into_iter().for_each(|h| h.join().unwrap());
+
+    let lines = sink.take_lines();
+    assert_eq!(lines.len(), 3);
+    for (packet, line) in lines.iter().enumerate() {
+        assert!(line.starts_with(&format!(
+            "[cycle {}] DEBUG SimpleSender(1): sent event: {} eid ",
+            packet + 1,
+            packet
+        )));
+    }
+
+    // Raising the default level lets the loopback through as well, down to the level asked for
+    assert!(!logger.enabled(Level::Debug, "SimpleLoopback"));
+    logger.set_level(Level::Debug);
+    assert!(logger.enabled(Level::Debug, "SimpleLoopback"));
+    assert!(!logger.enabled(Level::Trace, "SimpleLoopback"));
+
+    logger.set_component_level("SimpleLoopback", Level::Error);
+    logger.log(&LogRecord {
+        level: Level::Warn,
+        cycle: None,
+        component_id: Some(2),
+        component_name: "SimpleLoopback",
+        message: format_args!("dropped"),
+    });
+    logger.log(&LogRecord {
+        level: Level::Error,
+        cycle: None,
+        component_id: None,
+        component_name: "SimpleLoopback",
+        message: format_args!("kept"),
+    });
+    assert_eq!(sink.take_lines(), vec!["ERROR SimpleLoopback: kept"]);
+
+    // No sinks, no output, the sink that was taken off sees nothing
+    logger.set_sinks(vec![]);
+    logger.log(&LogRecord {
+        level: Level::Error,
+        cycle: Some(0),
+        component_id: None,
+        component_name: "SimpleLoopback",
+        message: format_args!("nowhere"),
+    });
+    assert!(sink.take_lines().is_empty());
+}
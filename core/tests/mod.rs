@@ -1 +1,10 @@
+pub mod bus_test;
+pub mod elf_test;
+pub mod hooks_test;
+pub mod logging_test;
+pub mod pdes_test;
 pub mod simple_test;
+pub mod stats_test;
+pub mod timescale_test;
+pub mod trace_hash_test;
+pub mod watchpoint_test;
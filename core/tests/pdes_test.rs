@@ -0,0 +1,229 @@
+use rsim_core::pdes::ParallelSim;
+use rsim_core::simple_component::simple_link::SimpleLink;
+use rsim_core::simple_component::simple_receiver::SimpleReceiver;
+use rsim_core::simple_component::simple_sender::SimpleSender;
+use rsim_core::watchpoint::port_matches;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn pdes_test() {
+    let mut sim = ParallelSim::new();
+    let sender_partition = sim.add_partition();
+    let link_partition = sim.add_partition();
+    let receiver_partition = sim.add_partition();
+
+    let (sender_output, link_input) = sim.connect(sender_partition, link_partition, 1);
+    let (link_output, receiver_input) = sim.connect(link_partition, receiver_partition, 3);
+
+    let sender = SimpleSender::new(
+        1,
+        sim.get_sim_manager(sender_partition),
+        50,
+        sender_output,
+        sim.get_ack_sender(sender_partition),
+    );
+    let link = SimpleLink::new(
+        0,
+        sim.get_sim_manager(link_partition),
+        link_input,
+        link_output,
+        sim.get_ack_sender(link_partition),
+    );
+    let receiver = SimpleReceiver::new(
+        2,
+        sim.get_sim_manager(receiver_partition),
+        receiver_input,
+        sim.get_ack_sender(receiver_partition),
+    );
+
+    sim.add_component(sender_partition, sender);
+    sim.add_component(link_partition, link);
+    sim.add_component(receiver_partition, receiver.clone());
+
+    // Records the arrival cycle of every packet
+    let receiver_sim_manager = sim.get_sim_manager(receiver_partition);
+    for packet in 0..50u128 {
+        receiver_sim_manager.add_watchpoint(
+            2,
+            port_matches("input", move |input: &(u128, bool)| input.0 == packet),
+        );
+    }
+
+    sim.run(2);
+
+    assert_eq!(receiver.lock().unwrap().input, (49, true));
+
+    // Same timing as the single sim manager, plus the delay of both links
+    let hits = receiver_sim_manager.take_watchpoint_hits();
+    // Packet 0 matches the link's reset value, so the link never forwards it
+    assert_eq!(hits.len(), 49);
+    for (packet, hit) in (1..).zip(hits.iter()) {
+        assert_eq!(hit.watchpoint_id, packet as u64);
+        assert_eq!(hit.cycle, packet as u128 + 11 + 1 + 3);
+    }
+}
+
+/// sender -> link -> receiver like `pdes_test`, with a clocked sender -> receiver pair next to
+/// the receiver so its partition has events of its own every tick. The link forwards in the tick
+/// it receives, so its events land exactly on the receiving partition's horizon.
+/// `stall_link` slows the link partition down, otherwise the receiving one, and returns the trace
+/// hash of the receiving partition
+fn run_boundary(stall_link: bool) -> u64 {
+    let mut sim = ParallelSim::new();
+    let sender_partition = sim.add_partition();
+    let link_partition = sim.add_partition();
+    let receiver_partition = sim.add_partition();
+
+    let (sender_output, link_input) = sim.connect(sender_partition, link_partition, 1);
+    let (link_output, receiver_input) = sim.connect(link_partition, receiver_partition, 2);
+    let (local_output, local_input) = crossbeam_channel::unbounded();
+
+    sim.add_component(
+        sender_partition,
+        SimpleSender::new(
+            1,
+            sim.get_sim_manager(sender_partition),
+            20,
+            sender_output,
+            sim.get_ack_sender(sender_partition),
+        ),
+    );
+    sim.add_component(
+        link_partition,
+        SimpleLink::new(
+            0,
+            sim.get_sim_manager(link_partition),
+            link_input,
+            link_output,
+            sim.get_ack_sender(link_partition),
+        ),
+    );
+    sim.add_component(
+        receiver_partition,
+        SimpleReceiver::new(
+            2,
+            sim.get_sim_manager(receiver_partition),
+            receiver_input,
+            sim.get_ack_sender(receiver_partition),
+        ),
+    );
+    sim.add_component(
+        receiver_partition,
+        SimpleSender::new(
+            3,
+            sim.get_sim_manager(receiver_partition),
+            20,
+            local_output,
+            sim.get_ack_sender(receiver_partition),
+        ),
+    );
+    sim.add_component(
+        receiver_partition,
+        SimpleReceiver::new(
+            4,
+            sim.get_sim_manager(receiver_partition),
+            local_input,
+            sim.get_ack_sender(receiver_partition),
+        ),
+    );
+
+    // Never matches, only holds up the partition every time a packet comes in
+    let stall = port_matches("input", |_: &(u128, bool)| {
+        thread::sleep(Duration::from_millis(2));
+        false
+    });
+    let receiver_sim_manager = sim.get_sim_manager(receiver_partition);
+    if stall_link {
+        sim.get_sim_manager(link_partition).add_watchpoint(0, stall);
+    } else {
+        receiver_sim_manager.add_watchpoint(2, stall);
+    }
+    receiver_sim_manager.enable_trace_hash();
+
+    sim.run(2);
+    receiver_sim_manager.get_trace_hash().unwrap()
+}
+
+#[test]
+fn pdes_boundary_event_test() {
+    // Events due at the horizon are only dispatched once the link partition is past the tick
+    // that sends them, however far behind it is
+    assert_eq!(run_boundary(true), run_boundary(false));
+}
+
+/// sender -> link -> receiver like `pdes_test`, but the sender and the receiver share a partition,
+/// so the two links form a cycle. With a delay of one on both, each partition's horizon is one tick
+/// past the other's current time
+#[test]
+fn pdes_cycle_test() {
+    let mut sim = ParallelSim::new();
+    let ends_partition = sim.add_partition();
+    let link_partition = sim.add_partition();
+
+    let (sender_output, link_input) = sim.connect(ends_partition, link_partition, 1);
+    let (link_output, receiver_input) = sim.connect(link_partition, ends_partition, 1);
+
+    sim.add_component(
+        ends_partition,
+        SimpleSender::new(
+            1,
+            sim.get_sim_manager(ends_partition),
+            50,
+            sender_output,
+            sim.get_ack_sender(ends_partition),
+        ),
+    );
+    sim.add_component(
+        link_partition,
+        SimpleLink::new(
+            0,
+            sim.get_sim_manager(link_partition),
+            link_input,
+            link_output,
+            sim.get_ack_sender(link_partition),
+        ),
+    );
+    let receiver = SimpleReceiver::new(
+        2,
+        sim.get_sim_manager(ends_partition),
+        receiver_input,
+        sim.get_ack_sender(ends_partition),
+    );
+    sim.add_component(ends_partition, receiver.clone());
+
+    let ends_sim_manager = sim.get_sim_manager(ends_partition);
+    for packet in 0..50u128 {
+        ends_sim_manager.add_watchpoint(
+            2,
+            port_matches("input", move |input: &(u128, bool)| input.0 == packet),
+        );
+    }
+
+    // A deadlock fails the test instead of hanging it
+    let (done_sender, done_receiver) = crossbeam_channel::bounded(1);
+    thread::spawn(move || {
+        sim.run(2);
+        done_sender.send(()).unwrap();
+    });
+    done_receiver
+        .recv_timeout(Duration::from_secs(120))
+        .expect("partitions linked in a cycle deadlocked");
+
+    assert_eq!(receiver.lock().unwrap().input, (49, true));
+    let hits = ends_sim_manager.take_watchpoint_hits();
+    assert_eq!(hits.len(), 49);
+    for (packet, hit) in (1..).zip(hits.iter()) {
+        assert_eq!(hit.watchpoint_id, packet as u64);
+        assert_eq!(hit.cycle, packet as u128 + 11 + 1 + 1);
+    }
+}
+
+#[test]
+#[should_panic]
+fn pdes_zero_lookahead_test() {
+    let mut sim = ParallelSim::new();
+    let src = sim.add_partition();
+    let dest = sim.add_partition();
+    sim.connect(src, dest, 0);
+}
@@ -0,0 +1,92 @@
+use crossbeam_channel::unbounded;
+use rsim_core::sim_manager::SimManager;
+
+#[test]
+fn stats_test() {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    let stats = sim_manager.get_stats();
+
+    let counter = stats.counter("link", "packets");
+    counter.inc();
+    counter.add(2);
+    // Registering again hands back the same counter
+    assert_eq!(stats.counter("link", "packets").get(), 3);
+
+    let average = stats.average("link", "occupancy");
+    average.sample(1.0);
+    average.sample(2.0);
+    assert_eq!(average.get_mean(), 1.5);
+
+    let histogram = stats.histogram("link", "latency", 4, 3);
+    for latency in [0, 3, 4, 11, 100] {
+        histogram.sample(latency);
+    }
+    assert_eq!(histogram.get_buckets(), vec![2, 1, 2]);
+
+    for _ in 0..3 {
+        sim_manager.run_cycle().unwrap();
+    }
+
+    let report = sim_manager.stats_report();
+    assert_eq!(report.sim.cycles, 3);
+    assert_eq!(report.sim.events_processed, 0);
+
+    assert_eq!(
+        report.to_csv(),
+        format!(
+            "owner,name,value\n\
+             sim,cycles,3\n\
+             sim,time,3\n\
+             sim,events_processed,0\n\
+             sim,wall_time_seconds,{}\n\
+             sim,events_per_second,0\n\
+             link,latency.mean,23.6\n\
+             link,latency.count,5\n\
+             link,latency.bucket_0,2\n\
+             link,latency.bucket_4,1\n\
+             link,latency.bucket_8,2\n\
+             link,occupancy.mean,1.5\n\
+             link,occupancy.count,2\n\
+             link,packets,3\n",
+            report.sim.wall_time_seconds
+        )
+    );
+
+    let json = report.to_json();
+    assert!(json.contains("\"kind\": \"histogram\""));
+    assert!(json.contains("\"timescale\": \"Cycle\""));
+}
+
+#[test]
+fn stats_csv_escape_test() {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    let stats = sim_manager.get_stats();
+    stats.counter("link", "packets,dropped").add(2);
+    stats.counter("bus \"a\"", "reads").inc();
+    stats.average("link", "occupancy,\nqueued").sample(1.0);
+
+    let csv = sim_manager.stats_report().to_csv();
+    let rows: Vec<&str> = csv.lines().skip(6).collect();
+    assert_eq!(
+        rows,
+        [
+            "\"bus \"\"a\"\"\",reads,1",
+            "link,\"occupancy,",
+            "queued.mean\",1",
+            "link,\"occupancy,",
+            "queued.count\",1",
+            "link,\"packets,dropped\",2",
+        ]
+    );
+}
+
+#[test]
+#[should_panic]
+fn stats_kind_mismatch_test() {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    sim_manager.get_stats().counter("link", "packets");
+    sim_manager.get_stats().average("link", "packets");
+}
@@ -0,0 +1,109 @@
+use crossbeam_channel::unbounded;
+use rsim_core::clock::{Clock, Timescale};
+use rsim_core::sim_dispatcher::SimDispatcher;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::simple_component::simple_receiver::SimpleReceiver;
+use rsim_core::simple_component::simple_sender::SimpleSender;
+use rsim_core::watchpoint::port_matches;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn timescale_test() {
+    let ack_channel = unbounded();
+
+    // 1 GHz default clock, 1000ps per cycle
+    let sim_manager = SimManager::with_timescale(
+        ack_channel.1,
+        Timescale::Picosecond,
+        Clock::from_frequency(Timescale::Picosecond, 1_000_000_000),
+    );
+    assert_eq!(sim_manager.cycles_to_time(3), 3000);
+
+    // 3 GHz, edges at 333, 666, 1000, 1333, ...
+    let fast_clock =
+        sim_manager.add_clock(Clock::from_frequency(Timescale::Picosecond, 3_000_000_000));
+    sim_manager.assign_clock(1, fast_clock);
+
+    let slow_pair = unbounded();
+    let fast_pair = unbounded();
+
+    let slow_sender = SimpleSender::new(
+        0,
+        sim_manager.clone(),
+        5,
+        slow_pair.0,
+        ack_channel.0.clone(),
+    );
+    let fast_sender = SimpleSender::new(
+        1,
+        sim_manager.clone(),
+        15,
+        fast_pair.0,
+        ack_channel.0.clone(),
+    );
+    let slow_receiver =
+        SimpleReceiver::new(2, sim_manager.clone(), slow_pair.1, ack_channel.0.clone());
+    let fast_receiver =
+        SimpleReceiver::new(3, sim_manager.clone(), fast_pair.1, ack_channel.0.clone());
+
+    for (receiver, num_packets) in [(2, 5u128), (3, 15u128)] {
+        for packet in 0..num_packets {
+            sim_manager.add_watchpoint(
+                receiver,
+                port_matches("input", move |input: &(u128, bool)| input.0 == packet),
+            );
+        }
+    }
+
+    let sim_dispatchers = vec![
+        SimDispatcher::new(Arc::downgrade(&sim_manager), vec![slow_sender, fast_sender]),
+        SimDispatcher::new(
+            Arc::downgrade(&sim_manager),
+            vec![slow_receiver, fast_receiver],
+        ),
+    ];
+    sim_dispatchers.iter().for_each(|s| s.init());
+    let thread_handlers: Vec<_> = sim_dispatchers
+        .into_iter()
+        .map(|sim_dispatcher| thread::spawn(move || sim_dispatcher.run()))
+        .collect();
+
+    while !sim_manager.sim_can_end() {
+        let _ = sim_manager.run_cycle();
+        sim_manager.resume();
+        // A cycle always ends on a default clock edge
+        assert_eq!(
+            sim_manager.get_curr_time(),
+            sim_manager.get_curr_cycle() * 1000
+        );
+    }
+    thread_handlers.into_iter().for_each(|h| h.join().unwrap());
+
+    // Packets are sent on every clock edge and arrive 10 default cycles later
+    let hits = sim_manager.take_watchpoint_hits();
+    let slow_times: Vec<_> = hits
+        .iter()
+        .filter(|hit| hit.component_id == 2)
+        .map(|hit| hit.time)
+        .collect();
+    let fast_times: Vec<_> = hits
+        .iter()
+        .filter(|hit| hit.component_id == 3)
+        .map(|hit| hit.time)
+        .collect();
+    assert_eq!(
+        slow_times,
+        (1..=5).map(|edge| edge * 1000 + 10_000).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        fast_times,
+        (1..=15)
+            .map(|edge| edge * 1000 / 3 + 10_000)
+            .collect::<Vec<_>>()
+    );
+    // Sub-cycle times still map onto the cycle they fall in
+    for hit in hits {
+        assert_eq!(hit.cycle, hit.time / 1000);
+    }
+}
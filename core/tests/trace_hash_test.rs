@@ -0,0 +1,64 @@
+use crossbeam_channel::unbounded;
+use rsim_core::component::Component;
+use rsim_core::sim_dispatcher::SimDispatcher;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::simple_component::simple_link::SimpleLink;
+use rsim_core::simple_component::simple_receiver::SimpleReceiver;
+use rsim_core::simple_component::simple_sender::SimpleSender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Runs two sender -> link -> receiver chains spread over `threads_to_use` dispatchers
+fn run(threads_to_use: usize) -> u64 {
+    let ack_channel = unbounded();
+    let sim_manager = SimManager::new(ack_channel.1);
+    sim_manager.enable_trace_hash();
+
+    let mut components: Vec<Arc<Mutex<dyn Component>>> = vec![];
+    for chain in 0..2 {
+        let input_link_pair = unbounded();
+        let output_link_pair = unbounded();
+        components.push(SimpleSender::new(
+            chain * 3,
+            sim_manager.clone(),
+            20 + chain as u128,
+            input_link_pair.0,
+            ack_channel.0.clone(),
+        ));
+        components.push(SimpleLink::new(
+            chain * 3 + 1,
+            sim_manager.clone(),
+            input_link_pair.1,
+            output_link_pair.0,
+            ack_channel.0.clone(),
+        ));
+        components.push(SimpleReceiver::new(
+            chain * 3 + 2,
+            sim_manager.clone(),
+            output_link_pair.1,
+            ack_channel.0.clone(),
+        ));
+    }
+
+    let sim_dispatchers: Vec<_> = components
+        .chunks(components.len().div_ceil(threads_to_use))
+        .map(|components| SimDispatcher::new(Arc::downgrade(&sim_manager), components.into()))
+        .collect();
+    sim_dispatchers.iter().for_each(|s| s.init());
+
+    let thread_handlers: Vec<_> = sim_dispatchers
+        .into_iter()
+        .map(|sim_dispatcher| thread::spawn(move || sim_dispatcher.run()))
+        .collect();
+    sim_manager.run();
+    thread_handlers.into_iter().for_each(|h| h.join().unwrap());
+
+    sim_manager.get_trace_hash().unwrap()
+}
+
+#[test]
+fn trace_hash_test() {
+    let hash = run(1);
+    assert_eq!(hash, run(3));
+    assert_eq!(hash, run(6));
+}
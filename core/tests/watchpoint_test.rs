@@ -0,0 +1,100 @@
+use crossbeam_channel::unbounded;
+use rsim_core::sim_dispatcher::SimDispatcher;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::simple_component::simple_link::SimpleLink;
+use rsim_core::simple_component::simple_receiver::SimpleReceiver;
+use rsim_core::simple_component::simple_sender::SimpleSender;
+use rsim_core::watchpoint::{all_of, port_eq, port_matches};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn watchpoint_test() {
+    let ack_channel = unbounded();
+
+    let sim_manager = SimManager::new(ack_channel.1);
+
+    let input_link_pair = unbounded();
+    let output_link_pair = unbounded();
+
+    let link = SimpleLink::new(
+        0,
+        sim_manager.clone(),
+        input_link_pair.1,
+        output_link_pair.0,
+        ack_channel.0.clone(),
+    );
+
+    let sender = SimpleSender::new(
+        1,
+        sim_manager.clone(),
+        20,
+        input_link_pair.0,
+        ack_channel.0.clone(),
+    );
+
+    let receiver = SimpleReceiver::new(
+        2,
+        sim_manager.clone(),
+        output_link_pair.1,
+        ack_channel.0.clone(),
+    );
+
+    let packet_5 = sim_manager.add_watchpoint(2, port_eq("input", (5u128, false)));
+    let packet_10_to_12 = sim_manager.add_watchpoint(
+        2,
+        all_of(vec![
+            port_matches("input", |input: &(u128, bool)| input.0 >= 10),
+            port_matches("input", |input: &(u128, bool)| input.0 <= 12),
+        ]),
+    );
+    // wrong port name and wrong type never match
+    sim_manager.add_watchpoint(2, port_eq("output", (5u128, false)));
+    sim_manager.add_watchpoint(2, port_eq("input", 5u128));
+
+    let sim_dispatchers = vec![
+        SimDispatcher::new(Arc::downgrade(&sim_manager), vec![sender]),
+        SimDispatcher::new(Arc::downgrade(&sim_manager), vec![link]),
+        SimDispatcher::new(Arc::downgrade(&sim_manager), vec![receiver.clone()]),
+    ];
+
+    sim_dispatchers.iter().for_each(|s| s.init());
+
+    let mut thread_handlers = vec![];
+
+    for sim_dispatcher in sim_dispatchers {
+        thread_handlers.push(thread::spawn(move || sim_dispatcher.run()));
+    }
+
+    sim_manager.run();
+    assert!(sim_manager.is_paused());
+    assert!(!sim_manager.sim_can_end());
+    let hits = sim_manager.take_watchpoint_hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].watchpoint_id, packet_5);
+    assert_eq!(hits[0].component_id, 2);
+    assert_eq!(hits[0].port_name, "input");
+    // the sim stops at the cycle boundary following the hit
+    assert_eq!(hits[0].cycle + 1, sim_manager.get_curr_cycle());
+    assert_eq!(receiver.lock().unwrap().input, (5, false));
+
+    sim_manager.resume();
+    sim_manager.run();
+    assert!(sim_manager.is_paused());
+    let hits = sim_manager.take_watchpoint_hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].watchpoint_id, packet_10_to_12);
+    assert_eq!(receiver.lock().unwrap().input, (10, false));
+
+    // the condition stays true for packet 11 and 12, it should not trigger again
+    sim_manager.remove_watchpoint(packet_5);
+    sim_manager.resume();
+    sim_manager.run();
+    assert!(!sim_manager.is_paused());
+    assert!(sim_manager.sim_can_end());
+    assert!(sim_manager.take_watchpoint_hits().is_empty());
+
+    thread_handlers.into_iter().for_each(|h| {
+        h.join().unwrap();
+    });
+}
@@ -27,6 +27,25 @@ pub fn ComponentAttribute(config: TokenStream, input: TokenStream) -> TokenStrea
     let mut item_struct = parse_macro_input!(input as ItemStruct);
     let struct_name = item_struct.ident.clone();
 
+    let component_config: ComponentConfig = serde_json::from_str(&config.to_string()).unwrap();
+
+    // Input port values can be looked up by name, e.g. for watchpoints
+    let port_names: Vec<_> = component_config
+        .port
+        .as_ref()
+        .and_then(|port| port.input.as_ref())
+        .map(|input| {
+            input
+                .iter()
+                .map(|(port_name, _)| port_name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    let port_nets: Vec<_> = port_names
+        .iter()
+        .map(|port_name| format_ident!("{}", port_name))
+        .collect();
+
     let component_impl_ts = quote! {
         impl Component for #struct_name {
             fn init(&mut self) { self.init_impl(); }
@@ -37,15 +56,22 @@ pub fn ComponentAttribute(config: TokenStream, input: TokenStream) -> TokenStrea
 
             fn get_component_id(&self) -> ComponentId { self.component_id }
 
+            fn get_component_name(&self) -> &'static str { stringify!(#struct_name) }
+
             fn as_any(&self) -> &dyn std::any::Any { self }
+
+            fn get_port(&self, port_name: &str) -> Option<&dyn std::any::Any> {
+                match port_name {
+                    #(#port_names => Some(&self.#port_nets),)*
+                    _ => None,
+                }
+            }
         }
     }
     .into();
 
     let mut component_impl_item = parse_macro_input!(component_impl_ts as ItemImpl);
 
-    let component_config: ComponentConfig = serde_json::from_str(&config.to_string()).unwrap();
-
     // Every component should have these values
     let mut extended_field = vec![
         syn::Field::parse_named
@@ -93,7 +119,7 @@ pub fn ComponentAttribute(config: TokenStream, input: TokenStream) -> TokenStrea
                     if let ImplItem::Fn(func) = item {
                         if func.sig.ident == format_ident!("init") {
                             func.block.stmts.push(syn::parse_quote! {self.sim_manager
-                            .register_clock_tick(self.clock_sender.clone());})
+                            .register_component_clock_tick(self.component_id, self.clock_sender.clone());})
                         } else if func.sig.ident == format_ident!("poll_recv") {
                             push_clock_recv_stmt(&mut func.block.stmts)
                         }
@@ -185,6 +211,7 @@ fn push_comb_recv_stmt(stmt: &mut Vec<Stmt>, port_name: &str, port_type: &str) {
     stmt.push(syn::parse_quote! {
         if let Ok(event) = self.#receiver.try_recv() {
             self.#net = get_inner::<#net_type>(&*event);
+            self.sim_manager.check_watchpoints(self, #port_name, event.get_event_id());
             if self.#net != self.#net_old {
                 self.on_comb();
             }
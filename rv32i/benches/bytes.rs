@@ -0,0 +1,78 @@
+//! The ALU and comparator operations on a `Word`, the value and unknown mask pair against the
+//! old `Option<u8>` per byte. Run with `cargo bench -p rsim_rv32i --bench bytes`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[path = "../src/backend/util/byte.rs"]
+mod byte;
+mod old_bytes;
+
+const OPERANDS: [u32; 8] = [
+    0x0000_0000,
+    0x0000_0001,
+    0x7FFF_FFFF,
+    0x8000_0000,
+    0xDEAD_BEEF,
+    0x1234_5678,
+    0xFFFF_FFFF,
+    0x0000_001F,
+];
+
+macro_rules! bench_word_ops {
+    ($c:expr, $name:literal, $module:ident) => {{
+        use $module::{ByteOrd, Bytes, Shra, SignedOrd};
+        type Word = Bytes<4>;
+        let words = OPERANDS.map(Word::from);
+
+        $c.bench_function(concat!($name, "/alu"), |b| {
+            b.iter(|| {
+                let mut acc = Word::from(0u32);
+                for &lhs in &words {
+                    for &rhs in &words {
+                        let (lhs, rhs) = (black_box(lhs), black_box(rhs));
+                        acc ^= lhs + rhs;
+                        acc ^= lhs - rhs;
+                        acc ^= (lhs & rhs) | (lhs ^ rhs);
+                        let shamt = rhs & Word::from(0x1Fu32);
+                        acc ^= (lhs << shamt) ^ (lhs >> shamt) ^ lhs.shra(shamt);
+                    }
+                }
+                acc
+            })
+        });
+
+        $c.bench_function(concat!($name, "/cmp"), |b| {
+            b.iter(|| {
+                let mut less = 0;
+                for &lhs in &words {
+                    for &rhs in &words {
+                        let (lhs, rhs) = (black_box(lhs), black_box(rhs));
+                        less += (lhs.byte_cmp(rhs) == std::cmp::Ordering::Less) as u32;
+                        less += (lhs.signed_cmp(rhs) == std::cmp::Ordering::Less) as u32;
+                        less += (lhs == rhs) as u32;
+                    }
+                }
+                less
+            })
+        });
+
+        $c.bench_function(concat!($name, "/unknown"), |b| {
+            b.iter(|| {
+                let mut acc = Word::unknown();
+                for &word in &words {
+                    acc = black_box(acc) + word;
+                    acc &= word;
+                }
+                acc.has_unknown()
+            })
+        });
+    }};
+}
+
+fn word_ops(c: &mut Criterion) {
+    bench_word_ops!(c, "bytes", byte);
+    bench_word_ops!(c, "old_bytes", old_bytes);
+}
+
+criterion_group!(benches, word_ops);
+criterion_main!(benches);
@@ -0,0 +1,620 @@
+//! `Bytes` as it was before the value and unknown mask pair, an `Option<u8>` per byte, kept to
+//! compare against in the benchmarks
+
+#![allow(clippy::suspicious_arithmetic_impl)]
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Index, IndexMut};
+use std::option::Option;
+
+/// A generic Byte type
+///
+/// Bytes[0] is the **least** significant byte
+///
+/// Due to generic limitations (pending stable generic_const_exprs),
+/// operation will return Bytes of the same size as lhs, as opposed to the bigger of the two.
+///
+/// Support operations
+/// - bytes = lhs + rhs
+/// - lhs += rhs
+/// - bytes = lhs - rhs
+/// - lhs -= rhs
+/// - bytes = lhs & rhs
+/// - lhs &= rhs
+/// - bytes = lhs | rhs
+/// - lhs |= rhs
+/// - bytes = lhs ^ rhs
+/// - lhs ^= rhs
+/// - lhs << rhs
+/// - lhs <<= rhs
+/// - lhs >> rhs
+/// - lhs >>= rhs
+///
+/// Note:
+/// - DO NOT use the derived Ord for comparison, use either `byte_cmp` or `signed_cmp`
+/// - Left/Right shifting assumes the rhs is within usize, if you need to shift more than 8^64, you have better things to use than this simulator
+/// - Bytes are treated as **unsigned**
+/// - Operation will have funky result if unknowns exist in add/sub operands
+///
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Bytes<const T: usize> {
+    /// The container for the bytes, exposed for easier manipulation.
+    /// Users should prefer the implemented traits over directly manipulating the container.
+    pub data: [Option<u8>; T],
+}
+
+impl<const T: usize> Bytes<T> {
+    pub fn unknown() -> Self {
+        Bytes { data: [None; T] }
+    }
+    pub fn zeros() -> Self {
+        Bytes { data: [Some(0); T] }
+    }
+    pub fn has_unknown(&self) -> bool {
+        for byte in self.data {
+            if byte.is_none() {
+                return true;
+            }
+        }
+        false
+    }
+    pub fn is_zero(&self) -> bool {
+        for byte in self.data {
+            if byte.is_some() && byte.unwrap() != 0 {
+                return false;
+            }
+        }
+        true
+    }
+    pub fn is_something_nonzero(&self) -> bool {
+        !self.has_unknown() && !self.is_zero()
+    }
+}
+
+impl<const T: usize> Default for Bytes<T> {
+    fn default() -> Self {
+        Bytes::unknown()
+    }
+}
+
+impl<const T: usize> Display for Bytes<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = self
+            .data
+            .iter()
+            .rev()
+            .map(|x| {
+                x.map(|byte| format!("{:02x}", byte))
+                    .unwrap_or("xx".to_string())
+            })
+            .collect::<String>();
+        write!(f, "{}", value)
+    }
+}
+
+impl<const T: usize> Debug for Bytes<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self, f)
+    }
+}
+
+impl<const T: usize> Index<usize> for Bytes<T> {
+    type Output = Option<u8>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.data.index(index)
+    }
+}
+
+impl<const T: usize> IndexMut<usize> for Bytes<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.data.index_mut(index)
+    }
+}
+
+pub trait ByteOrd<Rhs = Self> {
+    fn byte_cmp(self, rhs: Rhs) -> Ordering;
+}
+impl<const T: usize, const R: usize> ByteOrd<Bytes<R>> for Bytes<T> {
+    fn byte_cmp(self, other: Bytes<R>) -> Ordering {
+        if self.has_unknown() || other.has_unknown() {
+            return Ordering::Equal;
+        }
+
+        // if self is longer than other
+        for i in R..T {
+            if self[i].unwrap() > 0 {
+                return Ordering::Greater;
+            }
+        }
+
+        // if other is longer than self
+        for i in T..R {
+            if other[i].unwrap() > 0 {
+                return Ordering::Less;
+            }
+        }
+
+        // they are the same length
+        for i in (0..T).rev() {
+            match self[i].unwrap().cmp(&other[i].unwrap()) {
+                Ordering::Greater => return Ordering::Greater,
+                Ordering::Less => return Ordering::Less,
+                _ => {}
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+pub trait SignedOrd<Rhs = Self> {
+    fn signed_cmp(self, rhs: Rhs) -> Ordering;
+}
+
+impl<const T: usize, const R: usize> SignedOrd<Bytes<R>> for Bytes<T> {
+    fn signed_cmp(self, rhs: Bytes<R>) -> Ordering {
+        if self.has_unknown() || rhs.has_unknown() {
+            return Ordering::Equal;
+        }
+
+        let self_is_negative = (self[T - 1].unwrap() >> 7) & 0x1 == 0x1;
+        let other_is_negative = (rhs[R - 1].unwrap() >> 7) & 0x1 == 0x1;
+
+        if self_is_negative != other_is_negative {
+            return if self_is_negative && !other_is_negative {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+
+        let mut self_no_msb = self;
+        self_no_msb[T - 1] = Some(self_no_msb[T - 1].unwrap() & 0x7F);
+        let mut other_no_msb = rhs;
+        other_no_msb[T - 1] = Some(other_no_msb[T - 1].unwrap() & 0x7F);
+
+        // if self is longer than other
+        for i in R..T {
+            if self_no_msb[i].unwrap() > 0 {
+                return if self_is_negative {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+            }
+        }
+
+        // if other is longer than self
+        for i in T..R {
+            if other_no_msb[i].unwrap() > 0 {
+                return if self_is_negative {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                };
+            }
+        }
+
+        self.byte_cmp(rhs)
+    }
+}
+
+impl<const T: usize> From<u8> for Bytes<T> {
+    fn from(value: u8) -> Self {
+        assert!(T >= 1);
+        let mut result = Bytes::unknown();
+        for i in 0..1 {
+            result.data[i] = Some(value);
+        }
+        result
+    }
+}
+
+impl<const T: usize> From<u16> for Bytes<T> {
+    fn from(value: u16) -> Self {
+        assert!(T >= 2);
+        let mut result = Bytes::unknown();
+        for i in 0..2 {
+            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+        }
+        result
+    }
+}
+
+impl<const T: usize> From<u32> for Bytes<T> {
+    fn from(value: u32) -> Self {
+        assert!(T >= 4);
+        let mut result = Bytes::unknown();
+        for i in 0..4 {
+            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+        }
+        result
+    }
+}
+
+impl<const T: usize> From<u64> for Bytes<T> {
+    fn from(value: u64) -> Self {
+        assert!(T >= 8);
+        let mut result = Bytes::unknown();
+        for i in 0..8 {
+            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+        }
+        result
+    }
+}
+
+impl<const T: usize> From<u128> for Bytes<T> {
+    fn from(value: u128) -> Self {
+        assert!(T >= 16);
+        let mut result = Bytes::unknown();
+        for i in 0..16 {
+            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+        }
+        result
+    }
+}
+
+impl<const T: usize> From<Bytes<T>> for Option<u8> {
+    fn from(val: Bytes<T>) -> Self {
+        assert!(T >= 1);
+        val.data[0]
+    }
+}
+
+impl<const T: usize> From<Bytes<T>> for Option<u16> {
+    fn from(val: Bytes<T>) -> Self {
+        let mut ret = Some(0);
+        for i in 0..T.min(2) {
+            if val.data[i].is_none() {
+                ret = None;
+                break;
+            } else {
+                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u16) << (i * 8));
+            }
+        }
+        ret
+    }
+}
+
+impl<const T: usize> From<Bytes<T>> for Option<u32> {
+    fn from(val: Bytes<T>) -> Self {
+        let mut ret = Some(0);
+        for i in 0..T.min(4) {
+            if val.data[i].is_none() {
+                ret = None;
+                break;
+            } else {
+                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u32) << (i * 8));
+            }
+        }
+        ret
+    }
+}
+
+impl<const T: usize> From<Bytes<T>> for Option<u64> {
+    fn from(val: Bytes<T>) -> Self {
+        let mut ret = Some(0);
+        for i in 0..T.min(8) {
+            if val.data[i].is_none() {
+                ret = None;
+                break;
+            } else {
+                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u64) << (i * 8));
+            }
+        }
+        ret
+    }
+}
+
+impl<const T: usize> From<Bytes<T>> for Option<u128> {
+    fn from(val: Bytes<T>) -> Self {
+        let mut ret = Some(0);
+        for i in 0..T.min(16) {
+            if val.data[i].is_none() {
+                ret = None;
+                break;
+            } else {
+                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u128) << (i * 8));
+            }
+        }
+        ret
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::Add<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn add(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = self;
+
+        let mut extended_rhs = Bytes::<T>::unknown();
+        for i in 0..T {
+            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
+        }
+
+        let mut cout: u8 = 0;
+        for i in 0..T {
+            result.data[i] = if let Some(lhs_byte) = result.data[i] {
+                extended_rhs.data[i].map(|rhs_byte| {
+                    let imm_add = lhs_byte as u16 + rhs_byte as u16 + cout as u16;
+                    cout = (imm_add >> 8) as u8;
+                    imm_add as u8
+                })
+            } else {
+                cout = 0;
+                None
+            }
+        }
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::AddAssign<Bytes<R>> for Bytes<T> {
+    fn add_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::Sub<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn sub(self, rhs: Bytes<R>) -> Self::Output {
+        let mut extended_rhs = Bytes::<T>::unknown();
+        for i in 0..T {
+            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
+        }
+
+        self + !extended_rhs + Bytes::<1>::from(1u8)
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::SubAssign<Bytes<R>> for Bytes<T> {
+    fn sub_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitAnd<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn bitand(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = self;
+
+        let mut extended_rhs = Bytes::<T>::unknown();
+        for i in 0..T {
+            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
+        }
+
+        let _: Vec<_> = (0..T)
+            .map(|i| {
+                result.data[i] = match (result.data[i], extended_rhs.data[i]) {
+                    (Some(0), _) => Some(0),
+                    (_, Some(0)) => Some(0),
+                    (Some(lhs), Some(rhs)) => Some(lhs & rhs),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitAndAssign<Bytes<R>> for Bytes<T> {
+    fn bitand_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self & rhs;
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitOr<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn bitor(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = self;
+
+        let _: Vec<_> = (0..R)
+            .filter(|i| i < &T)
+            .map(|i| {
+                result.data[i] = match (result.data[i], rhs.data[i]) {
+                    (Some(0xFF), _) => Some(0xFF),
+                    (_, Some(0xFF)) => Some(0xFF),
+                    (Some(lhs), Some(rhs)) => Some(lhs | rhs),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitOrAssign<Bytes<R>> for Bytes<T> {
+    fn bitor_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self | rhs;
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitXor<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn bitxor(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = self;
+
+        let _: Vec<_> = (0..R)
+            .filter(|i| i < &T)
+            .map(|i| {
+                result.data[i] = match (result.data[i], rhs.data[i]) {
+                    (Some(0xFF), Some(v)) => Some(!v),
+                    (Some(v), Some(0xFF)) => Some(!v),
+                    (Some(lhs), Some(rhs)) => Some(lhs ^ rhs),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::BitXorAssign<Bytes<R>> for Bytes<T> {
+    fn bitxor_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl<const T: usize> std::ops::Not for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn not(self) -> Self::Output {
+        let mut result = self;
+
+        let _: Vec<_> = (0..T)
+            .map(|i| self.data[i].map(|byte| result.data[i] = Some(!byte)))
+            .collect();
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::Shl<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn shl(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = Self::zeros();
+
+        if self.has_unknown() || rhs.has_unknown() {
+            return Self::unknown();
+        }
+
+        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
+        let byte_shift_count = shift_count / 8;
+        let bit_shift_count = shift_count % 8;
+        if byte_shift_count > T {
+            return Self::zeros();
+        }
+
+        for i in byte_shift_count..T {
+            result.data[i] = self.data[i - byte_shift_count];
+        }
+
+        let mut shift_in = 0u8;
+        for i in 0..T {
+            let byte = ((result.data[i].unwrap() as u16) << bit_shift_count) | (shift_in as u16);
+            shift_in = (byte >> 8) as u8;
+            result.data[i] = Some(byte as u8);
+        }
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::ShlAssign<Bytes<R>> for Bytes<T> {
+    fn shl_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self << rhs;
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::Shr<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+    fn shr(self, rhs: Bytes<R>) -> Self::Output {
+        let mut result = Self::zeros();
+
+        if self.has_unknown() || rhs.has_unknown() {
+            return Self::unknown();
+        }
+
+        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
+        let byte_shift_count = shift_count / 8;
+        let bit_shift_count = shift_count % 8;
+        if byte_shift_count > T {
+            return Self::zeros();
+        }
+
+        for i in 0..T - byte_shift_count {
+            result.data[i] = self.data[i + byte_shift_count];
+        }
+
+        let mut shift_in = 0u8;
+        for i in (0..T).rev() {
+            let byte = (result.data[i].unwrap() as u16) | ((shift_in as u16) << 8);
+            shift_in = (byte & (0xFF >> (8 - bit_shift_count))) as u8;
+            result.data[i] = Some((byte >> bit_shift_count) as u8);
+        }
+
+        result
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::ShrAssign<Bytes<R>> for Bytes<T> {
+    fn shr_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self >> rhs;
+    }
+}
+
+pub trait Shra<Rhs = Self> {
+    type Output;
+    fn shra(self, rhs: Rhs) -> Self::Output;
+}
+
+impl<const T: usize, const R: usize> Shra<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn shra(self, rhs: Bytes<R>) -> Bytes<T> {
+        let mut result = Self::zeros();
+
+        if self.has_unknown() || rhs.has_unknown() {
+            return Self::unknown();
+        }
+
+        let msb_fill = if (self.data[T - 1].unwrap() >> 7) & 0x1 == 0x1 {
+            Some(0xFF)
+        } else {
+            Some(0x00)
+        };
+
+        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
+        let byte_shift_count = shift_count / 8;
+        let bit_shift_count = shift_count % 8;
+        if byte_shift_count > T {
+            return Self::zeros();
+        }
+
+        for i in 0..T - byte_shift_count {
+            result.data[i] = self.data[i + byte_shift_count];
+        }
+
+        let mut shift_in = 0u8;
+        for i in (0..T).rev() {
+            let byte = (result.data[i].unwrap() as u16) | ((shift_in as u16) << 8);
+            shift_in = (byte & (0xFF >> (8 - bit_shift_count))) as u8;
+            result.data[i] = Some((byte >> bit_shift_count) as u8);
+        }
+
+        for i in T - byte_shift_count..T {
+            result.data[i] = msb_fill;
+        }
+
+        if bit_shift_count != 0 {
+            result.data[T - byte_shift_count - 1] = Some(
+                msb_fill.unwrap() << (8 - bit_shift_count)
+                    | result.data[T - byte_shift_count - 1].unwrap(),
+            );
+        }
+
+        result
+    }
+}
+
+pub trait ShraAssign<Rhs = Self> {
+    fn shra_assign(&mut self, rhs: Rhs);
+}
+
+impl<const T: usize, const R: usize> ShraAssign<Bytes<R>> for Bytes<T> {
+    fn shra_assign(&mut self, rhs: Bytes<R>) {
+        *self = self.shra(rhs);
+    }
+}
@@ -27,7 +27,8 @@ use std::sync::Arc;
     "output": [
         ["out_pc_mux", "Word"],
         ["out_mar_mux", "Word"],
-        ["out_regfile_mux", "Word"]
+        ["out_regfile_mux", "Word"],
+        ["out_control", "Word"]
     ]
 }
 })]
@@ -44,6 +45,7 @@ impl Alu {
         out_pc_mux: Output,
         out_mar_mux: Output,
         out_regfile_mux: Output,
+        out_control: Output,
     ) -> Self {
         Alu {
             component_id,
@@ -61,6 +63,7 @@ impl Alu {
             out_pc_mux,
             out_mar_mux,
             out_regfile_mux,
+            out_control,
         }
     }
     fn init_impl(&mut self) {}
@@ -85,6 +88,7 @@ impl Alu {
         send_word!(self, self.out_pc_mux, out);
         send_word!(self, self.out_mar_mux, out);
         send_word!(self, self.out_regfile_mux, out);
+        send_word!(self, self.out_control, out);
     }
 }
 
@@ -123,6 +127,7 @@ mod tests {
         let out_pc_mux_link = unbounded();
         let out_mar_mux_link = unbounded();
         let out_regfile_mux_link = unbounded();
+        let out_control_link = unbounded();
 
         let alu = Arc::new(Mutex::new(Alu::new(
             0,
@@ -134,6 +139,7 @@ mod tests {
             out_pc_mux_link.0.clone(),
             out_mar_mux_link.0.clone(),
             out_regfile_mux_link.0.clone(),
+            out_control_link.0.clone(),
         )));
 
         sim_manager.register_do_not_end(0);
@@ -159,6 +165,13 @@ mod tests {
             ack_channel.0.clone(),
         );
 
+        let out_control_blackhole = WordBlackhole::new(
+            1,
+            sim_manager.clone(),
+            out_control_link.1.clone(),
+            ack_channel.0.clone(),
+        );
+
         let sim_dispatchers = vec![
             SimDispatcher::new(Arc::downgrade(&sim_manager), vec![alu.clone()]),
             SimDispatcher::new(
@@ -173,6 +186,10 @@ mod tests {
                 Arc::downgrade(&sim_manager),
                 vec![out_regfile_mux_blackhole.clone()],
             ),
+            SimDispatcher::new(
+                Arc::downgrade(&sim_manager),
+                vec![out_control_blackhole.clone()],
+            ),
         ];
 
         let mut thread_handlers = vec![];
@@ -181,7 +198,7 @@ mod tests {
             thread_handlers.push(thread::spawn(move || sim_dispatcher.run()));
         }
 
-        for i in 0..16u8 {
+        for _ in 0..16u8 {
             let a_u32 = random::<u32>();
             let b_u32 = random::<u32>();
             let op_u8 = random::<u8>() % (alu_op::AND + 1);
@@ -382,6 +399,7 @@ impl AluMux2 {
             Some(mux_sel::alu2::S_IMM) => self.s_imm,
             Some(mux_sel::alu2::J_IMM) => self.j_imm,
             Some(mux_sel::alu2::RS2_OUT) => self.rs2,
+            Some(mux_sel::alu2::ZERO) => Word::zeros(),
             _ => Word::unknown(),
         };
 
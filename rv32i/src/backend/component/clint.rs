@@ -0,0 +1,195 @@
+use crate::backend::util::event::{ByteEvent, WordEvent};
+use crate::backend::util::types::*;
+use crate::{send_byte, send_word};
+use crossbeam_channel::unbounded;
+use crossbeam_channel::Sender;
+use rsim_core::ack;
+use rsim_core::component::Component;
+use rsim_core::enq;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Same layout as the SiFive CLINT with a single hart
+pub const BASE: u32 = 0x02000000;
+pub const SIZE: u32 = 0xC000;
+pub const MSIP: u32 = 0x0000;
+pub const MTIMECMP: u32 = 0x4000;
+pub const MTIMECMPH: u32 = 0x4004;
+pub const MTIME: u32 = 0xBFF8;
+pub const MTIMEH: u32 = 0xBFFC;
+
+pub fn contains(addr: Word) -> bool {
+    Into::<Option<u32>>::into(addr).is_some_and(|addr| (BASE..BASE + SIZE).contains(&addr))
+}
+
+/// Replaces the bytes of `old` selected by `wmask` with the ones of `data`
+fn merge(old: u32, data: u32, wmask: u8) -> u32 {
+    (0..4).fold(old, |acc, i| {
+        if wmask >> i & 0x1 == 0x1 {
+            (acc & !(0xFF << (i * 8))) | (data & (0xFF << (i * 8)))
+        } else {
+            acc
+        }
+    })
+}
+
+/// The core local interruptor, `mtime` counts cycles, the timer interrupt is pending while
+/// `mtime >= mtimecmp` and the software interrupt while bit 0 of `msip` is set.
+///
+/// Requests come from `MemCtl` and are answered one cycle later, `resp` is high for that cycle.
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["addr", "Word"],
+        ["wdata", "Word"],
+        ["wmask", "Byte"],
+        ["read_en", "Byte"],
+        ["write_en", "Byte"]
+    ],
+    "output": [
+        ["rdata", "Word"],
+        ["resp", "Byte"],
+        ["mtip", "Byte"],
+        ["msip", "Byte"]
+    ],
+    "clock": true
+}
+})]
+pub struct Clint {
+    pub mtime: u64,
+    pub mtimecmp: u64,
+    pub msip_inner: bool,
+}
+
+impl Clint {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        addr_receiver: Input,
+        wdata_receiver: Input,
+        wmask_receiver: Input,
+        read_en_receiver: Input,
+        write_en_receiver: Input,
+        rdata: Output,
+        resp: Output,
+        mtip: Output,
+        msip: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        Clint {
+            mtime: 0,
+            mtimecmp: u64::MAX,
+            msip_inner: false,
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            addr_receiver,
+            addr: Default::default(),
+            addr_old: Default::default(),
+            wdata_receiver,
+            wdata: Default::default(),
+            wdata_old: Default::default(),
+            wmask_receiver,
+            wmask: Default::default(),
+            wmask_old: Default::default(),
+            read_en_receiver,
+            read_en: Default::default(),
+            read_en_old: Default::default(),
+            write_en_receiver,
+            write_en: Default::default(),
+            write_en_old: Default::default(),
+            rdata,
+            resp,
+            mtip,
+            msip,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.mtime = 0;
+        self.mtimecmp = u64::MAX;
+        self.msip_inner = false;
+    }
+
+    fn poll_impl(&mut self) {}
+
+    /// Unmapped offsets read as unknown
+    pub fn read(&self, offset: u32) -> Word {
+        match offset {
+            MSIP => Word::from(self.msip_inner as u32),
+            MTIMECMP => Word::from(self.mtimecmp as u32),
+            MTIMECMPH => Word::from((self.mtimecmp >> 32) as u32),
+            MTIME => Word::from(self.mtime as u32),
+            MTIMEH => Word::from((self.mtime >> 32) as u32),
+            _ => Word::unknown(),
+        }
+    }
+
+    /// Writes to unmapped offsets are ignored
+    pub fn write(&mut self, offset: u32, data: u32, wmask: u8) {
+        let merge_low = |old: u64| (old & !0xFFFFFFFF) | merge(old as u32, data, wmask) as u64;
+        let merge_high =
+            |old: u64| (old & 0xFFFFFFFF) | (merge((old >> 32) as u32, data, wmask) as u64) << 32;
+        match offset {
+            MSIP => self.msip_inner = merge(self.msip_inner as u32, data, wmask) & 0x1 == 0x1,
+            MTIMECMP => self.mtimecmp = merge_low(self.mtimecmp),
+            MTIMECMPH => self.mtimecmp = merge_high(self.mtimecmp),
+            MTIME => self.mtime = merge_low(self.mtime),
+            MTIMEH => self.mtime = merge_high(self.mtime),
+            _ => {}
+        }
+    }
+
+    fn on_clock(&mut self) {
+        self.mtime = self.mtime.wrapping_add(1);
+
+        let offset = Into::<Option<u32>>::into(self.addr).map(|addr| addr.wrapping_sub(BASE));
+        if self.write_en.is_something_nonzero() {
+            if let (Some(offset), Some(data), Some(wmask)) =
+                (offset, self.wdata.into(), self.wmask.into())
+            {
+                self.write(offset, data, wmask);
+            }
+            send_byte!(self, self.resp, Byte::from(1u8));
+        } else if self.read_en.is_something_nonzero() {
+            let rdata = offset.map_or(Word::unknown(), |offset| self.read(offset));
+            send_word!(self, self.rdata, rdata);
+            send_byte!(self, self.resp, Byte::from(1u8));
+        } else {
+            send_byte!(self, self.resp, Byte::from(0u8));
+        }
+    }
+
+    fn on_comb(&mut self) {
+        send_byte!(
+            self,
+            self.mtip,
+            Byte::from((self.mtime >= self.mtimecmp) as u8)
+        );
+        send_byte!(self, self.msip, Byte::from(self.msip_inner as u8));
+    }
+}
+
+impl Debug for Clint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Clint: {{mtime: {}, mtimecmp: {}, msip: {}}}",
+            self.mtime, self.mtimecmp, self.msip_inner
+        )
+    }
+}
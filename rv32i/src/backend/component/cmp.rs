@@ -71,21 +71,30 @@ impl Cmp {
     fn poll_impl(&mut self) {}
 
     fn on_comb(&mut self) {
-        if match self.op.into() {
-            Some(funct3::branch::BEQ) => self.a == self.b,
-            Some(funct3::branch::BNE) => self.a != self.b,
-            Some(funct3::branch::BLT) => self.a.signed_cmp(self.b) == Less,
-            Some(funct3::branch::BGE) => self.a.signed_cmp(self.b) != Less,
-            Some(funct3::branch::BLTU) => self.a.byte_cmp(self.b) == Less,
-            Some(funct3::branch::BGEU) => self.a.byte_cmp(self.b) != Less,
-            _ => false,
-        } {
-            send_byte!(self, self.out_control, Byte::from(1u8));
-            send_word!(self, self.out_regfile_mux, Word::from(1u32));
-        } else {
-            send_byte!(self, self.out_control, Byte::from(0u8));
-            send_word!(self, self.out_regfile_mux, Word::from(0u32));
+        // Unknown operand bits only give an unknown result if they could change it
+        let taken = match self.op.into() {
+            Some(funct3::branch::BEQ) => self.a.byte_eq(self.b),
+            Some(funct3::branch::BNE) => self.a.byte_eq(self.b).map(|eq| !eq),
+            Some(funct3::branch::BLT) => self.a.signed_partial_cmp(self.b).map(|ord| ord == Less),
+            Some(funct3::branch::BGE) => self.a.signed_partial_cmp(self.b).map(|ord| ord != Less),
+            Some(funct3::branch::BLTU) => self.a.byte_partial_cmp(self.b).map(|ord| ord == Less),
+            Some(funct3::branch::BGEU) => self.a.byte_partial_cmp(self.b).map(|ord| ord != Less),
+            _ => Some(false),
         };
+        match taken {
+            Some(true) => {
+                send_byte!(self, self.out_control, Byte::from(1u8));
+                send_word!(self, self.out_regfile_mux, Word::from(1u32));
+            }
+            Some(false) => {
+                send_byte!(self, self.out_control, Byte::from(0u8));
+                send_word!(self, self.out_regfile_mux, Word::from(0u32));
+            }
+            None => {
+                send_byte!(self, self.out_control, Byte::unknown());
+                send_word!(self, self.out_regfile_mux, Word::unknown());
+            }
+        }
     }
 }
 
@@ -1,7 +1,12 @@
+use crate::backend::component::clint;
+use crate::backend::component::csr::CsrFile;
+use crate::backend::component::fpu;
 use crate::backend::util::event::ByteEvent;
+use crate::backend::util::event::WordEvent;
 use crate::backend::util::types::States::*;
 use crate::backend::util::types::*;
 use crate::send_byte;
+use crate::send_word;
 use crossbeam_channel::{unbounded, Sender};
 use rsim_core::ack;
 use rsim_core::component::Component;
@@ -21,12 +26,21 @@ use std::sync::Arc;
 #[ComponentAttribute({
 "port": {
     "input": [
-        ["funct3", "Byte"],
-        ["funct7", "Byte"],
+        ["funct3", "Funct3"],
+        ["funct7", "Funct7"],
         ["cmp_out", "Byte"],
-        ["opcode", "Byte"],
+        ["opcode", "Opcode"],
         ["mar", "Word"],
-        ["mem_resp", "Byte"]
+        ["mem_resp", "Byte"],
+        ["alu_out", "Word"],
+        ["ir", "Word"],
+        ["interrupt", "Word"],
+        ["muldiv_done", "Byte"],
+        ["straddle", "Byte"],
+        ["compressed", "Byte"],
+        ["frm", "Byte"],
+        ["fpu_done", "Byte"],
+        ["fs", "Byte"]
     ],
     "output": [
         ["load_mar", "Byte"],
@@ -46,7 +60,29 @@ use std::sync::Arc;
         ["mem_read", "Byte"],
         ["mem_write", "Byte"],
         ["mem_wmask", "Byte"],
-        ["mem_rmask", "Byte"]
+        ["mem_rmask", "Byte"],
+        ["load_csr", "Byte"],
+        ["csr_op", "Byte"],
+        ["csr_mux_sel", "Byte"],
+        ["trap", "Byte"],
+        ["mret", "Byte"],
+        ["cause", "Word"],
+        ["tval_mux_sel", "Byte"],
+        ["muldiv_start", "Byte"],
+        ["muldiv_op", "Byte"],
+        ["load_rvc_upper", "Byte"],
+        ["rvc_sel", "Byte"],
+        ["mem_amo", "Byte"],
+        ["mem_amo_op", "Byte"],
+        ["fpu_start", "Byte"],
+        ["fpu_op", "Byte"],
+        ["fpu_rm", "Byte"],
+        ["load_fp_regfile", "Byte"],
+        ["fp_regfile_mux_sel", "Byte"],
+        ["data_out_mux_sel", "Byte"],
+        ["accrue_fflags", "Byte"],
+        ["retire", "Byte"],
+        ["fp_dirty", "Byte"]
     ],
     "clock": true
 }
@@ -67,6 +103,15 @@ impl Control {
         opcode_receiver: Input,
         mar_receiver: Input,
         mem_resp_receiver: Input,
+        alu_out_receiver: Input,
+        ir_receiver: Input,
+        interrupt_receiver: Input,
+        muldiv_done_receiver: Input,
+        straddle_receiver: Input,
+        compressed_receiver: Input,
+        frm_receiver: Input,
+        fpu_done_receiver: Input,
+        fs_receiver: Input,
         load_mar: Output,
         load_mdr: Output,
         load_pc: Output,
@@ -85,6 +130,28 @@ impl Control {
         mem_write: Output,
         mem_wmask: Output,
         mem_rmask: Output,
+        load_csr: Output,
+        csr_op: Output,
+        csr_mux_sel: Output,
+        trap: Output,
+        mret: Output,
+        cause: Output,
+        tval_mux_sel: Output,
+        muldiv_start: Output,
+        muldiv_op: Output,
+        load_rvc_upper: Output,
+        rvc_sel: Output,
+        mem_amo: Output,
+        mem_amo_op: Output,
+        fpu_start: Output,
+        fpu_op: Output,
+        fpu_rm: Output,
+        load_fp_regfile: Output,
+        fp_regfile_mux_sel: Output,
+        data_out_mux_sel: Output,
+        accrue_fflags: Output,
+        retire: Output,
+        fp_dirty: Output,
     ) -> Self {
         let clock_channel = unbounded();
 
@@ -114,6 +181,33 @@ impl Control {
             mem_resp_receiver,
             mem_resp: Default::default(),
             mem_resp_old: Default::default(),
+            alu_out_receiver,
+            alu_out: Default::default(),
+            alu_out_old: Default::default(),
+            ir_receiver,
+            ir: Default::default(),
+            ir_old: Default::default(),
+            interrupt_receiver,
+            interrupt: Default::default(),
+            interrupt_old: Default::default(),
+            muldiv_done_receiver,
+            muldiv_done: Default::default(),
+            muldiv_done_old: Default::default(),
+            straddle_receiver,
+            straddle: Default::default(),
+            straddle_old: Default::default(),
+            compressed_receiver,
+            compressed: Default::default(),
+            compressed_old: Default::default(),
+            frm_receiver,
+            frm: Default::default(),
+            frm_old: Default::default(),
+            fpu_done_receiver,
+            fpu_done: Default::default(),
+            fpu_done_old: Default::default(),
+            fs_receiver,
+            fs: Default::default(),
+            fs_old: Default::default(),
             load_mar,
             load_mdr,
             load_pc,
@@ -132,6 +226,28 @@ impl Control {
             mem_write,
             mem_wmask,
             mem_rmask,
+            load_csr,
+            csr_op,
+            csr_mux_sel,
+            trap,
+            mret,
+            cause,
+            tval_mux_sel,
+            muldiv_start,
+            muldiv_op,
+            load_rvc_upper,
+            rvc_sel,
+            mem_amo,
+            mem_amo_op,
+            fpu_start,
+            fpu_op,
+            fpu_rm,
+            load_fp_regfile,
+            fp_regfile_mux_sel,
+            data_out_mux_sel,
+            accrue_fflags,
+            retire,
+            fp_dirty,
         }
     }
 
@@ -146,7 +262,7 @@ impl Control {
     }
 
     pub fn get_rmask(&self) -> Byte {
-        if self.state == Fetch2 {
+        if self.state == Fetch2 || self.state == Fetch4 {
             Byte::from(0x0Fu8)
         } else {
             match self.funct3.into() {
@@ -178,6 +294,16 @@ impl Control {
         send_byte!(self, self.load_ir, Byte::from(0u8));
         send_byte!(self, self.load_regfile, Byte::from(0u8));
         send_byte!(self, self.load_dataout, Byte::from(0u8));
+        send_byte!(self, self.load_csr, Byte::from(0u8));
+        send_byte!(self, self.trap, Byte::from(0u8));
+        send_byte!(self, self.mret, Byte::from(0u8));
+        send_byte!(self, self.muldiv_start, Byte::from(0u8));
+        send_byte!(self, self.load_rvc_upper, Byte::from(0u8));
+        send_byte!(self, self.fpu_start, Byte::from(0u8));
+        send_byte!(self, self.load_fp_regfile, Byte::from(0u8));
+        send_byte!(self, self.accrue_fflags, Byte::from(0u8));
+        send_byte!(self, self.retire, Byte::from(0u8));
+        send_byte!(self, self.fp_dirty, Byte::from(0u8));
         // send_byte!(self, self.pc_mux_sel, Byte::from(0u8));
         // send_byte!(self, self.cmp_op, Byte::from(0u8));
         // send_byte!(self, self.alu_mux1_sel, Byte::from(0u8));
@@ -188,31 +314,296 @@ impl Control {
         // send_byte!(self, self.alu_op, Byte::from(0u8));
         send_byte!(self, self.mem_read, Byte::from(0u8));
         send_byte!(self, self.mem_write, Byte::from(0u8));
+        send_byte!(self, self.mem_amo, Byte::from(0u8));
         send_byte!(self, self.mem_wmask, self.get_wmask());
         send_byte!(self, self.mem_rmask, self.get_rmask());
     }
 
+    /// Every instruction ends by loading the PC, all of them retire except the ones that trap
     fn load_pc(&mut self, sel: u8) {
+        if sel != mux_sel::pc::MTVEC {
+            send_byte!(self, self.retire, Byte::from(1u8));
+        }
         send_byte!(self, self.load_pc, Byte::from(1u8));
         send_byte!(self, self.pc_mux_sel, Byte::from(sel));
     }
 
+    /// Past the 2 or 4 bytes of the current instruction
+    fn load_next_pc(&mut self) {
+        self.load_pc(if self.compressed.is_something_nonzero() {
+            mux_sel::pc::PC_PLUS2
+        } else {
+            mux_sel::pc::PC_PLUS4
+        });
+    }
+
+    /// The return address of JAL and JALR
+    fn load_link(&mut self) {
+        self.load_regfile(if self.compressed.is_something_nonzero() {
+            mux_sel::regfile::PC_PLUS2
+        } else {
+            mux_sel::regfile::PC_PLUS4
+        });
+    }
+
     fn load_regfile(&mut self, sel: u8) {
         send_byte!(self, self.load_regfile, Byte::from(1u8));
         send_byte!(self, self.regfile_mux_sel, Byte::from(sel));
     }
 
+    fn load_fp_regfile(&mut self, sel: u8) {
+        send_byte!(self, self.fp_dirty, Byte::from(1u8));
+        send_byte!(self, self.load_fp_regfile, Byte::from(1u8));
+        send_byte!(self, self.fp_regfile_mux_sel, Byte::from(sel));
+    }
+
     fn load_mar(&mut self, sel: u8) {
         send_byte!(self, self.load_mar, Byte::from(1u8));
         send_byte!(self, self.mar_mux_sel, Byte::from(sel));
     }
 
-    fn load_ir(&mut self) {
+    fn load_ir(&mut self, sel: u8) {
         send_byte!(self, self.load_ir, Byte::from(1u8));
+        send_byte!(self, self.rvc_sel, Byte::from(sel));
+    }
+
+    /// Keeps the low half of an instruction at the end of the word, and fetches the next word
+    fn fetch_rest(&mut self) {
+        send_byte!(self, self.load_rvc_upper, Byte::from(1u8));
+        send_byte!(self, self.rvc_sel, Byte::from(mux_sel::ir::MDR_OUT));
+        self.load_mar(mux_sel::mar::PC_PLUS2);
     }
 
-    fn load_dout(&mut self) {
+    fn load_dout(&mut self, sel: u8) {
         send_byte!(self, self.load_dataout, Byte::from(1u8));
+        send_byte!(self, self.data_out_mux_sel, Byte::from(sel));
+    }
+
+    fn load_csr(&mut self, csr_op: u8, sel: u8) {
+        send_byte!(self, self.load_csr, Byte::from(1u8));
+        send_byte!(self, self.csr_op, Byte::from(csr_op));
+        send_byte!(self, self.csr_mux_sel, Byte::from(sel));
+    }
+
+    /// The CSR file saves the PC and the cause, the PC goes to mtvec
+    fn take_trap(&mut self, cause: u32) {
+        send_byte!(self, self.trap, Byte::from(1u8));
+        send_word!(self, self.cause, Word::from(cause));
+        send_byte!(
+            self,
+            self.tval_mux_sel,
+            Byte::from(match cause {
+                exception::INST_ADDR_MISALIGNED
+                | exception::LOAD_ADDR_MISALIGNED
+                | exception::STORE_ADDR_MISALIGNED
+                | exception::LOAD_ACCESS_FAULT
+                | exception::STORE_ACCESS_FAULT => mux_sel::tval::MAR_OUT,
+                exception::ILLEGAL_INST => mux_sel::tval::IR_OUT,
+                exception::BREAKPOINT => mux_sel::tval::PC_OUT,
+                _ => mux_sel::tval::ZERO,
+            })
+        );
+        self.load_pc(mux_sel::pc::MTVEC);
+    }
+
+    fn take_mret(&mut self) {
+        send_byte!(self, self.mret, Byte::from(1u8));
+        self.load_pc(mux_sel::pc::MEPC);
+    }
+
+    /// The AMO, the top 5 bits of funct7
+    fn funct5(&self) -> Option<u8> {
+        Into::<Option<u8>>::into(self.funct7).map(|funct7| funct7 >> 2)
+    }
+
+    /// LR counts as a load for its exceptions, SC and the other AMOs as stores
+    fn is_load(&self) -> bool {
+        self.opcode == Opcode::from(opcode::LOAD)
+            || self.opcode == Opcode::from(opcode::LOAD_FP)
+            || (self.opcode == Opcode::from(opcode::AMO) && self.funct5() == Some(amo::LR))
+    }
+
+    /// Loads, stores and AMOs must be naturally aligned, the address is on the ALU output
+    /// in `AddrCalc`
+    fn addr_misaligned(&self) -> bool {
+        match Into::<Option<u8>>::into(self.funct3).map(|funct3| funct3 & 0b011) {
+            Some(0b01) => self.alu_out.get_bit(0) != Some(false),
+            Some(0b10) => {
+                self.alu_out.get_bit(0) != Some(false) || self.alu_out.get_bit(1) != Some(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// One of `fpu_op`, `None` if the instruction is not in RV32F
+    fn fpu_op(&self) -> Option<u8> {
+        let inst = Into::<Option<u32>>::into(self.ir)?;
+        let opcode = Into::<Option<u8>>::into(self.opcode)?;
+        let funct3 = Into::<Option<u8>>::into(self.funct3)?;
+        let funct7 = Into::<Option<u8>>::into(self.funct7)?;
+        let rs2 = (inst >> 20) & 0x1F;
+        // fmt is 0b00 for single precision
+        if funct7 & 0b11 != 0 {
+            return None;
+        }
+        match opcode {
+            opcode::MADD => Some(fpu_op::MADD),
+            opcode::MSUB => Some(fpu_op::MSUB),
+            opcode::NMSUB => Some(fpu_op::NMSUB),
+            opcode::NMADD => Some(fpu_op::NMADD),
+            opcode::OP_FP => match (funct7, funct3, rs2) {
+                (funct7::fp::ADD, _, _) => Some(fpu_op::ADD),
+                (funct7::fp::SUB, _, _) => Some(fpu_op::SUB),
+                (funct7::fp::MUL, _, _) => Some(fpu_op::MUL),
+                (funct7::fp::DIV, _, _) => Some(fpu_op::DIV),
+                (funct7::fp::SQRT, _, 0) => Some(fpu_op::SQRT),
+                (funct7::fp::SGNJ, 0b000, _) => Some(fpu_op::SGNJ),
+                (funct7::fp::SGNJ, 0b001, _) => Some(fpu_op::SGNJN),
+                (funct7::fp::SGNJ, 0b010, _) => Some(fpu_op::SGNJX),
+                (funct7::fp::MIN_MAX, 0b000, _) => Some(fpu_op::MIN),
+                (funct7::fp::MIN_MAX, 0b001, _) => Some(fpu_op::MAX),
+                (funct7::fp::CVT_W_S, _, 0) => Some(fpu_op::CVT_W_S),
+                (funct7::fp::CVT_W_S, _, 1) => Some(fpu_op::CVT_WU_S),
+                (funct7::fp::MV_X_W, 0b000, 0) => Some(fpu_op::MV_X_W),
+                (funct7::fp::MV_X_W, 0b001, 0) => Some(fpu_op::CLASS),
+                (funct7::fp::CMP, 0b010, _) => Some(fpu_op::EQ),
+                (funct7::fp::CMP, 0b001, _) => Some(fpu_op::LT),
+                (funct7::fp::CMP, 0b000, _) => Some(fpu_op::LE),
+                (funct7::fp::CVT_S_W, _, 0) => Some(fpu_op::CVT_S_W),
+                (funct7::fp::CVT_S_W, _, 1) => Some(fpu_op::CVT_S_WU),
+                (funct7::fp::MV_W_X, 0b000, 0) => Some(fpu_op::MV_W_X),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The rm field, frm when it is DYN. `None` for the reserved modes
+    fn fpu_rm(&self) -> Option<u8> {
+        let rm = match Into::<Option<u8>>::into(self.funct3)? {
+            rounding_mode::DYN => Into::<Option<u8>>::into(self.frm)?,
+            rm => rm,
+        };
+        (rm <= rounding_mode::RMM).then_some(rm)
+    }
+
+    /// CSRRS and CSRRC with rs1 of x0, and their immediate forms with a zimm of 0, do not write.
+    /// It is the field that counts, a register holding 0 still writes
+    fn csr_writes(inst: u32, funct3: u8) -> bool {
+        funct3 & 0b011 == csr_op::RW || (inst >> 15) & 0x1F != 0
+    }
+
+    /// The mcause of the interrupt to take, none while `interrupt` is zero or not fully known
+    fn interrupt_cause(interrupt: Word) -> Option<u32> {
+        Into::<Option<u32>>::into(interrupt).filter(|&cause| cause != 0)
+    }
+
+    /// mstatus.FS is not Off, the FP instructions and CSRs are illegal otherwise
+    fn fp_enabled(&self) -> bool {
+        self.fs.is_something_nonzero()
+    }
+
+    /// Valid CSR address, and no write to a read only CSR
+    fn csr_legal(&self, inst: u32, funct3: u8) -> bool {
+        let addr = (inst >> 20) as u16;
+        CsrFile::is_implemented(addr)
+            && !(Self::csr_writes(inst, funct3) && CsrFile::is_read_only(addr))
+            && (self.fp_enabled() || !CsrFile::is_fp(addr))
+    }
+
+    /// The next state after `Decode`, anything not in RV32IMAF + Zicsr is an illegal instruction,
+    /// compressed ones were expanded before they got to `IR`
+    fn decode(&self) -> States {
+        let illegal = Trap(exception::ILLEGAL_INST);
+        let (Some(inst), Some(opcode), Some(funct3), Some(funct7)) = (
+            Into::<Option<u32>>::into(self.ir),
+            Into::<Option<u8>>::into(self.opcode),
+            Into::<Option<u8>>::into(self.funct3),
+            Into::<Option<u8>>::into(self.funct7),
+        ) else {
+            return illegal;
+        };
+        // The two low bits are 0b11 for every 32 bit instruction
+        if inst & 0b11 != 0b11 {
+            return illegal;
+        }
+        match opcode {
+            opcode::LUI => Lui,
+            opcode::AUIPC => Auipc,
+            opcode::JAL => Jal,
+            opcode::JALR if funct3 == 0 => Jalr,
+            opcode::BR if funct3 >> 1 != 0b01 => Br,
+            opcode::LOAD if funct3 != 0b011 && funct3 >> 1 != 0b11 => AddrCalc,
+            opcode::STORE if funct3 <= funct3::store::SW => AddrCalc,
+            opcode::LOAD_FP
+            | opcode::STORE_FP
+            | opcode::MADD
+            | opcode::MSUB
+            | opcode::NMSUB
+            | opcode::NMADD
+            | opcode::OP_FP
+                if !self.fp_enabled() =>
+            {
+                illegal
+            }
+            opcode::LOAD_FP if funct3 == funct3::load::LW => AddrCalc,
+            opcode::STORE_FP if funct3 == funct3::store::SW => AddrCalc,
+            // the rounding mode only matters to the ones that round
+            opcode::MADD | opcode::MSUB | opcode::NMSUB | opcode::NMADD | opcode::OP_FP => {
+                match self.fpu_op() {
+                    Some(op) if !fpu::is_rounded(op) || self.fpu_rm().is_some() => Fp1,
+                    _ => illegal,
+                }
+            }
+            opcode::IMM => match funct3 {
+                funct3::arith::SLL if funct7 != funct7::BASE => illegal,
+                funct3::arith::SR if funct7 != funct7::BASE && funct7 != funct7::ALT => illegal,
+                _ => Imm,
+            },
+            opcode::REG => match funct7 {
+                funct7::BASE => Reg,
+                funct7::MULDIV => MulDiv1,
+                funct7::ALT if funct3 == funct3::arith::ADD || funct3 == funct3::arith::SR => Reg,
+                _ => illegal,
+            },
+            // LR has no rs2
+            opcode::AMO if funct3 == funct3::amo::W => match funct7 >> 2 {
+                amo::LR if (inst >> 20) & 0x1F == 0 => AddrCalc,
+                amo::SC
+                | amo::SWAP
+                | amo::ADD
+                | amo::XOR
+                | amo::AND
+                | amo::OR
+                | amo::MIN
+                | amo::MAX
+                | amo::MINU
+                | amo::MAXU => AddrCalc,
+                _ => illegal,
+            },
+            opcode::MISC_MEM
+                if funct3 == funct3::misc_mem::FENCE || funct3 == funct3::misc_mem::FENCE_I =>
+            {
+                Nop
+            }
+            opcode::SYSTEM => match (funct3, inst) {
+                (funct3::system::PRIV, priv_inst::ECALL) => Trap(exception::ECALL_M),
+                (funct3::system::PRIV, priv_inst::EBREAK) => Trap(exception::BREAKPOINT),
+                (funct3::system::PRIV, priv_inst::MRET) => Mret,
+                (funct3::system::PRIV, priv_inst::WFI) => Nop,
+                (
+                    funct3::system::CSRRW
+                    | funct3::system::CSRRS
+                    | funct3::system::CSRRC
+                    | funct3::system::CSRRWI
+                    | funct3::system::CSRRSI
+                    | funct3::system::CSRRCI,
+                    _,
+                ) if self.csr_legal(inst, funct3) => Csr,
+                _ => illegal,
+            },
+            _ => illegal,
+        }
     }
 
     fn set_alu(&mut self, sel1: u8, sel2: u8, alu_op: u8) {
@@ -226,6 +617,16 @@ impl Control {
         send_byte!(self, self.cmp_op, Byte::from(cmp_op));
     }
 
+    fn amo_mem(&mut self) {
+        send_byte!(self, self.load_mdr, Byte::from(1u8));
+        send_byte!(self, self.mem_amo, Byte::from(1u8));
+        send_byte!(
+            self,
+            self.mem_amo_op,
+            self.funct5().map_or(Byte::unknown(), Byte::from)
+        );
+    }
+
     fn read_from_mem(&mut self) {
         send_byte!(self, self.load_mdr, Byte::from(1u8));
         send_byte!(self, self.mem_read, Byte::from(1u8));
@@ -244,7 +645,17 @@ impl Control {
                 self.read_from_mem();
             }
             Fetch3 => {
-                self.load_ir();
+                if self.straddle.is_something_nonzero() {
+                    self.fetch_rest();
+                } else {
+                    self.load_ir(mux_sel::ir::MDR_OUT);
+                }
+            }
+            Fetch4 => {
+                self.read_from_mem();
+            }
+            Fetch5 => {
+                self.load_ir(mux_sel::ir::STRADDLE);
             }
             Decode => {}
             Imm => {
@@ -279,7 +690,7 @@ impl Control {
                         }
                     }
                 }
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
             Reg => {
                 if let (Some(funct3), Some(funct7)) = (
@@ -325,22 +736,22 @@ impl Control {
                         }
                     }
                 }
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
             Lui => {
                 self.load_regfile(mux_sel::regfile::U_IMM);
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
             Br => {
                 self.set_alu(mux_sel::alu1::PC_OUT, mux_sel::alu2::B_IMM, alu_op::ADD);
                 if let Some(funct3) = Into::<Option<u8>>::into(self.funct3) {
                     self.set_cmp(mux_sel::cmp::RS2_OUT, funct3);
                 }
-                self.load_pc(if self.cmp_out.is_something_nonzero() {
-                    mux_sel::pc::ALU_OUT
+                if self.cmp_out.is_something_nonzero() {
+                    self.load_pc(mux_sel::pc::ALU_OUT);
                 } else {
-                    mux_sel::pc::PC_PLUS4
-                });
+                    self.load_next_pc();
+                }
             }
             Auipc => {
                 self.load_regfile(mux_sel::regfile::ALU_OUT);
@@ -348,26 +759,31 @@ impl Control {
                 if let Some(funct3) = Into::<Option<u8>>::into(self.funct3) {
                     self.set_cmp(mux_sel::cmp::RS2_OUT, funct3);
                 }
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
             AddrCalc => {
                 self.load_mar(mux_sel::mar::ALU_OUT);
-                self.set_alu(
-                    mux_sel::alu1::RS1_OUT,
-                    if self.opcode == Byte::from(opcode::LOAD) {
-                        mux_sel::alu2::I_IMM
-                    } else {
-                        mux_sel::alu2::S_IMM
-                    },
-                    alu_op::ADD,
-                );
-                if self.opcode == Byte::from(opcode::STORE) {
-                    self.load_dout();
+                let sel2 = match self.opcode.into() {
+                    Some(opcode::LOAD | opcode::LOAD_FP) => mux_sel::alu2::I_IMM,
+                    Some(opcode::STORE | opcode::STORE_FP) => mux_sel::alu2::S_IMM,
+                    _ => mux_sel::alu2::ZERO,
+                };
+                self.set_alu(mux_sel::alu1::RS1_OUT, sel2, alu_op::ADD);
+                if self.opcode == Opcode::from(opcode::STORE_FP) {
+                    self.load_dout(mux_sel::data_out::FP_RS2_OUT);
+                } else if self.opcode != Opcode::from(opcode::LOAD)
+                    && self.opcode != Opcode::from(opcode::LOAD_FP)
+                {
+                    self.load_dout(mux_sel::data_out::RS2_OUT);
                 }
             }
             Load1 => {
                 self.read_from_mem();
             }
+            Load2 if self.opcode == Opcode::from(opcode::LOAD_FP) => {
+                self.load_fp_regfile(mux_sel::fp_regfile::MDR_OUT);
+                self.load_next_pc();
+            }
             Load2 => {
                 if let Some(funct3) = Into::<Option<u8>>::into(self.funct3) {
                     match funct3 {
@@ -389,23 +805,97 @@ impl Control {
                         _ => {}
                     }
                 }
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
             Store1 => {
                 self.write_to_mem();
             }
+            Amo1 => {
+                self.amo_mem();
+            }
+            Amo2 => {
+                self.load_regfile(mux_sel::regfile::LW);
+                self.load_next_pc();
+            }
             Store2 => {
-                self.load_pc(mux_sel::pc::PC_PLUS4);
+                self.load_next_pc();
             }
+            // With C every target is at least 2 byte aligned, there is no misaligned fetch
             Jal => {
-                self.load_pc(mux_sel::pc::ALU_OUT);
                 self.set_alu(mux_sel::alu1::PC_OUT, mux_sel::alu2::J_IMM, alu_op::ADD);
-                self.load_regfile(mux_sel::regfile::PC_PLUS4);
+                self.load_pc(mux_sel::pc::ALU_OUT);
+                self.load_link();
             }
             Jalr => {
-                self.load_pc(mux_sel::pc::ALU_MOD2);
                 self.set_alu(mux_sel::alu1::RS1_OUT, mux_sel::alu2::I_IMM, alu_op::ADD);
-                self.load_regfile(mux_sel::regfile::PC_PLUS4);
+                self.load_pc(mux_sel::pc::ALU_MOD2);
+                self.load_link();
+            }
+            MulDiv1 => {
+                send_byte!(self, self.muldiv_start, Byte::from(1u8));
+                send_byte!(
+                    self,
+                    self.muldiv_op,
+                    Into::<Option<u8>>::into(self.funct3).map_or(Byte::unknown(), Byte::from)
+                );
+            }
+            MulDiv2 => {
+                self.load_regfile(mux_sel::regfile::MULDIV_OUT);
+                self.load_next_pc();
+            }
+            Fp1 => {
+                send_byte!(self, self.fpu_start, Byte::from(1u8));
+                send_byte!(
+                    self,
+                    self.fpu_op,
+                    self.fpu_op().map_or(Byte::unknown(), Byte::from)
+                );
+                send_byte!(
+                    self,
+                    self.fpu_rm,
+                    self.fpu_rm().map_or(Byte::unknown(), Byte::from)
+                );
+            }
+            Fp2 => {
+                if self.fpu_op().is_some_and(fpu::writes_int) {
+                    self.load_regfile(mux_sel::regfile::FPU_OUT);
+                } else {
+                    self.load_fp_regfile(mux_sel::fp_regfile::FPU_OUT);
+                }
+                send_byte!(self, self.accrue_fflags, Byte::from(1u8));
+                send_byte!(self, self.fp_dirty, Byte::from(1u8));
+                self.load_next_pc();
+            }
+            Csr => {
+                // rd gets the old value, the CSR the new one, both on the same edge
+                if let (Some(inst), Some(funct3)) = (
+                    Into::<Option<u32>>::into(self.ir),
+                    Into::<Option<u8>>::into(self.funct3),
+                ) {
+                    self.load_regfile(mux_sel::regfile::CSR_OUT);
+                    self.load_csr(
+                        if Self::csr_writes(inst, funct3) {
+                            funct3 & 0b011
+                        } else {
+                            csr_op::READ
+                        },
+                        if funct3 & 0b100 == 0b100 {
+                            mux_sel::csr::ZIMM
+                        } else {
+                            mux_sel::csr::RS1_OUT
+                        },
+                    );
+                }
+                self.load_next_pc();
+            }
+            Nop => {
+                self.load_next_pc();
+            }
+            Trap(cause) => {
+                self.take_trap(cause);
+            }
+            Mret => {
+                self.take_mret();
             }
         }
     }
@@ -414,7 +904,11 @@ impl Control {
         self.next_state = self.state;
 
         self.next_state = match self.state {
-            Fetch1 => Fetch2,
+            // Interrupts are only taken between instructions, mepc is the one not fetched yet
+            Fetch1 => match Self::interrupt_cause(self.interrupt) {
+                Some(cause) => Trap(cause),
+                None => Fetch2,
+            },
             Fetch2 => {
                 if self.mem_resp.is_something_nonzero() {
                     Fetch3
@@ -422,20 +916,40 @@ impl Control {
                     Fetch2
                 }
             }
-            Fetch3 => Decode,
-            Decode => match Into::<Option<u8>>::into(self.opcode) {
-                Some(opcode::LUI) => Lui,
-                Some(opcode::AUIPC) => Auipc,
-                Some(opcode::JAL) => Jal,
-                Some(opcode::JALR) => Jalr,
-                Some(opcode::BR) => Br,
-                Some(opcode::LOAD) | Some(opcode::STORE) => AddrCalc,
-                Some(opcode::IMM) => Imm,
-                Some(opcode::REG) => Reg,
-                _ => Fetch1,
-            },
+            Fetch3 => {
+                if self.straddle.is_something_nonzero() {
+                    Fetch4
+                } else {
+                    Decode
+                }
+            }
+            Fetch4 => {
+                if self.mem_resp.is_something_nonzero() {
+                    Fetch5
+                } else {
+                    Fetch4
+                }
+            }
+            Fetch5 => Decode,
+            Decode => self.decode(),
             AddrCalc => {
-                if self.opcode == Byte::from(opcode::LOAD) {
+                let is_amo = self.opcode == Opcode::from(opcode::AMO);
+                if self.addr_misaligned() {
+                    Trap(if self.is_load() {
+                        exception::LOAD_ADDR_MISALIGNED
+                    } else {
+                        exception::STORE_ADDR_MISALIGNED
+                    })
+                } else if is_amo && clint::contains(self.alu_out) {
+                    // the CLINT only takes plain reads and writes
+                    Trap(if self.is_load() {
+                        exception::LOAD_ACCESS_FAULT
+                    } else {
+                        exception::STORE_ACCESS_FAULT
+                    })
+                } else if is_amo {
+                    Amo1
+                } else if self.is_load() {
                     Load1
                 } else {
                     Store1
@@ -455,6 +969,27 @@ impl Control {
                     Store1
                 }
             }
+            Amo1 => {
+                if self.mem_resp.is_something_nonzero() {
+                    Amo2
+                } else {
+                    Amo1
+                }
+            }
+            Fp1 => {
+                if self.fpu_done.is_something_nonzero() {
+                    Fp2
+                } else {
+                    Fp1
+                }
+            }
+            MulDiv1 => {
+                if self.muldiv_done.is_something_nonzero() {
+                    MulDiv2
+                } else {
+                    MulDiv1
+                }
+            }
             _ => Fetch1,
         }
     }
@@ -475,3 +1010,44 @@ impl Debug for Control {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csr_writes() {
+        let inst = |funct3: u8, rs1: u32| {
+            ((csr::MSCRATCH as u32) << 20) | (rs1 << 15) | ((funct3 as u32) << 12) | 0b1110011
+        };
+        assert!(Control::csr_writes(
+            inst(funct3::system::CSRRW, 0),
+            funct3::system::CSRRW
+        ));
+        assert!(Control::csr_writes(
+            inst(funct3::system::CSRRWI, 0),
+            funct3::system::CSRRWI
+        ));
+        for funct3 in [
+            funct3::system::CSRRS,
+            funct3::system::CSRRC,
+            funct3::system::CSRRSI,
+            funct3::system::CSRRCI,
+        ] {
+            assert!(!Control::csr_writes(inst(funct3, 0), funct3));
+            // x2 or a zimm of 2, whatever the register holds
+            assert!(Control::csr_writes(inst(funct3, 2), funct3));
+        }
+    }
+
+    #[test]
+    fn test_interrupt_cause() {
+        let timer = interrupt::FLAG | interrupt::TIMER_M;
+        assert_eq!(Control::interrupt_cause(Word::from(timer)), Some(timer));
+        assert_eq!(Control::interrupt_cause(Word::zeros()), None);
+        assert_eq!(Control::interrupt_cause(Word::unknown()), None);
+        let mut partly = Word::from(timer);
+        partly.set_bit(0, None);
+        assert_eq!(Control::interrupt_cause(partly), None);
+    }
+}
@@ -0,0 +1,679 @@
+use crate::backend::util::event::{ByteEvent, WordEvent};
+use crate::backend::util::types::*;
+use crate::{send_byte, send_word};
+use crossbeam_channel::unbounded;
+use crossbeam_channel::Sender;
+use rsim_core::ack;
+use rsim_core::component::Component;
+use rsim_core::enq;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::stats::{Counter, Histogram};
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+const MSTATUS_MIE: u32 = 1 << 3;
+const MSTATUS_MPIE: u32 = 1 << 7;
+/// Only M-mode is implemented, so MPP always reads as M
+const MSTATUS_MPP: u32 = 0b11 << 11;
+/// Off, Initial, Clean or Dirty, the FP instructions and CSRs are illegal while it is Off
+const MSTATUS_FS: u32 = 0b11 << 13;
+/// Read only, set while FS is Dirty
+const MSTATUS_SD: u32 = 1 << 31;
+const MIE_MASK: u32 =
+    (1 << interrupt::SOFTWARE_M) | (1 << interrupt::TIMER_M) | (1 << interrupt::EXTERNAL_M);
+/// MXL is 32 bits, I, M, A, C and F
+const MISA: u32 = (0b01 << 30) | (1 << 12) | (1 << 8) | (1 << 5) | (1 << 2) | 1;
+const FCSR_FFLAGS: u32 = 0x1F;
+const FCSR_FRM: u32 = 0b111 << 5;
+
+/// The machine mode CSRs that hold a value of their own, mcycle is kept as an offset from the
+/// simulator's cycle and minstret counts the `retire` pulses, `None` once an unknown value was written
+#[derive(Debug, Clone, Copy)]
+pub struct Csrs {
+    pub mstatus: Word,
+    pub mie: Word,
+    pub mtvec: Word,
+    pub mscratch: Word,
+    pub mepc: Word,
+    pub mcause: Word,
+    pub mtval: Word,
+    /// frm in bits 7:5, fflags in 4:0
+    pub fcsr: Word,
+    mcycle_offset: Option<u64>,
+    minstret: Option<u64>,
+}
+
+impl Default for Csrs {
+    fn default() -> Self {
+        Csrs {
+            mstatus: Word::from(MSTATUS_MPP),
+            mie: Word::zeros(),
+            mtvec: Word::zeros(),
+            mscratch: Word::zeros(),
+            mepc: Word::zeros(),
+            mcause: Word::zeros(),
+            mtval: Word::zeros(),
+            fcsr: Word::from((rounding_mode::RNE as u32) << 5),
+            mcycle_offset: Some(0),
+            minstret: Some(0),
+        }
+    }
+}
+
+/// Low half of a 64 bit counter
+fn counter_low(counter: Option<u64>) -> Word {
+    counter.map_or(Word::unknown(), |counter| Word::from(counter as u32))
+}
+
+/// High half of a 64 bit counter
+fn counter_high(counter: Option<u64>) -> Word {
+    counter.map_or(Word::unknown(), |counter| {
+        Word::from((counter >> 32) as u32)
+    })
+}
+
+/// New offset so that the counter reads as `counter` with one of its halves replaced by `value`
+fn counter_offset(counter: Option<u64>, base: u64, value: Word, high: bool) -> Option<u64> {
+    let value = Into::<Option<u32>>::into(value)? as u64;
+    let counter = counter?;
+    let counter = if high {
+        (counter & 0xFFFFFFFF) | (value << 32)
+    } else {
+        (counter & !0xFFFFFFFF) | value
+    };
+    Some(counter.wrapping_sub(base))
+}
+
+/// The CSR file, reads are combinational, writes happen on the clock edge.
+///
+/// `op` is one of `csr_op`, the new value is computed from the old one and `src`.
+/// `Control` sends `csr_op::READ` for CSRRS and CSRRC with rs1 of x0 or a zimm of 0, which do not write.
+/// Unimplemented CSRs read as unknown and ignore writes, so do writes to read only CSRs,
+/// `Control` raises an illegal instruction before either can happen.
+///
+/// `trap` saves `pc`, `cause` and `tval` into mepc, mcause and mtval and disables interrupts,
+/// `mret` enables them again. The PC is redirected through `PcMux` with the mtvec and mepc outputs.
+///
+/// mip is read only and follows the `mtip` and `msip` lines of the CLINT. `interrupt` is the mcause
+/// of the interrupt `Control` should take, zero if none is both pending and enabled.
+/// The cycles from that point to the trap are sampled in `csr_file.interrupt_latency`.
+///
+/// `accrue` ORs the exception flags in `fflags` into fcsr, `frm` is the dynamic rounding mode for `Control`.
+/// mstatus.FS resets to Off and goes to Dirty when `fp_dirty` is set or fcsr is written, `fs` tells
+/// `Control` whether FP is on.
+///
+/// minstret goes up on the clock edge an instruction ends with `retire`, unless that instruction
+/// wrote minstret itself.
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["load", "Byte"],
+        ["op", "Byte"],
+        ["addr", "CsrAddr"],
+        ["src", "Word"],
+        ["trap", "Byte"],
+        ["mret", "Byte"],
+        ["cause", "Word"],
+        ["tval", "Word"],
+        ["pc", "Word"],
+        ["mtip", "Byte"],
+        ["msip", "Byte"],
+        ["fflags", "Byte"],
+        ["accrue", "Byte"],
+        ["retire", "Byte"],
+        ["fp_dirty", "Byte"]
+    ],
+    "output": [
+        ["out_regfile_mux", "Word"],
+        ["mtvec_pc_mux", "Word"],
+        ["mepc_pc_mux", "Word"],
+        ["interrupt", "Word"],
+        ["frm", "Byte"],
+        ["fs", "Byte"]
+    ],
+    "clock": true
+}
+})]
+pub struct CsrFile {
+    pub csrs: Csrs,
+    /// The cycle the current interrupt became pending and enabled
+    pending_since: Option<u64>,
+    interrupts: Counter,
+    interrupt_latency: Histogram,
+}
+
+impl CsrFile {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        load_receiver: Input,
+        op_receiver: Input,
+        addr_receiver: Input,
+        src_receiver: Input,
+        trap_receiver: Input,
+        mret_receiver: Input,
+        cause_receiver: Input,
+        tval_receiver: Input,
+        pc_receiver: Input,
+        mtip_receiver: Input,
+        msip_receiver: Input,
+        fflags_receiver: Input,
+        accrue_receiver: Input,
+        retire_receiver: Input,
+        fp_dirty_receiver: Input,
+        out_regfile_mux: Output,
+        mtvec_pc_mux: Output,
+        mepc_pc_mux: Output,
+        interrupt: Output,
+        frm: Output,
+        fs: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        let interrupts = sim_manager.get_stats().counter("csr_file", "interrupts");
+        let interrupt_latency =
+            sim_manager
+                .get_stats()
+                .histogram("csr_file", "interrupt_latency", 1, 32);
+        CsrFile {
+            csrs: Default::default(),
+            pending_since: None,
+            interrupts,
+            interrupt_latency,
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            load_receiver,
+            load: Default::default(),
+            load_old: Default::default(),
+            op_receiver,
+            op: Default::default(),
+            op_old: Default::default(),
+            addr_receiver,
+            addr: Default::default(),
+            addr_old: Default::default(),
+            src_receiver,
+            src: Default::default(),
+            src_old: Default::default(),
+            trap_receiver,
+            trap: Default::default(),
+            trap_old: Default::default(),
+            mret_receiver,
+            mret: Default::default(),
+            mret_old: Default::default(),
+            cause_receiver,
+            cause: Default::default(),
+            cause_old: Default::default(),
+            tval_receiver,
+            tval: Default::default(),
+            tval_old: Default::default(),
+            pc_receiver,
+            pc: Default::default(),
+            pc_old: Default::default(),
+            mtip_receiver,
+            mtip: Default::default(),
+            mtip_old: Default::default(),
+            msip_receiver,
+            msip: Default::default(),
+            msip_old: Default::default(),
+            fflags_receiver,
+            fflags: Default::default(),
+            fflags_old: Default::default(),
+            accrue_receiver,
+            accrue: Default::default(),
+            accrue_old: Default::default(),
+            retire_receiver,
+            retire: Default::default(),
+            retire_old: Default::default(),
+            fp_dirty_receiver,
+            fp_dirty: Default::default(),
+            fp_dirty_old: Default::default(),
+            out_regfile_mux,
+            mtvec_pc_mux,
+            mepc_pc_mux,
+            interrupt,
+            frm,
+            fs,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.csrs = Default::default();
+        self.pending_since = None;
+    }
+
+    fn poll_impl(&mut self) {}
+
+    fn cycle(&self) -> u64 {
+        self.sim_manager.get_curr_cycle() as u64
+    }
+
+    fn mcycle(&self) -> Option<u64> {
+        self.csrs
+            .mcycle_offset
+            .map(|offset| self.cycle().wrapping_add(offset))
+    }
+
+    fn minstret(&self) -> Option<u64> {
+        self.csrs.minstret
+    }
+
+    /// Counts one more retired instruction, `Core` calls it for the ECALLs it serves without the FSM
+    pub fn retire_instruction(&mut self) {
+        self.csrs.minstret = self.csrs.minstret.map(|minstret| minstret.wrapping_add(1));
+    }
+
+    /// Whether `addr` is one of the CSRs above, accessing any other one is an illegal instruction
+    pub fn is_implemented(addr: u16) -> bool {
+        matches!(
+            addr,
+            csr::FFLAGS
+                | csr::FRM
+                | csr::FCSR
+                | csr::MSTATUS
+                | csr::MISA
+                | csr::MIE
+                | csr::MTVEC
+                | csr::MSCRATCH
+                | csr::MEPC
+                | csr::MCAUSE
+                | csr::MTVAL
+                | csr::MIP
+                | csr::MCYCLE
+                | csr::MCYCLEH
+                | csr::MINSTRET
+                | csr::MINSTRETH
+                | csr::CYCLE
+                | csr::CYCLEH
+                | csr::INSTRET
+                | csr::INSTRETH
+                | csr::MVENDORID
+                | csr::MARCHID
+                | csr::MIMPID
+                | csr::MHARTID
+        )
+    }
+
+    /// The top two address bits are 0b11 for read only CSRs
+    pub fn is_read_only(addr: u16) -> bool {
+        addr >> 10 == 0b11
+    }
+
+    /// fflags, frm and fcsr, which need mstatus.FS on
+    pub fn is_fp(addr: u16) -> bool {
+        matches!(addr, csr::FFLAGS | csr::FRM | csr::FCSR)
+    }
+
+    /// FS to Dirty, which sets SD as well
+    fn set_fp_dirty(&mut self) {
+        self.csrs.mstatus |= Word::from(MSTATUS_FS | MSTATUS_SD);
+    }
+
+    /// SD follows FS, it is set while FS is Dirty
+    fn update_sd(&mut self) {
+        let (low, high) = (self.csrs.mstatus.get_bit(13), self.csrs.mstatus.get_bit(14));
+        let sd = match (low, high) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        };
+        self.csrs.mstatus.set_bit(31, sd);
+    }
+
+    pub fn read(&self, addr: u16) -> Word {
+        match addr {
+            csr::FFLAGS => self.csrs.fcsr & Word::from(FCSR_FFLAGS),
+            csr::FRM => (self.csrs.fcsr & Word::from(FCSR_FRM)) >> Word::from(5u32),
+            csr::FCSR => self.csrs.fcsr,
+            csr::MSTATUS => self.csrs.mstatus,
+            csr::MISA => Word::from(MISA),
+            csr::MIE => self.csrs.mie,
+            csr::MTVEC => self.csrs.mtvec,
+            csr::MSCRATCH => self.csrs.mscratch,
+            csr::MEPC => self.csrs.mepc,
+            csr::MCAUSE => self.csrs.mcause,
+            csr::MTVAL => self.csrs.mtval,
+            csr::MIP => self.mip(),
+            csr::MCYCLE | csr::CYCLE => counter_low(self.mcycle()),
+            csr::MCYCLEH | csr::CYCLEH => counter_high(self.mcycle()),
+            csr::MINSTRET | csr::INSTRET => counter_low(self.minstret()),
+            csr::MINSTRETH | csr::INSTRETH => counter_high(self.minstret()),
+            csr::MVENDORID | csr::MARCHID | csr::MIMPID | csr::MHARTID => Word::zeros(),
+            _ => Word::unknown(),
+        }
+    }
+
+    /// Keeps the read only bits as they are
+    pub fn write(&mut self, addr: u16, value: Word) {
+        if Self::is_fp(addr) {
+            self.set_fp_dirty();
+        }
+        match addr {
+            csr::FFLAGS => {
+                self.csrs.fcsr =
+                    (self.csrs.fcsr & Word::from(FCSR_FRM)) | (value & Word::from(FCSR_FFLAGS))
+            }
+            csr::FRM => {
+                self.csrs.fcsr = (self.csrs.fcsr & Word::from(FCSR_FFLAGS))
+                    | ((value << Word::from(5u32)) & Word::from(FCSR_FRM))
+            }
+            csr::FCSR => self.csrs.fcsr = value & Word::from(FCSR_FRM | FCSR_FFLAGS),
+            csr::MSTATUS => {
+                self.csrs.mstatus = (value & Word::from(MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_FS))
+                    | Word::from(MSTATUS_MPP);
+                self.update_sd();
+            }
+            csr::MIE => self.csrs.mie = value & Word::from(MIE_MASK),
+            // Direct and vectored are the only modes
+            csr::MTVEC => self.csrs.mtvec = value & Word::from(!0b10u32),
+            csr::MSCRATCH => self.csrs.mscratch = value,
+            // instructions are 2 byte aligned with C
+            csr::MEPC => self.csrs.mepc = value & Word::from(!0b1u32),
+            csr::MCAUSE => self.csrs.mcause = value,
+            csr::MTVAL => self.csrs.mtval = value,
+            csr::MCYCLE | csr::MCYCLEH => {
+                self.csrs.mcycle_offset =
+                    counter_offset(self.mcycle(), self.cycle(), value, addr == csr::MCYCLEH)
+            }
+            csr::MINSTRET | csr::MINSTRETH => {
+                self.csrs.minstret =
+                    counter_offset(self.minstret(), 0, value, addr == csr::MINSTRETH)
+            }
+            _ => {}
+        }
+    }
+
+    fn mip(&self) -> Word {
+        let mut mip = Word::zeros();
+        mip.set_bit(interrupt::SOFTWARE_M as usize, self.msip.get_bit(0));
+        mip.set_bit(interrupt::TIMER_M as usize, self.mtip.get_bit(0));
+        mip
+    }
+
+    /// External interrupts come first, then software and timer ones
+    fn pending_interrupt(&self) -> Word {
+        let (Some(mstatus), Some(mie), Some(mip)) = (
+            Into::<Option<u32>>::into(self.csrs.mstatus),
+            Into::<Option<u32>>::into(self.csrs.mie),
+            Into::<Option<u32>>::into(self.mip()),
+        ) else {
+            return Word::zeros();
+        };
+        if mstatus & MSTATUS_MIE == 0 {
+            return Word::zeros();
+        }
+        [
+            interrupt::EXTERNAL_M,
+            interrupt::SOFTWARE_M,
+            interrupt::TIMER_M,
+        ]
+        .into_iter()
+        .find(|code| (mip & mie) >> code & 0x1 == 0x1)
+        .map_or(Word::zeros(), |code| Word::from(interrupt::FLAG | code))
+    }
+
+    fn is_interrupt(cause: Word) -> bool {
+        cause.get_bit(31) == Some(true)
+    }
+
+    /// MPIE takes MIE and MIE is cleared, MPP is always M
+    fn take_trap(&mut self) {
+        let mie = self.csrs.mstatus & Word::from(MSTATUS_MIE);
+        let fs = self.csrs.mstatus & Word::from(MSTATUS_FS | MSTATUS_SD);
+        self.csrs.mstatus = (mie << Word::from(4u32)) | fs | Word::from(MSTATUS_MPP);
+        self.write(csr::MEPC, self.pc);
+        self.csrs.mcause = self.cause;
+        self.csrs.mtval = self.tval;
+    }
+
+    /// MIE takes MPIE and MPIE is set
+    fn take_mret(&mut self) {
+        let mpie = self.csrs.mstatus & Word::from(MSTATUS_MPIE);
+        let fs = self.csrs.mstatus & Word::from(MSTATUS_FS | MSTATUS_SD);
+        self.csrs.mstatus =
+            (mpie >> Word::from(4u32)) | fs | Word::from(MSTATUS_MPIE | MSTATUS_MPP);
+    }
+
+    fn on_clock(&mut self) {
+        let cycle = self.cycle();
+        if self.trap.is_something_nonzero() && Self::is_interrupt(self.cause) {
+            self.interrupts.inc();
+            if let Some(pending_since) = self.pending_since.take() {
+                self.interrupt_latency.sample(cycle - pending_since);
+            }
+        } else if self.pending_interrupt().is_zero() {
+            self.pending_since = None;
+        } else if self.pending_since.is_none() {
+            self.pending_since = Some(cycle);
+        }
+
+        if self.fp_dirty.is_something_nonzero() {
+            self.set_fp_dirty();
+        }
+        if self.accrue.is_something_nonzero() {
+            let mut flags = Word::zeros();
+            flags.set_byte(0, self.fflags);
+            self.csrs.fcsr |= flags & Word::from(FCSR_FFLAGS);
+        }
+
+        if self.trap.is_something_nonzero() {
+            self.take_trap();
+            return;
+        }
+        let written = if self.mret.is_something_nonzero() {
+            self.take_mret();
+            None
+        } else {
+            self.load_csr()
+        };
+        if self.retire.is_something_nonzero()
+            && !matches!(written, Some(csr::MINSTRET | csr::MINSTRETH))
+        {
+            self.retire_instruction();
+        }
+    }
+
+    /// The CSR instruction's write, returns the address written to
+    fn load_csr(&mut self) -> Option<u16> {
+        if !self.load.is_something_nonzero() {
+            return None;
+        }
+        let addr = Into::<Option<u16>>::into(self.addr)?;
+        let old = self.read(addr);
+        let new = match self.op.into() {
+            Some(csr_op::RW) => self.src,
+            Some(csr_op::RS) => old | self.src,
+            Some(csr_op::RC) => old & !self.src,
+            _ => return None,
+        };
+        self.write(addr, new);
+        Some(addr)
+    }
+
+    fn on_comb(&mut self) {
+        let out = Into::<Option<u16>>::into(self.addr)
+            .map(|addr| self.read(addr))
+            .unwrap_or_default();
+        send_word!(self, self.out_regfile_mux, out);
+        // Exceptions always go to the base, vectored mode only applies to interrupts
+        let base = self.csrs.mtvec & Word::from(!0b11u32);
+        let mtvec = if self.csrs.mtvec.get_bit(0) == Some(true) && Self::is_interrupt(self.cause) {
+            base + ((self.cause & Word::from(!interrupt::FLAG)) << Word::from(2u32))
+        } else {
+            base
+        };
+        send_word!(self, self.mtvec_pc_mux, mtvec);
+        send_word!(self, self.mepc_pc_mux, self.csrs.mepc);
+        send_word!(self, self.interrupt, self.pending_interrupt());
+        send_byte!(self, self.frm, self.read(csr::FRM).get_byte(0));
+        send_byte!(
+            self,
+            self.fs,
+            ((self.csrs.mstatus & Word::from(MSTATUS_FS)) >> Word::from(13u32)).get_byte(0)
+        );
+    }
+}
+
+impl Debug for CsrFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CsrFile: {{load: {:?}, op: {:?}, addr: {:?}, src: {:?}, trap: {:?}, mret: {:?}, csrs: {:?}}}",
+            self.load, self.op, self.addr, self.src, self.trap, self.mret, self.csrs
+        )
+    }
+}
+
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["rs1", "Word"],
+        ["zimm", "Word"],
+        ["sel", "Byte"]
+    ],
+    "output": [
+        ["out", "Word"]
+    ]
+}
+})]
+pub struct CsrMux {}
+
+impl CsrMux {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        rs1_receiver: Input,
+        zimm_receiver: Input,
+        sel_receiver: Input,
+        out: Output,
+    ) -> Self {
+        CsrMux {
+            component_id,
+            sim_manager,
+            ack_sender,
+            rs1_receiver,
+            rs1: Default::default(),
+            rs1_old: Default::default(),
+            zimm_receiver,
+            zimm: Default::default(),
+            zimm_old: Default::default(),
+            sel_receiver,
+            sel: Default::default(),
+            sel_old: Default::default(),
+            out,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {}
+
+    fn poll_impl(&mut self) {}
+
+    fn on_comb(&mut self) {
+        let out = match self.sel.into() {
+            Some(mux_sel::csr::RS1_OUT) => self.rs1,
+            Some(mux_sel::csr::ZIMM) => self.zimm,
+            _ => Word::unknown(),
+        };
+        send_word!(self, self.out, out);
+    }
+}
+
+impl Debug for CsrMux {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CsrMux: {{rs1: {:?}, zimm: {:?}, sel: {:?}}}",
+            self.rs1, self.zimm, self.sel
+        )
+    }
+}
+
+/// Picks what goes into mtval when an exception is taken
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["pc", "Word"],
+        ["ir", "Word"],
+        ["mar", "Word"],
+        ["sel", "Byte"]
+    ],
+    "output": [
+        ["out", "Word"]
+    ]
+}
+})]
+pub struct TvalMux {}
+
+impl TvalMux {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        pc_receiver: Input,
+        ir_receiver: Input,
+        mar_receiver: Input,
+        sel_receiver: Input,
+        out: Output,
+    ) -> Self {
+        TvalMux {
+            component_id,
+            sim_manager,
+            ack_sender,
+            pc_receiver,
+            pc: Default::default(),
+            pc_old: Default::default(),
+            ir_receiver,
+            ir: Default::default(),
+            ir_old: Default::default(),
+            mar_receiver,
+            mar: Default::default(),
+            mar_old: Default::default(),
+            sel_receiver,
+            sel: Default::default(),
+            sel_old: Default::default(),
+            out,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {}
+
+    fn poll_impl(&mut self) {}
+
+    fn on_comb(&mut self) {
+        let out = match self.sel.into() {
+            Some(mux_sel::tval::ZERO) => Word::zeros(),
+            Some(mux_sel::tval::PC_OUT) => self.pc,
+            Some(mux_sel::tval::IR_OUT) => self.ir,
+            Some(mux_sel::tval::MAR_OUT) => self.mar,
+            _ => Word::unknown(),
+        };
+        send_word!(self, self.out, out);
+    }
+}
+
+impl Debug for TvalMux {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TvalMux: {{pc: {:?}, ir: {:?}, mar: {:?}, sel: {:?}}}",
+            self.pc, self.ir, self.mar, self.sel
+        )
+    }
+}
@@ -1,4 +1,5 @@
 use crate::backend::util::event::WordEvent;
+use crate::backend::util::types::mux_sel;
 use crate::backend::util::types::Byte;
 use crate::backend::util::types::Word;
 use crate::send_word;
@@ -97,3 +98,72 @@ impl Debug for DataOut {
         )
     }
 }
+
+/// Picks what gets stored, rs2 of the integer register file or of the FP one for FSW
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["rs2", "Word"],
+        ["fp_rs2", "Word"],
+        ["sel", "Byte"]
+    ],
+    "output": [
+        ["out", "Word"]
+    ]
+}
+})]
+pub struct DataOutMux {}
+
+impl DataOutMux {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        rs2_receiver: Input,
+        fp_rs2_receiver: Input,
+        sel_receiver: Input,
+        out: Output,
+    ) -> Self {
+        DataOutMux {
+            component_id,
+            sim_manager,
+            ack_sender,
+            rs2_receiver,
+            rs2: Default::default(),
+            rs2_old: Default::default(),
+            fp_rs2_receiver,
+            fp_rs2: Default::default(),
+            fp_rs2_old: Default::default(),
+            sel_receiver,
+            sel: Default::default(),
+            sel_old: Default::default(),
+            out,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {}
+
+    fn poll_impl(&mut self) {}
+
+    fn on_comb(&mut self) {
+        let out = match self.sel.into() {
+            Some(mux_sel::data_out::RS2_OUT) => self.rs2,
+            Some(mux_sel::data_out::FP_RS2_OUT) => self.fp_rs2,
+            _ => Word::unknown(),
+        };
+
+        send_word!(self, self.out, out);
+    }
+}
+
+impl Debug for DataOutMux {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DataOutMux: {{rs2: {:?}, fp_rs2: {:?}, sel: {:?}}}",
+            self.rs2, self.fp_rs2, self.sel
+        )
+    }
+}
@@ -0,0 +1,195 @@
+use crate::backend::util::event::WordEvent;
+use crate::backend::util::types::*;
+use crate::send_word;
+use crossbeam_channel::{unbounded, Sender};
+use rsim_core::component::Component;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_core::{ack, enq};
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// The RV32F registerfile, 32 4-byte registers holding single-precision bits. Unlike x0, f0 is a
+/// regular register
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["rs1_idx", "RegIdx"],
+        ["rs2_idx", "RegIdx"],
+        ["rs3_idx", "RegIdx"],
+        ["rd_wr", "Byte"],
+        ["rd_idx", "RegIdx"],
+        ["rd_data", "Word"]
+    ],
+    "output": [
+        ["rs1_data_fpu", "Word"],
+        ["rs2_data_fpu", "Word"],
+        ["rs3_data_fpu", "Word"],
+        ["rs2_data_data_out_mux", "Word"]
+    ],
+    "clock": true
+}
+})]
+pub struct FpRegFile {
+    pub registers: [Word; 32],
+}
+
+impl FpRegFile {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        rs1_idx_receiver: Input,
+        rs2_idx_receiver: Input,
+        rs3_idx_receiver: Input,
+        rd_wr_receiver: Input,
+        rd_idx_receiver: Input,
+        rd_data_receiver: Input,
+        rs1_data_fpu: Output,
+        rs2_data_fpu: Output,
+        rs3_data_fpu: Output,
+        rs2_data_data_out_mux: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        FpRegFile {
+            registers: [Word::zeros(); 32],
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            rs1_idx_receiver,
+            rs1_idx: Default::default(),
+            rs1_idx_old: Default::default(),
+            rs2_idx_receiver,
+            rs2_idx: Default::default(),
+            rs2_idx_old: Default::default(),
+            rs3_idx_receiver,
+            rs3_idx: Default::default(),
+            rs3_idx_old: Default::default(),
+            rd_wr_receiver,
+            rd_wr: Default::default(),
+            rd_wr_old: Default::default(),
+            rd_idx_receiver,
+            rd_idx: Default::default(),
+            rd_idx_old: Default::default(),
+            rd_data_receiver,
+            rd_data: Default::default(),
+            rd_data_old: Default::default(),
+            rs1_data_fpu,
+            rs2_data_fpu,
+            rs3_data_fpu,
+            rs2_data_data_out_mux,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.registers = [Word::unknown(); 32];
+    }
+
+    fn poll_impl(&mut self) {}
+
+    pub fn read(&self, index: RegIdx) -> Word {
+        Into::<Option<u8>>::into(index)
+            .map(|idx| self.registers[idx as usize])
+            .unwrap_or_default()
+    }
+
+    fn on_clock(&mut self) {
+        if self.rd_wr.is_something_nonzero() {
+            if let Some(idx) = Into::<Option<u8>>::into(self.rd_idx) {
+                self.registers[idx as usize] = self.rd_data;
+            }
+        }
+    }
+
+    fn on_comb(&mut self) {
+        send_word!(self, self.rs1_data_fpu, self.read(self.rs1_idx));
+        send_word!(self, self.rs2_data_fpu, self.read(self.rs2_idx));
+        send_word!(self, self.rs3_data_fpu, self.read(self.rs3_idx));
+        send_word!(self, self.rs2_data_data_out_mux, self.read(self.rs2_idx));
+    }
+}
+
+impl Debug for FpRegFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FpRegFile: {{rd_wr: {:?}, rd_idx: {:?}, rd_data: {:?}, rs1_idx: {:?}, rs2_idx: {:?}, rs3_idx: {:?}, data: {:?}}}", self.rd_wr, self.rd_idx, self.rd_data, self.rs1_idx, self.rs2_idx, self.rs3_idx, self.registers)
+    }
+}
+
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["mdr", "Word"],
+        ["fpu", "Word"],
+        ["sel", "Byte"]
+    ],
+    "output": [
+        ["out", "Word"]
+    ]
+}
+})]
+pub struct FpRegFileMux {}
+
+impl FpRegFileMux {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        mdr_receiver: Input,
+        fpu_receiver: Input,
+        sel_receiver: Input,
+        out: Output,
+    ) -> Self {
+        FpRegFileMux {
+            component_id,
+            sim_manager,
+            ack_sender,
+            mdr_receiver,
+            mdr: Default::default(),
+            mdr_old: Default::default(),
+            fpu_receiver,
+            fpu: Default::default(),
+            fpu_old: Default::default(),
+            sel_receiver,
+            sel: Default::default(),
+            sel_old: Default::default(),
+            out,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {}
+
+    fn poll_impl(&mut self) {}
+
+    fn on_comb(&mut self) {
+        let out = match self.sel.into() {
+            Some(mux_sel::fp_regfile::MDR_OUT) => self.mdr,
+            Some(mux_sel::fp_regfile::FPU_OUT) => self.fpu,
+            _ => Word::unknown(),
+        };
+
+        send_word!(self, self.out, out);
+    }
+}
+
+impl Debug for FpRegFileMux {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "FpRegFileMux: {{mdr: {:?}, fpu: {:?}, sel: {:?}}}",
+            self.mdr, self.fpu, self.sel
+        )
+    }
+}
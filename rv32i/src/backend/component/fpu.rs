@@ -0,0 +1,284 @@
+use crate::backend::util::event::{ByteEvent, WordEvent};
+use crate::backend::util::float;
+use crate::backend::util::types::*;
+use crate::{send_byte, send_word};
+use crossbeam_channel::unbounded;
+use crossbeam_channel::Sender;
+use rsim_core::ack;
+use rsim_core::component::Component;
+use rsim_core::enq;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Edges each kind of operation takes in `Fpu`, at least 1.
+/// Sign injection, min/max, compares, conversions and moves take `add`
+#[derive(Debug, Clone, Copy)]
+pub struct FpuLatency {
+    pub add: u64,
+    pub mul: u64,
+    pub fma: u64,
+    pub div: u64,
+    pub sqrt: u64,
+}
+
+impl Default for FpuLatency {
+    fn default() -> Self {
+        FpuLatency {
+            add: 3,
+            mul: 4,
+            fma: 5,
+            div: 16,
+            sqrt: 20,
+        }
+    }
+}
+
+/// The ones that go to the integer register file
+pub fn writes_int(op: u8) -> bool {
+    matches!(
+        op,
+        fpu_op::CVT_W_S
+            | fpu_op::CVT_WU_S
+            | fpu_op::MV_X_W
+            | fpu_op::EQ
+            | fpu_op::LT
+            | fpu_op::LE
+            | fpu_op::CLASS
+    )
+}
+
+/// The ones with an rm field, the others use funct3 to tell variants apart
+pub fn is_rounded(op: u8) -> bool {
+    matches!(
+        op,
+        fpu_op::ADD
+            | fpu_op::SUB
+            | fpu_op::MUL
+            | fpu_op::DIV
+            | fpu_op::SQRT
+            | fpu_op::MADD
+            | fpu_op::MSUB
+            | fpu_op::NMSUB
+            | fpu_op::NMADD
+            | fpu_op::CVT_W_S
+            | fpu_op::CVT_WU_S
+            | fpu_op::CVT_S_W
+            | fpu_op::CVT_S_WU
+    )
+}
+
+/// The RV32F unit, `op` is one of `fpu_op` and `rm` is the rounding mode, frm already applied.
+///
+/// `a`, `b` and `c` are rs1, rs2 and rs3 of the FP register file, `int_a` is rs1 of the integer one.
+/// Like `MulDiv`, the operands are latched on the edge `start` is seen and `done` is high for a single
+/// cycle once the latency is over. The result is on both outputs, `flags` holds the fflags it raised.
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["a", "Word"],
+        ["b", "Word"],
+        ["c", "Word"],
+        ["int_a", "Word"],
+        ["op", "Byte"],
+        ["rm", "Byte"],
+        ["start", "Byte"]
+    ],
+    "output": [
+        ["out_fp_regfile_mux", "Word"],
+        ["out_regfile_mux", "Word"],
+        ["flags", "Byte"],
+        ["done", "Byte"]
+    ],
+    "clock": true
+}
+})]
+pub struct Fpu {
+    pub latency: FpuLatency,
+    result: Word,
+    result_flags: Byte,
+    /// Edges left until the result is ready, `None` while idle
+    remaining: Option<u64>,
+    done_inner: bool,
+}
+
+impl Fpu {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        a_receiver: Input,
+        b_receiver: Input,
+        c_receiver: Input,
+        int_a_receiver: Input,
+        op_receiver: Input,
+        rm_receiver: Input,
+        start_receiver: Input,
+        out_fp_regfile_mux: Output,
+        out_regfile_mux: Output,
+        flags: Output,
+        done: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        Fpu {
+            latency: Default::default(),
+            result: Word::unknown(),
+            result_flags: Byte::unknown(),
+            remaining: None,
+            done_inner: false,
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            a_receiver,
+            a: Default::default(),
+            a_old: Default::default(),
+            b_receiver,
+            b: Default::default(),
+            b_old: Default::default(),
+            c_receiver,
+            c: Default::default(),
+            c_old: Default::default(),
+            int_a_receiver,
+            int_a: Default::default(),
+            int_a_old: Default::default(),
+            op_receiver,
+            op: Default::default(),
+            op_old: Default::default(),
+            rm_receiver,
+            rm: Default::default(),
+            rm_old: Default::default(),
+            start_receiver,
+            start: Default::default(),
+            start_old: Default::default(),
+            out_fp_regfile_mux,
+            out_regfile_mux,
+            flags,
+            done,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.result = Word::unknown();
+        self.result_flags = Byte::unknown();
+        self.remaining = None;
+        self.done_inner = false;
+    }
+
+    fn poll_impl(&mut self) {}
+
+    /// Unknown, flags included, if an operand it needs has unknown bits
+    fn compute(&self) -> (Word, Byte) {
+        let (Some(op), Some(rm)) = (self.op.into(), self.rm.into()) else {
+            return (Word::unknown(), Byte::unknown());
+        };
+        let a: Option<u32> = self.a.into();
+        let b: Option<u32> = self.b.into();
+        let c: Option<u32> = self.c.into();
+        let int_a: Option<u32> = self.int_a.into();
+        let fma = |negate_product, negate_c| {
+            a.zip(b)
+                .zip(c)
+                .map(|((a, b), c)| float::fma(a, b, c, negate_product, negate_c, rm))
+        };
+        let result = match op {
+            fpu_op::ADD => a.zip(b).map(|(a, b)| float::add(a, b, rm)),
+            fpu_op::SUB => a.zip(b).map(|(a, b)| float::sub(a, b, rm)),
+            fpu_op::MUL => a.zip(b).map(|(a, b)| float::mul(a, b, rm)),
+            fpu_op::DIV => a.zip(b).map(|(a, b)| float::div(a, b, rm)),
+            fpu_op::SQRT => a.map(|a| float::sqrt(a, rm)),
+            fpu_op::MADD => fma(false, false),
+            fpu_op::MSUB => fma(false, true),
+            fpu_op::NMSUB => fma(true, false),
+            fpu_op::NMADD => fma(true, true),
+            fpu_op::SGNJ => a
+                .zip(b)
+                .map(|(a, b)| (float::sign_inject(a, b, false, false), 0)),
+            fpu_op::SGNJN => a
+                .zip(b)
+                .map(|(a, b)| (float::sign_inject(a, b, true, false), 0)),
+            fpu_op::SGNJX => a
+                .zip(b)
+                .map(|(a, b)| (float::sign_inject(a, b, false, true), 0)),
+            fpu_op::MIN => a.zip(b).map(|(a, b)| float::min_max(a, b, false)),
+            fpu_op::MAX => a.zip(b).map(|(a, b)| float::min_max(a, b, true)),
+            fpu_op::CVT_W_S => a.map(|a| float::to_int(a, true, rm)),
+            fpu_op::CVT_WU_S => a.map(|a| float::to_int(a, false, rm)),
+            fpu_op::MV_X_W => a.map(|a| (a, 0)),
+            fpu_op::EQ => a.zip(b).map(|(a, b)| float::eq(a, b)),
+            fpu_op::LT => a.zip(b).map(|(a, b)| float::lt(a, b, false)),
+            fpu_op::LE => a.zip(b).map(|(a, b)| float::lt(a, b, true)),
+            fpu_op::CLASS => a.map(|a| (float::class(a), 0)),
+            fpu_op::CVT_S_W => int_a.map(|int_a| float::from_int(int_a, true, rm)),
+            fpu_op::CVT_S_WU => int_a.map(|int_a| float::from_int(int_a, false, rm)),
+            fpu_op::MV_W_X => int_a.map(|int_a| (int_a, 0)),
+            _ => None,
+        };
+        result.map_or((Word::unknown(), Byte::unknown()), |(result, flags)| {
+            (Word::from(result), Byte::from(flags))
+        })
+    }
+
+    fn op_latency(&self) -> u64 {
+        let latency = match self.op.into() {
+            Some(fpu_op::MUL) => self.latency.mul,
+            Some(fpu_op::MADD | fpu_op::MSUB | fpu_op::NMSUB | fpu_op::NMADD) => self.latency.fma,
+            Some(fpu_op::DIV) => self.latency.div,
+            Some(fpu_op::SQRT) => self.latency.sqrt,
+            _ => self.latency.add,
+        };
+        latency.max(1)
+    }
+
+    fn on_clock(&mut self) {
+        // let go of the previous request before taking the next one
+        if self.done_inner {
+            self.done_inner = false;
+            return;
+        }
+        match self.remaining {
+            Some(remaining) if remaining > 1 => self.remaining = Some(remaining - 1),
+            Some(_) => {
+                self.remaining = None;
+                self.done_inner = true;
+            }
+            None if self.start.is_something_nonzero() => {
+                (self.result, self.result_flags) = self.compute();
+                if self.op_latency() > 1 {
+                    self.remaining = Some(self.op_latency() - 1);
+                } else {
+                    self.done_inner = true;
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn on_comb(&mut self) {
+        send_word!(self, self.out_fp_regfile_mux, self.result);
+        send_word!(self, self.out_regfile_mux, self.result);
+        send_byte!(self, self.flags, self.result_flags);
+        send_byte!(self, self.done, Byte::from(self.done_inner as u8));
+    }
+}
+
+impl Debug for Fpu {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Fpu: {{a: {:?}, b: {:?}, c: {:?}, int_a: {:?}, op: {:?}, rm: {:?}, remaining: {:?}, done: {}}}",
+            self.a, self.b, self.c, self.int_a, self.op, self.rm, self.remaining, self.done_inner
+        )
+    }
+}
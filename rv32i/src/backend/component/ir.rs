@@ -1,7 +1,8 @@
-use crate::backend::util::event::{ByteEvent, WordEvent};
-use crate::backend::util::helper::sign_extend;
-use crate::backend::util::types::Byte;
-use crate::backend::util::types::Word;
+use crate::backend::util::bits::Bits;
+use crate::backend::util::event::{BitsEvent, ByteEvent, WordEvent};
+use crate::backend::util::types::RegIdx;
+use crate::backend::util::types::{Byte, Word};
+use crate::send_bits;
 use crate::send_byte;
 use crate::send_word;
 use crossbeam_channel::{unbounded, Sender};
@@ -24,12 +25,13 @@ use std::sync::Arc;
 "port": {
     "input": [
         ["load", "Byte"],
-        ["data", "Word"]
+        ["data", "Word"],
+        ["raw", "Word"]
     ],
     "output": [
-        ["funct3", "Byte"],
-        ["funct7", "Byte"],
-        ["opcode", "Byte"],
+        ["funct3", "Funct3"],
+        ["funct7", "Funct7"],
+        ["opcode", "Opcode"],
         ["i_imm_alu_mux2", "Word"],
         ["i_imm_cmp_mux", "Word"],
         ["s_imm", "Word"],
@@ -37,15 +39,26 @@ use std::sync::Arc;
         ["u_imm_alu_mux2", "Word"],
         ["u_imm_regfile_mux", "Word"],
         ["j_imm", "Word"],
-        ["rs1", "Byte"],
-        ["rs2", "Byte"],
-        ["rd", "Byte"]
+        ["rs1", "RegIdx"],
+        ["rs2", "RegIdx"],
+        ["rd", "RegIdx"],
+        ["csr_addr", "CsrAddr"],
+        ["zimm", "Word"],
+        ["data_control", "Word"],
+        ["data_tval_mux", "Word"],
+        ["compressed", "Byte"],
+        ["fp_rs1", "RegIdx"],
+        ["fp_rs2", "RegIdx"],
+        ["fp_rs3", "RegIdx"],
+        ["fp_rd", "RegIdx"]
     ],
     "clock": true
 }
 })]
+/// `data_inner` is always a 32 bit instruction, `raw_inner` is what was fetched for it
 pub struct IR {
     pub data_inner: Word,
+    pub raw_inner: Word,
 }
 
 impl IR {
@@ -55,6 +68,7 @@ impl IR {
         ack_sender: Sender<EventId>,
         load_receiver: Input,
         data_receiver: Input,
+        raw_receiver: Input,
         funct3: Output,
         funct7: Output,
         opcode: Output,
@@ -68,11 +82,21 @@ impl IR {
         rs1: Output,
         rs2: Output,
         rd: Output,
+        csr_addr: Output,
+        zimm: Output,
+        data_control: Output,
+        data_tval_mux: Output,
+        compressed: Output,
+        fp_rs1: Output,
+        fp_rs2: Output,
+        fp_rs3: Output,
+        fp_rd: Output,
     ) -> Self {
         let clock_channel = unbounded();
 
         IR {
             data_inner: Default::default(),
+            raw_inner: Default::default(),
             component_id,
             sim_manager,
             ack_sender,
@@ -84,6 +108,9 @@ impl IR {
             data_receiver,
             data: Default::default(),
             data_old: Default::default(),
+            raw_receiver,
+            raw: Default::default(),
+            raw_old: Default::default(),
             funct3,
             funct7,
             opcode,
@@ -97,6 +124,15 @@ impl IR {
             rs1,
             rs2,
             rd,
+            csr_addr,
+            zimm,
+            data_control,
+            data_tval_mux,
+            compressed,
+            fp_rs1,
+            fp_rs2,
+            fp_rs3,
+            fp_rd,
         }
     }
 
@@ -109,72 +145,75 @@ impl IR {
     fn on_clock(&mut self) {
         if self.load.is_something_nonzero() {
             self.data_inner = self.data;
+            self.raw_inner = self.raw;
         }
     }
 
-    pub fn get_rd_idx(&self) -> Byte {
-        if let Some(inst) = Into::<Option<u32>>::into(self.data_inner) {
-            Byte::from(((inst >> 7) & 0x1F) as u8)
-        } else {
-            Byte::unknown()
-        }
+    fn inst(&self) -> Bits<32> {
+        Bits::from(self.data_inner)
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.raw_inner.get_bit(0) == Some(false) || self.raw_inner.get_bit(1) == Some(false)
+    }
+
+    pub fn get_rd_idx(&self) -> RegIdx {
+        self.inst().slice(7)
     }
 
     fn on_comb(&mut self) {
-        if let Some(inst) = Into::<Option<u32>>::into(self.data_inner) {
-            send_byte!(self, self.funct3, Byte::from(((inst >> 12) & 0b111) as u8));
-            send_byte!(
-                self,
-                self.funct7,
-                Byte::from(((inst >> 25) & 0b1111111) as u8)
-            );
-            send_byte!(self, self.opcode, Byte::from((inst & 0b1111111) as u8));
-            send_word!(self, self.i_imm_alu_mux2, sign_extend(inst >> 20, 11));
-            send_word!(self, self.i_imm_cmp_mux, sign_extend(inst >> 20, 11));
-            send_word!(
-                self,
-                self.s_imm,
-                sign_extend(
-                    (((inst >> 25) & 0b1111111) << 5) | ((inst >> 7) & 0b11111),
-                    11,
-                )
-            );
-            send_word!(
-                self,
-                self.b_imm,
-                sign_extend(
-                    (((inst >> 31) & 0b1) << 12)
-                        | (((inst >> 7) & 0b1) << 11)
-                        | (((inst >> 25) & 0b111111) << 5)
-                        | (((inst >> 8) & 0b1111) << 1),
-                    12,
-                )
-            );
-            send_word!(
-                self,
-                self.u_imm_alu_mux2,
-                Word::from(((inst >> 12) & 0xFFFFF) << 12)
-            );
-            send_word!(
-                self,
-                self.u_imm_regfile_mux,
-                Word::from(((inst >> 12) & 0xFFFFF) << 12)
-            );
-            send_word!(
-                self,
-                self.j_imm,
-                sign_extend(
-                    (((inst >> 31) & 0b1) << 20)
-                        | (((inst >> 12) & 0xFF) << 12)
-                        | (((inst >> 20) & 0b1) << 11)
-                        | (((inst >> 21) & 0x3FF) << 1),
-                    20,
-                )
-            );
-            send_byte!(self, self.rs1, Byte::from(((inst >> 15) & 0x1F) as u8));
-            send_byte!(self, self.rs2, Byte::from(((inst >> 20) & 0x1F) as u8));
-            send_byte!(self, self.rd, Byte::from(((inst >> 7) & 0x1F) as u8));
-        }
+        let inst = self.inst();
+        let i_imm = inst.slice::<12>(20).sext::<32>();
+        let s_imm = inst
+            .slice::<7>(25)
+            .concat::<5, 12>(inst.slice(7))
+            .sext::<32>();
+        let b_imm = inst
+            .slice::<1>(31)
+            .concat::<1, 2>(inst.slice(7))
+            .concat::<6, 8>(inst.slice(25))
+            .concat::<4, 12>(inst.slice(8))
+            .concat::<1, 13>(Bits::zeros())
+            .sext::<32>();
+        let u_imm = inst.slice::<20>(12).concat::<12, 32>(Bits::zeros());
+        let j_imm = inst
+            .slice::<1>(31)
+            .concat::<8, 9>(inst.slice(12))
+            .concat::<1, 10>(inst.slice(20))
+            .concat::<10, 20>(inst.slice(21))
+            .concat::<1, 21>(Bits::zeros())
+            .sext::<32>();
+
+        send_bits!(self, self.funct3, inst.slice::<3>(12));
+        send_bits!(self, self.funct7, inst.slice::<7>(25));
+        send_bits!(self, self.opcode, inst.slice::<7>(0));
+        send_word!(self, self.i_imm_alu_mux2, Word::from(i_imm));
+        send_word!(self, self.i_imm_cmp_mux, Word::from(i_imm));
+        send_word!(self, self.s_imm, Word::from(s_imm));
+        send_word!(self, self.b_imm, Word::from(b_imm));
+        send_word!(self, self.u_imm_alu_mux2, Word::from(u_imm));
+        send_word!(self, self.u_imm_regfile_mux, Word::from(u_imm));
+        send_word!(self, self.j_imm, Word::from(j_imm));
+        send_bits!(self, self.rs1, inst.slice::<5>(15));
+        send_bits!(self, self.rs2, inst.slice::<5>(20));
+        send_bits!(self, self.rd, inst.slice::<5>(7));
+        send_bits!(self, self.csr_addr, inst.slice::<12>(20));
+        send_word!(
+            self,
+            self.zimm,
+            Word::from(inst.slice::<5>(15).zext::<32>())
+        );
+        send_word!(self, self.data_control, self.data_inner);
+        send_word!(self, self.data_tval_mux, self.raw_inner);
+        send_byte!(
+            self,
+            self.compressed,
+            Byte::from(self.is_compressed() as u8)
+        );
+        send_bits!(self, self.fp_rs1, inst.slice::<5>(15));
+        send_bits!(self, self.fp_rs2, inst.slice::<5>(20));
+        send_bits!(self, self.fp_rs3, inst.slice::<5>(27));
+        send_bits!(self, self.fp_rd, inst.slice::<5>(7));
     }
 
     pub fn can_end(&self) -> bool {
@@ -29,7 +29,8 @@ use std::sync::Arc;
         ["out_control", "Word"],
         ["out_regfile_mux", "Word"],
         ["out_data_out", "Word"],
-        ["out_mem_ctl", "Word"]
+        ["out_mem_ctl", "Word"],
+        ["out_tval_mux", "Word"]
     ],
     "clock": true
 }
@@ -49,6 +50,7 @@ impl Mar {
         out_regfile_mux: Output,
         out_data_out: Output,
         out_mem_ctl: Output,
+        out_tval_mux: Output,
     ) -> Self {
         let clock_channel = unbounded();
         Mar {
@@ -68,6 +70,7 @@ impl Mar {
             out_regfile_mux,
             out_data_out,
             out_mem_ctl,
+            out_tval_mux,
         }
     }
     fn init_impl(&mut self) {}
@@ -91,6 +94,7 @@ impl Mar {
             self.out_mem_ctl,
             self.data_inner & Word::from(0xFFFFFFFCu32)
         );
+        send_word!(self, self.out_tval_mux, self.data_inner);
     }
 }
 
@@ -155,6 +159,7 @@ impl MarMux {
         let out = match self.sel.into() {
             Some(mux_sel::mar::PC_OUT) => self.pc,
             Some(mux_sel::mar::ALU_OUT) => self.alu_out,
+            Some(mux_sel::mar::PC_PLUS2) => self.pc + Word::from(2u32),
             _ => Word::unknown(),
         };
 
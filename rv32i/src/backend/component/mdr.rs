@@ -26,8 +26,9 @@ use std::sync::Arc;
         ["data", "Word"]
     ],
     "output": [
-        ["out_ir", "Word"],
-        ["out_regfile_mux", "Word"]
+        ["out_rvc_expander", "Word"],
+        ["out_regfile_mux", "Word"],
+        ["out_fp_regfile_mux", "Word"]
     ],
     "clock": true
 }
@@ -43,8 +44,9 @@ impl Mdr {
         ack_sender: Sender<EventId>,
         load_receiver: Input,
         data_receiver: Input,
-        out_ir: Output,
+        out_rvc_expander: Output,
         out_regfile_mux: Output,
+        out_fp_regfile_mux: Output,
     ) -> Self {
         let clock_channel = unbounded();
         Mdr {
@@ -60,8 +62,9 @@ impl Mdr {
             data_receiver,
             data: Default::default(),
             data_old: Default::default(),
-            out_ir,
+            out_rvc_expander,
             out_regfile_mux,
+            out_fp_regfile_mux,
         }
     }
     fn init_impl(&mut self) {}
@@ -77,8 +80,9 @@ impl Mdr {
     }
 
     fn on_comb(&mut self) {
-        send_word!(self, self.out_ir, self.data_inner);
+        send_word!(self, self.out_rvc_expander, self.data_inner);
         send_word!(self, self.out_regfile_mux, self.data_inner);
+        send_word!(self, self.out_fp_regfile_mux, self.data_inner);
     }
 }
 
@@ -1,6 +1,7 @@
+use crate::backend::component::clint;
 use crate::backend::util::event::ByteEvent;
 use crate::backend::util::event::WordEvent;
-use crate::backend::util::types::{Byte, Word};
+use crate::backend::util::types::{amo, Byte, Word};
 use crate::send_byte;
 use crate::send_word;
 use crossbeam_channel::{unbounded, Sender};
@@ -8,6 +9,7 @@ use rsim_core::ack;
 use rsim_core::component::Component;
 use rsim_core::event::get_inner;
 use rsim_core::sim_manager::SimManager;
+use rsim_core::stats::Counter;
 use rsim_core::task::Task;
 use rsim_core::types::ComponentId;
 use rsim_core::types::EventId;
@@ -15,10 +17,137 @@ use rsim_core::types::Input;
 use rsim_core::types::Output;
 use rsim_core::{enq, send};
 use rsim_macro::ComponentAttribute;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 
+/// Only the CPU port takes reservations, the `MemCtl::test_port` master just stores
+pub const CPU_MASTER: usize = 0;
+
+/// One LR reservation per master, on a whole word. A write by anyone to a reserved word,
+/// including writes from outside the sim such as `load_bin`, drops every reservation on it.
+#[derive(Default, Debug)]
+pub struct ReservationSet {
+    by_master: BTreeMap<usize, Word>,
+}
+
+impl ReservationSet {
+    /// Replaces the previous reservation of `master`
+    pub fn reserve(&mut self, master: usize, addr: Word) {
+        self.by_master.insert(master, addr);
+    }
+
+    /// True if `master` still holds a reservation on `addr`, it is given up either way, like on SC
+    pub fn take(&mut self, master: usize, addr: Word) -> bool {
+        self.by_master.remove(&master) == Some(addr)
+    }
+
+    pub fn invalidate(&mut self, addr: Word) {
+        let word = addr & Word::from(0xFFFFFFFCu32);
+        self.by_master.retain(|_, reserved| *reserved != word);
+    }
+}
+
+/// The host-target interface of riscv-tests and riscv-arch-test, a program talks to the host through
+/// the 64-bit `tohost` word its ELF exports.
+///
+/// A command is taken once both words of `tohost` were written, in either order: riscv-test-env
+/// stores the lower word first, riscv-pk the upper one. An odd value with the upper word zero is
+/// `exit` with `value >> 1` as the code, device 1 command 1 prints the low byte. `tohost` is cleared
+/// after a command, `fromhost` gets the answer.
+#[derive(Default, Debug)]
+pub struct Htif {
+    pub tohost: Option<Word>,
+    pub fromhost: Option<Word>,
+    /// The words of `tohost` written since the last command, bit 0 is the lower one
+    written: u8,
+    /// Set once the program exited, `Core::can_end` is true from then on
+    pub exit_code: Option<u32>,
+    /// What the program printed and nobody took yet
+    pub console: Vec<u8>,
+}
+
+impl Htif {
+    /// 0 for the lower word of `tohost`, 1 for the upper one
+    fn word_of(&self, addr: Word) -> Option<u8> {
+        let tohost = self.tohost?;
+        if addr == tohost {
+            Some(0)
+        } else if addr == tohost + Word::from(4u32) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    /// True once the other word was written too
+    fn latch(&mut self, word: u8) -> bool {
+        self.written |= 1 << word;
+        if self.written == 0b11 {
+            self.written = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Devices and commands other than exit and putchar are ignored
+    fn command(&mut self, mem: &mut BTreeMap<Word, Byte>, upper: Word, lower: Word) {
+        let (Some(upper), Some(lower)) = (
+            Into::<Option<u32>>::into(upper),
+            Into::<Option<u32>>::into(lower),
+        ) else {
+            return;
+        };
+        let (device, command) = (upper >> 24, (upper >> 16) & 0xFF);
+        match (device, command) {
+            (0, 0) if upper == 0 && lower & 0x1 == 0x1 => {
+                self.exit_code = Some(lower >> 1);
+                return;
+            }
+            (1, 1) => {
+                self.console.push(lower as u8);
+                if let Some(fromhost) = self.fromhost {
+                    write_bytes(mem, fromhost, &[0, 0, 0, 0, 0, 0, 1, 1]);
+                }
+            }
+            _ => {}
+        }
+        if let Some(tohost) = self.tohost {
+            write_bytes(mem, tohost, &[0; 8]);
+        }
+    }
+}
+
+fn write_bytes(mem: &mut BTreeMap<Word, Byte>, addr: Word, data: &[u8]) {
+    for (i, byte) in data.iter().enumerate() {
+        mem.insert(addr + Word::from(i as u32), Byte::from(*byte));
+    }
+}
+
+/// The value an AMO writes back, `old` is what was in memory and `src` is rs2
+fn amo_result(op: u8, old: Word, src: Word) -> Word {
+    let pick = |f: fn(u32, u32) -> u32| match (
+        Into::<Option<u32>>::into(old),
+        Into::<Option<u32>>::into(src),
+    ) {
+        (Some(old), Some(src)) => Word::from(f(old, src)),
+        _ => Word::unknown(),
+    };
+    match op {
+        amo::SWAP => src,
+        amo::ADD => old + src,
+        amo::XOR => old ^ src,
+        amo::AND => old & src,
+        amo::OR => old | src,
+        amo::MIN => pick(|old, src| (old as i32).min(src as i32) as u32),
+        amo::MAX => pick(|old, src| (old as i32).max(src as i32) as u32),
+        amo::MINU => pick(u32::min),
+        amo::MAXU => pick(u32::max),
+        _ => Word::unknown(),
+    }
+}
+
 #[ComponentAttribute({
 "port": {
     "input": [
@@ -28,20 +157,49 @@ use std::sync::Arc;
         ["cpu_read_en", "Byte"],
         ["cpu_rmask", "Byte"],
         ["cpu_write_en", "Byte"],
-        ["cpu_wmask", "Byte"]
+        ["cpu_wmask", "Byte"],
+        ["cpu_amo_en", "Byte"],
+        ["cpu_amo_op", "Byte"],
+        ["mmio_rdata", "Word"],
+        ["mmio_resp", "Byte"]
     ],
     "output": [
         ["cpu_rdata", "Word"],
-        ["cpu_resp", "Byte"]
+        ["cpu_resp", "Byte"],
+        ["mmio_addr", "Word"],
+        ["mmio_wdata", "Word"],
+        ["mmio_wmask", "Byte"],
+        ["mmio_read_en", "Byte"],
+        ["mmio_write_en", "Byte"]
     ],
     "clock": true
 }
 })]
-#[allow(dead_code)]
+/// Accesses to the CLINT are forwarded on the mmio ports, `is_busy` is set until it answers.
+///
+/// An AMO request reads the word at `cpu_addr`, writes what `cpu_amo_op` makes of it and
+/// `cpu_wdata`, and answers with the old value. SC answers with 0 if it wrote and 1 if it did not.
+///
+/// Stores to either word of `htif.tohost` are handed to `Htif` once they are in memory.
+///
+/// `test_port` is a second master, like a DMA engine. One of its `(word address, data, wmask)`
+/// stores is served every clock, ahead of the CPU's request.
 pub struct MemCtl {
     pub backend_mem: BTreeMap<Word, Byte>,
-    mmio_addr: HashSet<Word>,
+    /// `(start, len)` ranges of `.bss`, the bytes in them never written to read as 0
+    pub zero_fill: Vec<(u32, u32)>,
+    pub test_port: VecDeque<(Word, Word, u8)>,
+    pub reservations: ReservationSet,
+    pub htif: Htif,
+    /// What the last AMO request wrote, None for LR and a failed SC
+    pub amo_written: Option<Word>,
+    /// Control holds `cpu_amo_en` until it sees the answer, it must not be applied twice
+    amo_answered: bool,
+    /// Same for a store to `tohost`, the command must not be taken twice
+    htif_answered: bool,
     is_busy: bool,
+    reads: Counter,
+    writes: Counter,
 }
 
 impl MemCtl {
@@ -55,14 +213,33 @@ impl MemCtl {
         cpu_rmask_receiver: Input,
         cpu_write_en_receiver: Input,
         cpu_wmask_receiver: Input,
+        cpu_amo_en_receiver: Input,
+        cpu_amo_op_receiver: Input,
+        mmio_rdata_receiver: Input,
+        mmio_resp_receiver: Input,
         cpu_rdata: Output,
         cpu_resp: Output,
+        mmio_addr: Output,
+        mmio_wdata: Output,
+        mmio_wmask: Output,
+        mmio_read_en: Output,
+        mmio_write_en: Output,
     ) -> Self {
         let clock_channel = unbounded();
+        let reads = sim_manager.get_stats().counter("mem_ctl", "reads");
+        let writes = sim_manager.get_stats().counter("mem_ctl", "writes");
 
         MemCtl {
+            reads,
+            writes,
             backend_mem: Default::default(),
-            mmio_addr: Default::default(),
+            zero_fill: vec![],
+            test_port: Default::default(),
+            reservations: Default::default(),
+            htif: Default::default(),
+            amo_written: None,
+            amo_answered: false,
+            htif_answered: false,
             is_busy: false,
             component_id,
             sim_manager,
@@ -87,63 +264,218 @@ impl MemCtl {
             cpu_wmask_receiver,
             cpu_wmask: Default::default(),
             cpu_wmask_old: Default::default(),
+            cpu_amo_en_receiver,
+            cpu_amo_en: Default::default(),
+            cpu_amo_en_old: Default::default(),
+            cpu_amo_op_receiver,
+            cpu_amo_op: Default::default(),
+            cpu_amo_op_old: Default::default(),
+            mmio_rdata_receiver,
+            mmio_rdata: Default::default(),
+            mmio_rdata_old: Default::default(),
+            mmio_resp_receiver,
+            mmio_resp: Default::default(),
+            mmio_resp_old: Default::default(),
             cpu_rdata,
             cpu_resp,
+            mmio_addr,
+            mmio_wdata,
+            mmio_wmask,
+            mmio_read_en,
+            mmio_write_en,
         }
     }
 
     fn init_impl(&mut self) {}
 
-    fn reset_impl(&mut self) {}
+    fn reset_impl(&mut self) {
+        self.test_port.clear();
+        self.amo_answered = false;
+        self.htif_answered = false;
+    }
 
     fn poll_impl(&mut self) {}
 
+    /// A byte never written to is 0 in `zero_fill` and unknown anywhere else
+    pub fn read_byte(&self, addr: Word) -> Byte {
+        self.backend_mem.get(&addr).copied().unwrap_or_else(|| {
+            if self.is_zero_filled(addr) {
+                Byte::from(0u8)
+            } else {
+                Byte::default()
+            }
+        })
+    }
+
+    fn is_zero_filled(&self, addr: Word) -> bool {
+        Into::<Option<u32>>::into(addr).is_some_and(|addr| {
+            self.zero_fill
+                .iter()
+                .any(|&(start, len)| addr.wrapping_sub(start) < len)
+        })
+    }
+
+    fn read_word(&self, addr: Word) -> Word {
+        let mut ret = Word::unknown();
+        for i in 0..4 {
+            ret.set_byte(i, self.read_byte(addr + Word::from(i as u32)));
+        }
+        ret
+    }
+
+    fn write_word(&mut self, addr: Word, data: Word) {
+        for i in 0..4 {
+            self.backend_mem
+                .insert(addr + Word::from(i as u32), data.get_byte(i));
+        }
+        self.reservations.invalidate(addr);
+    }
+
+    /// The bytes set in `wmask`, from any master
+    fn store(&mut self, addr: Word, data: Word, wmask: u8) {
+        for i in 0..4 {
+            if wmask >> i & 0x1 == 0x1 {
+                self.backend_mem
+                    .insert(addr + Word::from(i as u32), data.get_byte(i));
+            }
+        }
+        self.reservations.invalidate(addr);
+        self.writes.inc();
+    }
+
+    /// LR, SC and the read-modify-write AMOs, answered right away like any other access
+    fn amo(&mut self) {
+        let addr = self.cpu_addr;
+        let old = self.read_word(addr);
+        let (rdata, written) = match self.cpu_amo_op.into() {
+            Some(amo::LR) => {
+                self.reservations.reserve(CPU_MASTER, addr);
+                (old, None)
+            }
+            Some(amo::SC) if self.reservations.take(CPU_MASTER, addr) => {
+                (Word::zeros(), Some(self.cpu_wdata))
+            }
+            Some(amo::SC) => (Word::from(1u32), None),
+            Some(op) => (old, Some(amo_result(op, old, self.cpu_wdata))),
+            None => (Word::unknown(), None),
+        };
+
+        if !matches!(self.cpu_amo_op.into(), Some(amo::SC)) {
+            self.reads.inc();
+        }
+        if let Some(data) = written {
+            self.write_word(addr, data);
+            self.writes.inc();
+        }
+        self.amo_written = written;
+        send_word!(self, self.cpu_rdata, rdata);
+        send_byte!(self, self.cpu_resp, Byte::from(1u8));
+    }
+
     fn on_clock(&mut self) {
-        //can recv request
-        if !self.is_busy {
-            // a r/w request came in
-            if self.cpu_write_en.is_something_nonzero() {
-                if let Some(wmask) = Into::<Option<u8>>::into(self.cpu_wmask) {
-                    for i in 0..4 {
-                        if wmask >> i & 0x1 == 0x1 {
-                            let addr_idx = self.cpu_addr + Word::from(i as u32);
-                            let data = self.cpu_wdata[i].map(Byte::from).unwrap_or(Byte::unknown());
-                            self.backend_mem.insert(addr_idx, data);
-                        }
-                    }
+        if let Some((addr, data, wmask)) = self.test_port.pop_front() {
+            self.store(addr, data, wmask);
+        }
+
+        if self.is_busy {
+            // the request is only held for a cycle, the answer is forwarded in on_comb
+            send_byte!(self, self.mmio_read_en, Byte::from(0u8));
+            send_byte!(self, self.mmio_write_en, Byte::from(0u8));
+            if self.mmio_resp.is_something_nonzero() {
+                self.is_busy = false;
+                send_byte!(self, self.cpu_resp, Byte::from(0u8));
+            }
+            return;
+        }
+
+        let request =
+            self.cpu_write_en.is_something_nonzero() || self.cpu_read_en.is_something_nonzero();
+        if request && clint::contains(self.cpu_addr) {
+            send_word!(self, self.mmio_addr, self.cpu_addr);
+            send_word!(self, self.mmio_wdata, self.cpu_wdata);
+            send_byte!(self, self.mmio_wmask, self.cpu_wmask);
+            send_byte!(self, self.mmio_read_en, self.cpu_read_en);
+            send_byte!(self, self.mmio_write_en, self.cpu_write_en);
+            send_byte!(self, self.cpu_resp, Byte::from(0u8));
+            self.is_busy = true;
+            return;
+        }
+
+        if self.cpu_amo_en.is_something_nonzero() {
+            if !self.amo_answered {
+                self.amo();
+                self.amo_answered = true;
+            }
+            return;
+        }
+        self.amo_answered = false;
+        if !self.cpu_write_en.is_something_nonzero() {
+            self.htif_answered = false;
+        }
+
+        // a r/w request came in
+        if self.cpu_write_en.is_something_nonzero() {
+            let htif_word = self.htif.word_of(self.cpu_addr);
+            // the write would land on the cleared tohost again while the store is held
+            if !(htif_word.is_some() && self.htif_answered) {
+                let wmask = Into::<Option<u8>>::into(self.cpu_wmask).unwrap_or(0);
+                self.store(self.cpu_addr, self.cpu_wdata, wmask);
+                if htif_word.is_some_and(|word| self.htif.latch(word)) {
+                    let tohost = self.htif.tohost.unwrap();
+                    let upper = self.read_word(tohost + Word::from(4u32));
+                    let lower = self.read_word(tohost);
+                    self.htif.command(&mut self.backend_mem, upper, lower);
+                    self.htif_answered = true;
                 }
-                send_byte!(self, self.cpu_resp, Byte::from(1u8));
-            } else if self.cpu_read_en.is_something_nonzero() {
-                let mut ret = Word::unknown();
-                if let Some(rmask) = Into::<Option<u8>>::into(self.cpu_rmask) {
-                    for i in 0..4 {
-                        if rmask >> i & 0x1 == 0x1 {
-                            let addr_idx = self.cpu_addr + Word::from(i as u32);
-                            ret[i] = if self.backend_mem.contains_key(&addr_idx) {
-                                self.backend_mem[&addr_idx].into()
-                            } else {
-                                None
-                            }
-                        }
+            }
+            send_byte!(self, self.cpu_resp, Byte::from(1u8));
+        } else if self.cpu_read_en.is_something_nonzero() {
+            let mut ret = Word::unknown();
+            if let Some(rmask) = Into::<Option<u8>>::into(self.cpu_rmask) {
+                for i in 0..4 {
+                    if rmask >> i & 0x1 == 0x1 {
+                        ret.set_byte(i, self.read_byte(self.cpu_addr + Word::from(i as u32)));
                     }
                 }
-
-                send_word!(self, self.cpu_rdata, ret);
-                send_byte!(self, self.cpu_resp, Byte::from(1u8));
-            } else {
-                send_byte!(self, self.cpu_resp, Byte::from(0u8));
             }
+
+            self.reads.inc();
+            send_word!(self, self.cpu_rdata, ret);
+            send_byte!(self, self.cpu_resp, Byte::from(1u8));
+        } else {
+            send_byte!(self, self.cpu_resp, Byte::from(0u8));
         }
     }
 
-    fn on_comb(&mut self) {}
+    fn on_comb(&mut self) {
+        if self.is_busy && self.mmio_resp.is_something_nonzero() {
+            send_word!(self, self.cpu_rdata, self.mmio_rdata);
+            send_byte!(self, self.cpu_resp, Byte::from(1u8));
+        }
+    }
 
     pub fn load_bin(&mut self, data: &[u8], addr: Word) {
         for i in 0..data.len() as u32 {
             self.backend_mem
                 .insert(addr + Word::from(i), data[i as usize].into());
+            self.reservations.invalidate(addr + Word::from(i));
         }
     }
+
+    /// `len` zero bytes from `addr`, kept as a range so a large `.bss` costs nothing
+    pub fn load_zeros(&mut self, addr: Word, len: u32) {
+        let Some(start) = Into::<Option<u32>>::into(addr) else {
+            return;
+        };
+        let in_range = |addr: &Word| {
+            Into::<Option<u32>>::into(*addr).is_some_and(|addr| addr.wrapping_sub(start) < len)
+        };
+        self.backend_mem.retain(|addr, _| !in_range(addr));
+        self.reservations
+            .by_master
+            .retain(|_, reserved| !in_range(reserved));
+        self.zero_fill.push((start, len));
+    }
 }
 
 impl Debug for MemCtl {
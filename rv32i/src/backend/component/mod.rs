@@ -1,10 +1,16 @@
 pub mod alu;
+pub mod clint;
 pub mod cmp;
 pub mod control;
+pub mod csr;
 pub mod data_out;
+pub mod fp_regfile;
+pub mod fpu;
 pub mod ir;
 pub mod mar;
 pub mod mdr;
 pub mod mem_ctl;
+pub mod muldiv;
 pub mod pc;
 pub mod regfile;
+pub mod rvc;
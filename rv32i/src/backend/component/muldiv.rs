@@ -0,0 +1,162 @@
+use crate::backend::util::event::{ByteEvent, WordEvent};
+use crate::backend::util::types::*;
+use crate::{send_byte, send_word};
+use crossbeam_channel::unbounded;
+use crossbeam_channel::Sender;
+use rsim_core::ack;
+use rsim_core::component::Component;
+use rsim_core::enq;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// The RV32M unit, `op` is the funct3 of the instruction.
+///
+/// The operands are latched on the edge `start` is seen, `done` goes high `mul_latency` or
+/// `div_latency` edges later and stays high for a single cycle, `out` holds the result from then on.
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["a", "Word"],
+        ["b", "Word"],
+        ["op", "Byte"],
+        ["start", "Byte"]
+    ],
+    "output": [
+        ["out_regfile_mux", "Word"],
+        ["done", "Byte"]
+    ],
+    "clock": true
+}
+})]
+pub struct MulDiv {
+    /// At least 1
+    pub mul_latency: u64,
+    pub div_latency: u64,
+    result: Word,
+    /// Edges left until the result is ready, `None` while idle
+    remaining: Option<u64>,
+    done_inner: bool,
+}
+
+impl MulDiv {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        a_receiver: Input,
+        b_receiver: Input,
+        op_receiver: Input,
+        start_receiver: Input,
+        out_regfile_mux: Output,
+        done: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        MulDiv {
+            mul_latency: 1,
+            div_latency: 32,
+            result: Word::unknown(),
+            remaining: None,
+            done_inner: false,
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            a_receiver,
+            a: Default::default(),
+            a_old: Default::default(),
+            b_receiver,
+            b: Default::default(),
+            b_old: Default::default(),
+            op_receiver,
+            op: Default::default(),
+            op_old: Default::default(),
+            start_receiver,
+            start: Default::default(),
+            start_old: Default::default(),
+            out_regfile_mux,
+            done,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.result = Word::unknown();
+        self.remaining = None;
+        self.done_inner = false;
+    }
+
+    fn poll_impl(&mut self) {}
+
+    fn compute(&self) -> Word {
+        match self.op.into() {
+            Some(funct3::muldiv::MUL) => self.a * self.b,
+            Some(funct3::muldiv::MULH) => self.a.widening_mul_signed(self.b).1,
+            Some(funct3::muldiv::MULHSU) => self.a.widening_mul_signed_unsigned(self.b).1,
+            Some(funct3::muldiv::MULHU) => self.a.widening_mul(self.b).1,
+            Some(funct3::muldiv::DIV) => self.a.div_signed(self.b),
+            Some(funct3::muldiv::DIVU) => self.a.div_unsigned(self.b),
+            Some(funct3::muldiv::REM) => self.a.rem_signed(self.b),
+            Some(funct3::muldiv::REMU) => self.a.rem_unsigned(self.b),
+            _ => Word::unknown(),
+        }
+    }
+
+    fn latency(&self) -> u64 {
+        let is_div = self.op.get_bit(2) == Some(true);
+        if is_div {
+            self.div_latency
+        } else {
+            self.mul_latency
+        }
+    }
+
+    fn on_clock(&mut self) {
+        // let go of the previous request before taking the next one
+        if self.done_inner {
+            self.done_inner = false;
+            return;
+        }
+        match self.remaining {
+            Some(remaining) if remaining > 1 => self.remaining = Some(remaining - 1),
+            Some(_) => {
+                self.remaining = None;
+                self.done_inner = true;
+            }
+            None if self.start.is_something_nonzero() => {
+                self.result = self.compute();
+                if self.latency() > 1 {
+                    self.remaining = Some(self.latency() - 1);
+                } else {
+                    self.done_inner = true;
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn on_comb(&mut self) {
+        send_word!(self, self.out_regfile_mux, self.result);
+        send_byte!(self, self.done, Byte::from(self.done_inner as u8));
+    }
+}
+
+impl Debug for MulDiv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MulDiv: {{a: {:?}, b: {:?}, op: {:?}, start: {:?}, remaining: {:?}, done: {}}}",
+            self.a, self.b, self.op, self.start, self.remaining, self.done_inner
+        )
+    }
+}
@@ -30,7 +30,10 @@ use std::sync::Arc;
         ["out_alu_mux1", "Word"],
         ["out_pc_mux", "Word"],
         ["out_mar_mux", "Word"],
-        ["out_regfile_mux", "Word"]
+        ["out_regfile_mux", "Word"],
+        ["out_tval_mux", "Word"],
+        ["out_csr_file", "Word"],
+        ["out_rvc_expander", "Word"]
     ],
     "clock": true
 }
@@ -50,6 +53,9 @@ impl Pc {
         out_pc_mux: Output,
         out_mar_mux: Output,
         out_regfile_mux: Output,
+        out_tval_mux: Output,
+        out_csr_file: Output,
+        out_rvc_expander: Output,
     ) -> Self {
         let clock_channel = unbounded();
         Pc {
@@ -69,6 +75,9 @@ impl Pc {
             out_pc_mux,
             out_mar_mux,
             out_regfile_mux,
+            out_tval_mux,
+            out_csr_file,
+            out_rvc_expander,
         }
     }
     fn init_impl(&mut self) {
@@ -87,11 +96,20 @@ impl Pc {
         }
     }
 
+    /// Overwrites the PC and drives the new value out, used by the debugger frontends
+    pub fn set_data_inner(&mut self, data: Word) {
+        self.data_inner = data;
+        self.on_comb();
+    }
+
     fn on_comb(&mut self) {
         send_word!(self, self.out_alu_mux1, self.data_inner);
         send_word!(self, self.out_pc_mux, self.data_inner);
         send_word!(self, self.out_mar_mux, self.data_inner);
         send_word!(self, self.out_regfile_mux, self.data_inner);
+        send_word!(self, self.out_tval_mux, self.data_inner);
+        send_word!(self, self.out_csr_file, self.data_inner);
+        send_word!(self, self.out_rvc_expander, self.data_inner);
     }
 }
 
@@ -109,6 +127,8 @@ impl Debug for Pc {
     "input": [
         ["pc", "Word"],
         ["alu_out", "Word"],
+        ["mtvec", "Word"],
+        ["mepc", "Word"],
         ["sel", "Byte"]
     ],
     "output": [
@@ -125,6 +145,8 @@ impl PcMux {
         ack_sender: Sender<EventId>,
         pc_receiver: Input,
         alu_out_receiver: Input,
+        mtvec_receiver: Input,
+        mepc_receiver: Input,
         sel_receiver: Input,
         out: Output,
     ) -> Self {
@@ -138,6 +160,12 @@ impl PcMux {
             alu_out_receiver,
             alu_out: Default::default(),
             alu_out_old: Default::default(),
+            mtvec_receiver,
+            mtvec: Default::default(),
+            mtvec_old: Default::default(),
+            mepc_receiver,
+            mepc: Default::default(),
+            mepc_old: Default::default(),
             sel_receiver,
             sel: Default::default(),
             sel_old: Default::default(),
@@ -156,6 +184,9 @@ impl PcMux {
             Some(mux_sel::pc::PC_PLUS4) => self.pc + Word::from(4u32),
             Some(mux_sel::pc::ALU_OUT) => self.alu_out,
             Some(mux_sel::pc::ALU_MOD2) => self.alu_out & Word::from(0xFFFFFFFEu32),
+            Some(mux_sel::pc::MTVEC) => self.mtvec,
+            Some(mux_sel::pc::MEPC) => self.mepc,
+            Some(mux_sel::pc::PC_PLUS2) => self.pc + Word::from(2u32),
             _ => Word::unknown(),
         };
 
@@ -1,4 +1,3 @@
-use crate::backend::util::byte::Bytes;
 use crate::backend::util::event::WordEvent;
 use crate::backend::util::helper::sign_extend;
 use crate::backend::util::types::*;
@@ -24,7 +23,7 @@ pub struct Registers {
 }
 
 impl Registers {
-    fn read(&self, index: Bytes<1>) -> Word {
+    pub fn read(&self, index: RegIdx) -> Word {
         Into::<Option<u8>>::into(index)
             .map(|idx| {
                 if idx != 0 {
@@ -33,10 +32,10 @@ impl Registers {
                     Word::zeros()
                 }
             })
-            .unwrap_or(Word::unknown())
+            .unwrap_or_default()
     }
 
-    fn write(&mut self, index: Bytes<1>, value: Word) {
+    pub fn write(&mut self, index: RegIdx, value: Word) {
         if let Some(idx) = Into::<Option<u8>>::into(index) {
             if idx != 0 {
                 self.data[idx as usize] = value
@@ -62,10 +61,10 @@ impl Default for Registers {
 #[ComponentAttribute({
 "port": {
     "input": [
-        ["rs1_idx", "Byte"],
-        ["rs2_idx", "Byte"],
+        ["rs1_idx", "RegIdx"],
+        ["rs2_idx", "RegIdx"],
         ["rd_wr", "Byte"],
-        ["rd_idx", "Byte"],
+        ["rd_idx", "RegIdx"],
         ["rd_data", "Word"]
     ],
     "output": [
@@ -73,7 +72,11 @@ impl Default for Registers {
         ["rs1_data_cmp", "Word"],
         ["rs2_data_alu_mux2", "Word"],
         ["rs2_data_cmp_mux", "Word"],
-        ["rs2_data_data_out", "Word"]
+        ["rs2_data_data_out_mux", "Word"],
+        ["rs1_data_csr_mux", "Word"],
+        ["rs1_data_muldiv", "Word"],
+        ["rs2_data_muldiv", "Word"],
+        ["rs1_data_fpu", "Word"]
     ],
     "clock": true
 }
@@ -96,7 +99,11 @@ impl RegFile {
         rs1_data_cmp: Output,
         rs2_data_alu_mux2: Output,
         rs2_data_cmp_mux: Output,
-        rs2_data_data_out: Output,
+        rs2_data_data_out_mux: Output,
+        rs1_data_csr_mux: Output,
+        rs1_data_muldiv: Output,
+        rs2_data_muldiv: Output,
+        rs1_data_fpu: Output,
     ) -> Self {
         let clock_channel = unbounded();
         RegFile {
@@ -125,7 +132,11 @@ impl RegFile {
             rs1_data_cmp,
             rs2_data_alu_mux2,
             rs2_data_cmp_mux,
-            rs2_data_data_out,
+            rs2_data_data_out_mux,
+            rs1_data_csr_mux,
+            rs1_data_muldiv,
+            rs2_data_muldiv,
+            rs1_data_fpu,
         }
     }
 
@@ -143,6 +154,12 @@ impl RegFile {
         }
     }
 
+    /// Overwrites a register and drives the read ports again, used by the debugger frontends
+    pub fn set_register(&mut self, index: RegIdx, value: Word) {
+        self.registers.write(index, value);
+        self.on_comb();
+    }
+
     fn on_comb(&mut self) {
         send_word!(
             self,
@@ -150,6 +167,11 @@ impl RegFile {
             self.registers.read(self.rs1_idx)
         );
         send_word!(self, self.rs1_data_cmp, self.registers.read(self.rs1_idx));
+        send_word!(
+            self,
+            self.rs1_data_csr_mux,
+            self.registers.read(self.rs1_idx)
+        );
         send_word!(
             self,
             self.rs2_data_alu_mux2,
@@ -162,9 +184,20 @@ impl RegFile {
         );
         send_word!(
             self,
-            self.rs2_data_data_out,
+            self.rs2_data_data_out_mux,
             self.registers.read(self.rs2_idx)
         );
+        send_word!(
+            self,
+            self.rs1_data_muldiv,
+            self.registers.read(self.rs1_idx)
+        );
+        send_word!(
+            self,
+            self.rs2_data_muldiv,
+            self.registers.read(self.rs2_idx)
+        );
+        send_word!(self, self.rs1_data_fpu, self.registers.read(self.rs1_idx));
     }
 }
 
@@ -178,7 +211,7 @@ impl Debug for RegFile {
 mod tests {
     use super::*;
     use crate::backend::util::blackhole::WordBlackhole;
-    use crate::backend::util::event::ByteEvent;
+    use crate::backend::util::event::{BitsEvent, ByteEvent};
     use crossbeam_channel::unbounded;
     use rsim_core::sim_dispatcher::SimDispatcher;
     use rsim_core::sim_manager::SimManager;
@@ -202,7 +235,11 @@ mod tests {
         let rs1_data_cmp_link = unbounded();
         let rs2_data_alu_mux2_link = unbounded();
         let rs2_data_cmp_mux_link = unbounded();
-        let rs2_data_data_out_link = unbounded();
+        let rs2_data_data_out_mux_link = unbounded();
+        let rs1_data_csr_mux_link = unbounded();
+        let rs1_data_muldiv_link = unbounded();
+        let rs2_data_muldiv_link = unbounded();
+        let rs1_data_fpu_link = unbounded();
 
         let reg_file = Arc::new(Mutex::new(RegFile {
             registers: Registers::default(),
@@ -212,25 +249,29 @@ mod tests {
             clock_sender: reg_file_clock_link.0,
             clock_receiver: reg_file_clock_link.1,
             rs1_idx_receiver: rs1_idx_link.1,
-            rs1_idx: Bytes::unknown(),
+            rs1_idx: RegIdx::unknown(),
             rs1_idx_old: Default::default(),
             rs2_idx_receiver: rs2_idx_link.1,
-            rs2_idx: Bytes::unknown(),
+            rs2_idx: RegIdx::unknown(),
             rs2_idx_old: Default::default(),
             rd_wr_receiver: rd_wr_link.1,
-            rd_wr: Bytes::unknown(),
+            rd_wr: Byte::unknown(),
             rd_wr_old: Default::default(),
             rd_idx_receiver: rd_idx_link.1,
-            rd_idx: Bytes::unknown(),
+            rd_idx: RegIdx::unknown(),
             rd_idx_old: Default::default(),
             rd_data_receiver: rd_data_link.1,
-            rd_data: Bytes::unknown(),
+            rd_data: Word::unknown(),
             rd_data_old: Default::default(),
             rs1_data_alu_mux1: rs1_data_alu_mux1_link.0,
             rs1_data_cmp: rs1_data_cmp_link.0,
             rs2_data_alu_mux2: rs2_data_alu_mux2_link.0,
             rs2_data_cmp_mux: rs2_data_cmp_mux_link.0,
-            rs2_data_data_out: rs2_data_data_out_link.0,
+            rs2_data_data_out_mux: rs2_data_data_out_mux_link.0,
+            rs1_data_csr_mux: rs1_data_csr_mux_link.0,
+            rs1_data_muldiv: rs1_data_muldiv_link.0,
+            rs2_data_muldiv: rs2_data_muldiv_link.0,
+            rs1_data_fpu: rs1_data_fpu_link.0,
         }));
 
         sim_manager.register_do_not_end(0);
@@ -263,10 +304,38 @@ mod tests {
             ack_channel.0.clone(),
         );
 
-        let rs2_data_data_out_blackhole = WordBlackhole::new(
+        let rs2_data_data_out_mux_blackhole = WordBlackhole::new(
+            2,
+            sim_manager.clone(),
+            rs2_data_data_out_mux_link.1.clone(),
+            ack_channel.0.clone(),
+        );
+
+        let rs1_data_csr_mux_blackhole = WordBlackhole::new(
+            1,
+            sim_manager.clone(),
+            rs1_data_csr_mux_link.1.clone(),
+            ack_channel.0.clone(),
+        );
+
+        let rs1_data_muldiv_blackhole = WordBlackhole::new(
+            1,
+            sim_manager.clone(),
+            rs1_data_muldiv_link.1.clone(),
+            ack_channel.0.clone(),
+        );
+
+        let rs2_data_muldiv_blackhole = WordBlackhole::new(
             2,
             sim_manager.clone(),
-            rs2_data_data_out_link.1.clone(),
+            rs2_data_muldiv_link.1.clone(),
+            ack_channel.0.clone(),
+        );
+
+        let rs1_data_fpu_blackhole = WordBlackhole::new(
+            1,
+            sim_manager.clone(),
+            rs1_data_fpu_link.1.clone(),
             ack_channel.0.clone(),
         );
 
@@ -277,6 +346,9 @@ mod tests {
                 vec![
                     rs1_data_alu_mux1_blackhole.clone(),
                     rs1_data_cmp_blackhole.clone(),
+                    rs1_data_csr_mux_blackhole.clone(),
+                    rs1_data_muldiv_blackhole.clone(),
+                    rs1_data_fpu_blackhole.clone(),
                 ],
             ),
             SimDispatcher::new(
@@ -284,7 +356,8 @@ mod tests {
                 vec![
                     rs2_data_alu_mux2_blackhole.clone(),
                     rs2_data_cmp_mux_blackhole.clone(),
-                    rs2_data_data_out_blackhole.clone(),
+                    rs2_data_data_out_mux_blackhole.clone(),
+                    rs2_data_muldiv_blackhole.clone(),
                 ],
             ),
         ];
@@ -296,12 +369,16 @@ mod tests {
             thread_handlers.push(thread::spawn(move || sim_dispatcher.run()));
         }
 
-        sim_manager.run_cycle();
+        sim_manager.run_cycle().unwrap();
 
         // test init
         for i in 0..32 {
             assert_eq!(
-                reg_file.lock().unwrap().registers.read(Byte::from(i as u8)),
+                reg_file
+                    .lock()
+                    .unwrap()
+                    .registers
+                    .read(RegIdx::from(i as u8)),
                 Word::zeros()
             );
         }
@@ -309,7 +386,7 @@ mod tests {
         // test write
         for i in 0..32 {
             let wr = Byte::from(i as u8);
-            let idx = Byte::from(i as u8);
+            let idx = RegIdx::from(i as u8);
             let data = Word::from(i as u32);
             sim_manager.proxy_event(
                 Box::new(ByteEvent::new(
@@ -320,7 +397,7 @@ mod tests {
                 rd_wr_link.0.clone(),
             );
             sim_manager.proxy_event(
-                Box::new(ByteEvent::new(
+                Box::new(BitsEvent::new(
                     sim_manager.get_curr_cycle(),
                     idx,
                     sim_manager.request_new_event_id(),
@@ -336,19 +413,19 @@ mod tests {
                 rd_data_link.0.clone(),
             );
 
-            sim_manager.run_cycle();
+            sim_manager.run_cycle().unwrap();
             assert_eq!(reg_file.lock().unwrap().registers.read(idx), data);
         }
 
         // test read
         let curr_cycle = sim_manager.get_curr_cycle();
         for i in 0..16u8 {
-            let rs1_idx = Byte::from(i * 2);
-            let rs2_idx = Byte::from(i * 2 + 1);
+            let rs1_idx = RegIdx::from(i * 2);
+            let rs2_idx = RegIdx::from(i * 2 + 1);
             let rs1_data = Word::from((i * 2) as u32);
             let rs2_data = Word::from((i * 2 + 1) as u32);
             sim_manager.proxy_event(
-                Box::new(ByteEvent::new(
+                Box::new(BitsEvent::new(
                     sim_manager.get_curr_cycle(),
                     rs1_idx,
                     sim_manager.request_new_event_id(),
@@ -356,7 +433,7 @@ mod tests {
                 rs1_idx_link.0.clone(),
             );
             sim_manager.proxy_event(
-                Box::new(ByteEvent::new(
+                Box::new(BitsEvent::new(
                     sim_manager.get_curr_cycle(),
                     rs2_idx,
                     sim_manager.request_new_event_id(),
@@ -380,7 +457,7 @@ mod tests {
                 rs2_data
             );
             assert_eq!(
-                rs2_data_data_out_blackhole.lock().unwrap().get_input(),
+                rs2_data_data_out_mux_blackhole.lock().unwrap().get_input(),
                 rs2_data
             );
         }
@@ -388,7 +465,7 @@ mod tests {
         // test write after read
         for i in 0..32 {
             let wr = Byte::from(i as u8);
-            let idx = Byte::from(i as u8);
+            let idx = RegIdx::from(i as u8);
             let data = Word::from((i * 2) as u32);
             sim_manager.proxy_event(
                 Box::new(ByteEvent::new(
@@ -399,7 +476,7 @@ mod tests {
                 rd_wr_link.0.clone(),
             );
             sim_manager.proxy_event(
-                Box::new(ByteEvent::new(
+                Box::new(BitsEvent::new(
                     sim_manager.get_curr_cycle(),
                     idx,
                     sim_manager.request_new_event_id(),
@@ -415,7 +492,7 @@ mod tests {
                 rd_data_link.0.clone(),
             );
 
-            sim_manager.run_cycle();
+            sim_manager.run_cycle().unwrap();
             assert_eq!(reg_file.lock().unwrap().registers.read(idx), data);
         }
 
@@ -436,6 +513,9 @@ mod tests {
         ["mar", "Word"],
         ["mdr", "Word"],
         ["pc", "Word"],
+        ["csr", "Word"],
+        ["muldiv", "Word"],
+        ["fpu", "Word"],
         ["sel", "Byte"]
     ],
     "output": [
@@ -456,6 +536,9 @@ impl RegFileMux {
         mar_receiver: Input,
         mdr_receiver: Input,
         pc_receiver: Input,
+        csr_receiver: Input,
+        muldiv_receiver: Input,
+        fpu_receiver: Input,
         sel_receiver: Input,
         out: Output,
     ) -> Self {
@@ -481,6 +564,15 @@ impl RegFileMux {
             pc_receiver,
             pc: Default::default(),
             pc_old: Default::default(),
+            csr_receiver,
+            csr: Default::default(),
+            csr_old: Default::default(),
+            muldiv_receiver,
+            muldiv: Default::default(),
+            muldiv_old: Default::default(),
+            fpu_receiver,
+            fpu: Default::default(),
+            fpu_old: Default::default(),
             sel_receiver,
             sel: Default::default(),
             sel_old: Default::default(),
@@ -505,7 +597,7 @@ impl RegFileMux {
             Some(mux_sel::regfile::LB) => {
                 let val = self.mdr[mdr_idx]
                     .map(|byte| Word::from(byte as u32))
-                    .unwrap_or(Word::unknown());
+                    .unwrap_or_default();
                 if !val.has_unknown() {
                     sign_extend(Into::<Option<u32>>::into(val).unwrap(), 7)
                 } else {
@@ -514,15 +606,15 @@ impl RegFileMux {
             }
             Some(mux_sel::regfile::LBU) => self.mdr[mdr_idx]
                 .map(|byte| Word::from(byte as u32))
-                .unwrap_or(Word::unknown()),
+                .unwrap_or_default(),
             Some(mux_sel::regfile::LH) => {
                 let val = self.mdr[mdr_idx]
                     .map(|lsb| {
                         self.mdr[mdr_idx + 1]
                             .map(|msb| Word::from((((msb as u16) << 8) | lsb as u16) as u32))
-                            .unwrap_or(Word::unknown())
+                            .unwrap_or_default()
                     })
-                    .unwrap_or(Word::unknown());
+                    .unwrap_or_default();
                 if !val.has_unknown() {
                     sign_extend(Into::<Option<u32>>::into(val).unwrap(), 15)
                 } else {
@@ -533,9 +625,13 @@ impl RegFileMux {
                 .map(|lsb| {
                     self.mdr[mdr_idx + 1]
                         .map(|msb| Word::from((((msb as u16) << 8) | lsb as u16) as u32))
-                        .unwrap_or(Word::unknown())
+                        .unwrap_or_default()
                 })
-                .unwrap_or(Word::unknown()),
+                .unwrap_or_default(),
+            Some(mux_sel::regfile::CSR_OUT) => self.csr,
+            Some(mux_sel::regfile::MULDIV_OUT) => self.muldiv,
+            Some(mux_sel::regfile::PC_PLUS2) => self.pc + Word::from(2u32),
+            Some(mux_sel::regfile::FPU_OUT) => self.fpu,
             _ => Word::unknown(),
         };
 
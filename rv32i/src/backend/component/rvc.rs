@@ -0,0 +1,417 @@
+use crate::backend::util::event::{ByteEvent, WordEvent};
+use crate::backend::util::types::*;
+use crate::{send_byte, send_word};
+use crossbeam_channel::unbounded;
+use crossbeam_channel::Sender;
+use rsim_core::ack;
+use rsim_core::component::Component;
+use rsim_core::enq;
+use rsim_core::event::get_inner;
+use rsim_core::send;
+use rsim_core::sim_manager::SimManager;
+use rsim_core::task::Task;
+use rsim_core::types::ComponentId;
+use rsim_core::types::EventId;
+use rsim_core::types::Input;
+use rsim_core::types::Output;
+use rsim_macro::ComponentAttribute;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+fn sext(value: u32, bits: u32) -> i32 {
+    ((value << (32 - bits)) as i32) >> (32 - bits)
+}
+
+fn i_type(imm: i32, rs1: u32, funct3: u8, rd: u32, opcode: u8) -> u32 {
+    ((imm as u32 & 0xFFF) << 20) | (rs1 << 15) | ((funct3 as u32) << 12) | (rd << 7) | opcode as u32
+}
+
+fn s_type(imm: i32, rs2: u32, rs1: u32, funct3: u8, opcode: u8) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 5 & 0x7F) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | ((funct3 as u32) << 12)
+        | ((imm & 0x1F) << 7)
+        | opcode as u32
+}
+
+fn b_type(imm: i32, rs2: u32, rs1: u32, funct3: u8) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 12 & 0x1) << 31)
+        | ((imm >> 5 & 0x3F) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | ((funct3 as u32) << 12)
+        | ((imm >> 1 & 0xF) << 8)
+        | ((imm >> 11 & 0x1) << 7)
+        | opcode::BR as u32
+}
+
+fn j_type(imm: i32, rd: u32) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 20 & 0x1) << 31)
+        | ((imm >> 1 & 0x3FF) << 21)
+        | ((imm >> 11 & 0x1) << 20)
+        | ((imm >> 12 & 0xFF) << 12)
+        | (rd << 7)
+        | opcode::JAL as u32
+}
+
+fn r_type(funct7: u8, rs2: u32, rs1: u32, funct3: u8, rd: u32) -> u32 {
+    ((funct7 as u32) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | ((funct3 as u32) << 12)
+        | (rd << 7)
+        | opcode::REG as u32
+}
+
+/// The 32 bit instruction a compressed one stands for, `None` for the reserved encodings and the
+/// ones of extensions we don't have (the double precision loads and stores).
+/// The hints are expanded like any other instruction, they write x0 or change nothing.
+pub fn expand(inst: u16) -> Option<u32> {
+    let inst = inst as u32;
+    let bits = |hi: u32, lo: u32| (inst >> lo) & ((1 << (hi - lo + 1)) - 1);
+    // rd', rs1' and rs2' can only name x8 to x15
+    let rd_p = bits(4, 2) + 8;
+    let rs1_p = bits(9, 7) + 8;
+    let rd = bits(11, 7);
+    let rs2 = bits(6, 2);
+    let imm = sext(bits(12, 12) << 5 | bits(6, 2), 6);
+    // RV32 has no shifts by 32 or more
+    let shamt = (bits(12, 12) == 0).then_some(bits(6, 2) as i32);
+    let j_imm = sext(
+        bits(12, 12) << 11
+            | bits(11, 11) << 4
+            | bits(10, 9) << 8
+            | bits(8, 8) << 10
+            | bits(7, 7) << 6
+            | bits(6, 6) << 7
+            | bits(5, 3) << 1
+            | bits(2, 2) << 5,
+        12,
+    );
+    let b_imm = sext(
+        bits(12, 12) << 8 | bits(11, 10) << 3 | bits(6, 5) << 6 | bits(4, 3) << 1 | bits(2, 2) << 5,
+        9,
+    );
+
+    match (inst & 0b11, bits(15, 13)) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => {
+            let imm = bits(10, 7) << 6 | bits(12, 11) << 4 | bits(5, 5) << 3 | bits(6, 6) << 2;
+            (imm != 0).then(|| i_type(imm as i32, 2, funct3::arith::ADD, rd_p, opcode::IMM))
+        }
+        // C.LW
+        (0b00, 0b010) => {
+            let imm = bits(5, 5) << 6 | bits(12, 10) << 3 | bits(6, 6) << 2;
+            Some(i_type(
+                imm as i32,
+                rs1_p,
+                funct3::load::LW,
+                rd_p,
+                opcode::LOAD,
+            ))
+        }
+        // C.SW
+        (0b00, 0b110) => {
+            let imm = bits(5, 5) << 6 | bits(12, 10) << 3 | bits(6, 6) << 2;
+            Some(s_type(
+                imm as i32,
+                rd_p,
+                rs1_p,
+                funct3::store::SW,
+                opcode::STORE,
+            ))
+        }
+        // C.FLW
+        (0b00, 0b011) => {
+            let imm = bits(5, 5) << 6 | bits(12, 10) << 3 | bits(6, 6) << 2;
+            Some(i_type(
+                imm as i32,
+                rs1_p,
+                funct3::load::LW,
+                rd_p,
+                opcode::LOAD_FP,
+            ))
+        }
+        // C.FSW
+        (0b00, 0b111) => {
+            let imm = bits(5, 5) << 6 | bits(12, 10) << 3 | bits(6, 6) << 2;
+            Some(s_type(
+                imm as i32,
+                rd_p,
+                rs1_p,
+                funct3::store::SW,
+                opcode::STORE_FP,
+            ))
+        }
+        // C.ADDI, C.NOP
+        (0b01, 0b000) => Some(i_type(imm, rd, funct3::arith::ADD, rd, opcode::IMM)),
+        // C.JAL
+        (0b01, 0b001) => Some(j_type(j_imm, 1)),
+        // C.LI
+        (0b01, 0b010) => Some(i_type(imm, 0, funct3::arith::ADD, rd, opcode::IMM)),
+        // C.ADDI16SP
+        (0b01, 0b011) if rd == 2 => {
+            let imm = sext(
+                bits(12, 12) << 9
+                    | bits(4, 3) << 7
+                    | bits(5, 5) << 6
+                    | bits(2, 2) << 5
+                    | bits(6, 6) << 4,
+                10,
+            );
+            (imm != 0).then(|| i_type(imm, 2, funct3::arith::ADD, 2, opcode::IMM))
+        }
+        // C.LUI
+        (0b01, 0b011) => {
+            (imm != 0).then_some(((imm as u32) << 12) | (rd << 7) | opcode::LUI as u32)
+        }
+        (0b01, 0b100) => match bits(11, 10) {
+            // C.SRLI
+            0b00 => shamt.map(|shamt| i_type(shamt, rs1_p, funct3::arith::SR, rs1_p, opcode::IMM)),
+            // C.SRAI
+            0b01 => shamt.map(|shamt| {
+                let imm = (funct7::ALT as i32) << 5 | shamt;
+                i_type(imm, rs1_p, funct3::arith::SR, rs1_p, opcode::IMM)
+            }),
+            // C.ANDI
+            0b10 => Some(i_type(imm, rs1_p, funct3::arith::AND, rs1_p, opcode::IMM)),
+            // C.SUB, C.XOR, C.OR and C.AND, the others are RV64 only
+            _ if bits(12, 12) == 0 => {
+                let (funct7, funct3) = match bits(6, 5) {
+                    0b00 => (funct7::ALT, funct3::arith::ADD),
+                    0b01 => (funct7::BASE, funct3::arith::XOR),
+                    0b10 => (funct7::BASE, funct3::arith::OR),
+                    _ => (funct7::BASE, funct3::arith::AND),
+                };
+                Some(r_type(funct7, rd_p, rs1_p, funct3, rs1_p))
+            }
+            _ => None,
+        },
+        // C.J
+        (0b01, 0b101) => Some(j_type(j_imm, 0)),
+        // C.BEQZ
+        (0b01, 0b110) => Some(b_type(b_imm, 0, rs1_p, funct3::branch::BEQ)),
+        // C.BNEZ
+        (0b01, 0b111) => Some(b_type(b_imm, 0, rs1_p, funct3::branch::BNE)),
+        // C.SLLI
+        (0b10, 0b000) => shamt.map(|shamt| i_type(shamt, rd, funct3::arith::SLL, rd, opcode::IMM)),
+        // C.LWSP
+        (0b10, 0b010) if rd != 0 => {
+            let imm = bits(3, 2) << 6 | bits(12, 12) << 5 | bits(6, 4) << 2;
+            Some(i_type(imm as i32, 2, funct3::load::LW, rd, opcode::LOAD))
+        }
+        // C.FLWSP, f0 is a regular register
+        (0b10, 0b011) => {
+            let imm = bits(3, 2) << 6 | bits(12, 12) << 5 | bits(6, 4) << 2;
+            Some(i_type(imm as i32, 2, funct3::load::LW, rd, opcode::LOAD_FP))
+        }
+        (0b10, 0b100) => match (bits(12, 12), rd, rs2) {
+            (0, 0, 0) => None,
+            // C.JR
+            (0, _, 0) => Some(i_type(0, rd, 0, 0, opcode::JALR)),
+            // C.MV
+            (0, _, _) => Some(r_type(funct7::BASE, rs2, 0, funct3::arith::ADD, rd)),
+            (_, 0, 0) => Some(priv_inst::EBREAK),
+            // C.JALR
+            (_, _, 0) => Some(i_type(0, rd, 0, 1, opcode::JALR)),
+            // C.ADD
+            _ => Some(r_type(funct7::BASE, rs2, rd, funct3::arith::ADD, rd)),
+        },
+        // C.SWSP
+        (0b10, 0b110) => {
+            let imm = bits(8, 7) << 6 | bits(12, 9) << 2;
+            Some(s_type(imm as i32, rs2, 2, funct3::store::SW, opcode::STORE))
+        }
+        // C.FSWSP
+        (0b10, 0b111) => {
+            let imm = bits(8, 7) << 6 | bits(12, 9) << 2;
+            Some(s_type(
+                imm as i32,
+                rs2,
+                2,
+                funct3::store::SW,
+                opcode::STORE_FP,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// None if any of its bits is unknown, `index` 0 is the low half
+fn half(word: Word, index: usize) -> Option<u16> {
+    Some(word[index * 2]? as u16 | (word[index * 2 + 1]? as u16) << 8)
+}
+
+/// Sits between `Mdr` and `IR`. Picks the instruction the PC points at out of the fetched word
+/// and expands it if it is compressed, `raw_ir` is what was actually fetched.
+///
+/// A 32 bit instruction at the upper half of a word continues in the next one, `straddle` tells
+/// `Control` to keep the upper half with `load_upper` and fetch again.
+#[ComponentAttribute({
+"port": {
+    "input": [
+        ["data", "Word"],
+        ["pc", "Word"],
+        ["load_upper", "Byte"],
+        ["sel", "Byte"]
+    ],
+    "output": [
+        ["out_ir", "Word"],
+        ["raw_ir", "Word"],
+        ["straddle", "Byte"]
+    ],
+    "clock": true
+}
+})]
+pub struct RvcExpander {
+    /// The low half of a straddling instruction
+    upper: Option<u16>,
+}
+
+impl RvcExpander {
+    pub fn new(
+        component_id: ComponentId,
+        sim_manager: Arc<SimManager>,
+        ack_sender: Sender<EventId>,
+        data_receiver: Input,
+        pc_receiver: Input,
+        load_upper_receiver: Input,
+        sel_receiver: Input,
+        out_ir: Output,
+        raw_ir: Output,
+        straddle: Output,
+    ) -> Self {
+        let clock_channel = unbounded();
+        RvcExpander {
+            upper: None,
+            component_id,
+            sim_manager,
+            ack_sender,
+            clock_sender: clock_channel.0,
+            clock_receiver: clock_channel.1,
+            data_receiver,
+            data: Default::default(),
+            data_old: Default::default(),
+            pc_receiver,
+            pc: Default::default(),
+            pc_old: Default::default(),
+            load_upper_receiver,
+            load_upper: Default::default(),
+            load_upper_old: Default::default(),
+            sel_receiver,
+            sel: Default::default(),
+            sel_old: Default::default(),
+            out_ir,
+            raw_ir,
+            straddle,
+        }
+    }
+
+    fn init_impl(&mut self) {}
+
+    fn reset_impl(&mut self) {
+        self.upper = None;
+    }
+
+    fn poll_impl(&mut self) {}
+
+    fn on_clock(&mut self) {
+        if self.load_upper.is_something_nonzero() {
+            self.upper = half(self.data, 1);
+        }
+    }
+
+    fn on_comb(&mut self) {
+        let pc_at_upper = self.pc.get_bit(1) == Some(true);
+        let (low, high) = match self.sel.into() {
+            Some(mux_sel::ir::MDR_OUT) if pc_at_upper => (half(self.data, 1), None),
+            Some(mux_sel::ir::MDR_OUT) => (half(self.data, 0), half(self.data, 1)),
+            Some(mux_sel::ir::STRADDLE) => (self.upper, half(self.data, 0)),
+            _ => (None, None),
+        };
+
+        let (raw, expanded) = match (low, high) {
+            // the reserved encodings become 0, which is illegal too
+            (Some(low), _) if low & 0b11 != 0b11 => {
+                (Word::from(low as u32), Word::from(expand(low).unwrap_or(0)))
+            }
+            (Some(low), Some(high)) => {
+                let inst = Word::from((high as u32) << 16 | low as u32);
+                (inst, inst)
+            }
+            _ => (Word::unknown(), Word::unknown()),
+        };
+        let straddle = self.sel == Byte::from(mux_sel::ir::MDR_OUT)
+            && pc_at_upper
+            && low.is_some_and(|low| low & 0b11 == 0b11);
+
+        send_word!(self, self.out_ir, expanded);
+        send_word!(self, self.raw_ir, raw);
+        send_byte!(self, self.straddle, Byte::from(straddle as u8));
+    }
+}
+
+impl Debug for RvcExpander {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RvcExpander: {{data: {:?}, pc: {:?}, sel: {:?}, upper: {:?}}}",
+            self.data, self.pc, self.sel, self.upper
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        // from llvm-mc, the compressed form and what it stands for
+        let cases: [(u16, u32); 28] = [
+            (0x0040, 0x00410413), // c.addi4spn x8, x2, 4
+            (0x4144, 0x00452483), // c.lw x9, 4(x10)
+            (0xc144, 0x00952223), // c.sw x9, 4(x10)
+            (0x0001, 0x00000013), // c.nop
+            (0x157d, 0xfff50513), // c.addi x10, -1
+            (0x2011, 0x004000ef), // c.jal 4
+            (0x557d, 0xfff00513), // c.li x10, -1
+            (0x7139, 0xfc010113), // c.addi16sp x2, -64
+            (0x7541, 0xffff0537), // c.lui x10, 0xffff0
+            (0x8405, 0x40145413), // c.srai x8, 1
+            (0x8005, 0x00145413), // c.srli x8, 1
+            (0x987d, 0xfff47413), // c.andi x8, -1
+            (0x8c05, 0x40940433), // c.sub x8, x9
+            (0x8c25, 0x00944433), // c.xor x8, x9
+            (0x8c45, 0x00946433), // c.or x8, x9
+            (0x8c65, 0x00947433), // c.and x8, x9
+            (0xbff5, 0xffdff06f), // c.j -4
+            (0xdc75, 0xfe040ee3), // c.beqz x8, -4
+            (0xe011, 0x00041263), // c.bnez x8, 4
+            (0x0506, 0x00151513), // c.slli x10, 1
+            (0x4512, 0x00412503), // c.lwsp x10, 4(x2)
+            (0x8502, 0x00050067), // c.jr x10
+            (0x852e, 0x00b00533), // c.mv x10, x11
+            (0xc22a, 0x00a12223), // c.swsp x10, 4(x2)
+            (0x6144, 0x00452487), // c.flw f9, 4(x10)
+            (0xe144, 0x00952227), // c.fsw f9, 4(x10)
+            (0x6512, 0x00412507), // c.flwsp f10, 4(x2)
+            (0xe22a, 0x00a12227), // c.fswsp f10, 4(x2)
+        ];
+        for (inst, expected) in cases {
+            assert_eq!(expand(inst), Some(expected), "{:#06x}", inst);
+        }
+        assert_eq!(expand(0x9002), Some(priv_inst::EBREAK));
+        assert_eq!(expand(0x9502), Some(0x000500e7)); // c.jalr x10
+        assert_eq!(expand(0x952e), Some(0x00b50533)); // c.add x10, x11
+
+        // all zeros, C.ADDI4SPN with 0, C.LWSP to x0, C.JR x0, shamt of 32 and C.FLD
+        for reserved in [0x0000, 0x0004, 0x4002, 0x8002, 0x1006, 0x2000] {
+            assert_eq!(expand(reserved), None, "{:#06x}", reserved);
+        }
+    }
+}
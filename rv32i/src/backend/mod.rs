@@ -1,3 +1,5 @@
 pub mod component;
 pub mod core;
+pub mod plugins;
+pub mod syscall;
 pub mod util;
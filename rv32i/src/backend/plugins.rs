@@ -0,0 +1,180 @@
+use crate::backend::core::Core;
+use crate::backend::util::byte::Bytes;
+use crate::backend::util::types::States;
+use crate::backend::util::types::Word;
+use crate::backend::util::types::{amo, Byte};
+use rsim_core::logging::{Level, LogRecord};
+use std::fs::File;
+use std::io::Write;
+
+/// What `commit_logger` writes for a commit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommitFormat {
+    /// Like spike's `--log-commits`, with the register and memory writes
+    Spike,
+    /// The pc and the instruction only, for comparing control flow
+    Pc,
+}
+
+/// Writes a line for every committed instruction, attach it to `HookPoint::CycleEnd`
+pub fn commit_logger(mut commit_file: File, format: CommitFormat) -> impl FnMut(&Core) + Send {
+    move |core| {
+        // locking is fine here, we are not advancing the sim
+        let control = core.control.lock().unwrap();
+        let pc = core.pc.lock().unwrap();
+        let ir = core.ir.lock().unwrap();
+        let regfile = core.regfile.lock().unwrap();
+        let fp_regfile = core.fp_regfile.lock().unwrap();
+        let mar = core.mar.lock().unwrap();
+        let mem_ctl = core.mem_ctl.lock().unwrap();
+
+        if control.state == States::Fetch1
+            || control.state == States::Fetch2
+            || control.state == States::Fetch3
+            || control.state == States::Fetch4
+            || control.state == States::Fetch5
+            || control.state == States::Decode
+            || control.state == States::Store1
+            || control.state == States::Load1
+            || control.state == States::MulDiv1
+            || control.state == States::Amo1
+            || control.state == States::Fp1
+            || control.state == States::AddrCalc
+            || matches!(control.state, States::Trap(_))
+            || matches!(control.next_state, States::Trap(_))
+        {
+            return;
+        }
+
+        let mut line = String::new();
+
+        // spike shows compressed instructions as they were fetched, in 4 digits
+        let inst = if ir.is_compressed() {
+            format!("{:04x}", ir.raw_inner.get_value() & 0xFFFF)
+        } else {
+            format!("{}", ir.raw_inner)
+        };
+        line.push_str(&format!("core   0: 3 0x{} (0x{})", pc.data_inner, inst));
+        let pc_end = line.len();
+
+        if regfile.rd_wr.is_something_nonzero() && ir.get_rd_idx().is_something_nonzero() {
+            let raw_rd: u8 = Into::<Option<u8>>::into(ir.get_rd_idx()).unwrap();
+            if raw_rd < 10 {
+                line.push_str(&format!(" x{}  ", raw_rd))
+            } else {
+                line.push_str(&format!(" x{} ", raw_rd))
+            }
+            line.push_str(&format!("0x{}", regfile.rd_data));
+        }
+
+        // f0 is a regular register, so it is logged too
+        if fp_regfile.rd_wr.is_something_nonzero() {
+            let raw_rd: u8 = Into::<Option<u8>>::into(ir.get_rd_idx()).unwrap();
+            if raw_rd < 10 {
+                line.push_str(&format!(" f{}  ", raw_rd))
+            } else {
+                line.push_str(&format!(" f{} ", raw_rd))
+            }
+            line.push_str(&format!("0x{}", fp_regfile.rd_data));
+        }
+
+        if control.state == States::Load2 && control.get_rmask().is_something_nonzero() {
+            let rmask = Into::<Option<u8>>::into(control.get_rmask()).unwrap();
+            let mut byte_shift = 0;
+            for i in 0..4u8 {
+                if (rmask >> i) & 0x1 == 0x1 {
+                    byte_shift = i;
+                    break;
+                }
+            }
+            line.push_str(&format!(
+                " mem 0x{}",
+                (mar.data_inner & Word::from(0xFFFFFFFCu32)) + Byte::from(byte_shift)
+            ));
+        }
+
+        if control.state == States::Store2 && control.get_wmask().is_something_nonzero() {
+            let wmask = Into::<Option<u8>>::into(control.get_wmask()).unwrap();
+            let mut byte_shift = 0;
+            for i in 0..4u8 {
+                if (wmask >> i) & 0x1 == 0x1 {
+                    byte_shift = i;
+                    break;
+                }
+            }
+            let mut byte_count = 0;
+            for i in 0..4u8 {
+                if (wmask >> i) & 0x1 == 0x1 {
+                    byte_count += 1;
+                }
+            }
+
+            line.push_str(&format!(
+                " mem 0x{}",
+                (mar.data_inner & Word::from(0xFFFFFFFCu32)) + Byte::from(byte_shift)
+            ));
+            if let Some(data_out) = Into::<Option<u32>>::into(mem_ctl.cpu_wdata) {
+                let shifted_data = data_out >> (8 * byte_shift);
+                let data_out_str = match byte_count {
+                    1 => {
+                        format!("{}", Byte::from(shifted_data as u8))
+                    }
+                    2 => {
+                        format!("{}", Bytes::<2>::from(shifted_data as u16))
+                    }
+                    4 => {
+                        format!("{}", Word::from(shifted_data))
+                    }
+                    _ => "".to_string(),
+                };
+                line.push_str(&format!(" 0x{}", data_out_str));
+            }
+        }
+
+        // like spike, the read of an AMO then its write, SC only shows the write and only if it did
+        if control.state == States::Amo2 {
+            let addr = mar.data_inner;
+            if mem_ctl.cpu_amo_op != Byte::from(amo::SC) {
+                line.push_str(&format!(" mem 0x{}", addr));
+            }
+            if let Some(written) = mem_ctl.amo_written {
+                line.push_str(&format!(" mem 0x{} 0x{}", addr, written));
+            }
+        }
+
+        if format == CommitFormat::Pc {
+            line.truncate(pc_end);
+        }
+        line.push('\n');
+        commit_file.write_all(line.as_bytes()).unwrap();
+
+        // Every commit is there at debug level, a progress line every 1000 commits otherwise
+        let instructions_ran = core.get_instructions_ran();
+        let level = if instructions_ran.is_multiple_of(1000) {
+            Level::Info
+        } else {
+            Level::Debug
+        };
+        core.sim_manager.get_logger().log(&LogRecord {
+            level,
+            cycle: Some(core.sim_manager.get_curr_cycle()),
+            component_id: None,
+            component_name: "Core",
+            message: format_args!("commit #{}: {}", instructions_ran, line.trim_end()),
+        });
+    }
+}
+
+/// Instruction count and cycles per instruction, attach it to `HookPoint::InstructionRetired`
+pub fn instruction_stats(core: &Core) -> impl FnMut(&Core) + Send {
+    let stats = core.sim_manager.get_stats();
+    let instructions_ran = stats.counter("core", "instructions_ran");
+    let cycles_per_instruction = stats.histogram("core", "cycles_per_instruction", 1, 16);
+    let mut last_retired_cycle = core.sim_manager.get_curr_cycle();
+    move |core| {
+        let curr_cycle = core.sim_manager.get_curr_cycle();
+        instructions_ran.inc();
+        cycles_per_instruction.sample((curr_cycle - last_retired_cycle) as u64);
+        last_retired_cycle = curr_cycle;
+    }
+}
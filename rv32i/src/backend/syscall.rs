@@ -0,0 +1,40 @@
+use crate::backend::core::Core;
+
+/// The newlib (libgloss) syscall numbers, passed in a7
+pub mod number {
+    pub const OPENAT: u32 = 56;
+    pub const CLOSE: u32 = 57;
+    pub const LSEEK: u32 = 62;
+    pub const READ: u32 = 63;
+    pub const WRITE: u32 = 64;
+    pub const FSTAT: u32 = 80;
+    pub const EXIT: u32 = 93;
+    pub const EXIT_GROUP: u32 = 94;
+    pub const BRK: u32 = 214;
+    /// Older libgloss uses it instead of openat
+    pub const OPEN: u32 = 1024;
+}
+
+/// The errno values handlers usually need, returned negated in a0
+pub mod errno {
+    pub const EIO: u32 = 5;
+    pub const EBADF: u32 = 9;
+    pub const EINVAL: u32 = 22;
+    pub const ESPIPE: u32 = 29;
+    pub const ENOSYS: u32 = 38;
+}
+
+pub enum SyscallResult {
+    /// Goes to a0, errors as `-errno`
+    Return(u32),
+    /// The program is done, `Core::get_exit_code` returns the code from then on
+    Exit(u32),
+}
+
+/// Emulates what a kernel would do on ECALL, see `Core::set_syscall_handler`.
+///
+/// `args` are a0 to a5 and `core` is there to get at the buffers they point to, with `read_bytes`
+/// and `write_mem`.
+pub trait SyscallHandler: Send {
+    fn syscall(&mut self, core: &mut Core, number: u32, args: [u32; 6]) -> SyscallResult;
+}
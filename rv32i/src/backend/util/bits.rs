@@ -0,0 +1,393 @@
+#![allow(clippy::suspicious_arithmetic_impl)]
+#![allow(dead_code)]
+
+use crate::backend::util::byte::{sign_extend, ByteOrd, Bytes, Shra, SignedOrd};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+
+/// A bit vector of any width up to 128 bits, with the same per bit unknown tracking as `Bytes`
+///
+/// Bit 0 is the **least** significant bit
+///
+/// Meant for values that are not a whole number of bytes, e.g. register indices and instruction fields.
+/// Operators behave like their `Bytes` counterparts and return a vector as wide as the lhs.
+/// Conversions from and to `Bytes` need the widths to match, use `zext`/`truncate` first otherwise.
+///
+/// Support operations
+/// - the arithmetic, bitwise and shift operators of `Bytes`, plus `Shra`, `ByteOrd` and `SignedOrd`
+/// - `zext`, `sext` and `truncate` to change the width
+/// - `concat` to put two vectors side by side
+/// - `slice` and `set_slice` to access a range of bits
+///
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Bits<const N: usize> {
+    /// Bits past N are always known zeros
+    bits: Bytes<16>,
+}
+
+impl<const N: usize> Bits<N> {
+    const WIDTH_MASK: u128 = {
+        assert!(N >= 1 && N <= 128, "Bits can be 1 to 128 bits wide");
+        u128::MAX >> (128 - N)
+    };
+
+    /// Bits set in `unknown` are unknown, whatever `value` says for them
+    pub fn from_parts(value: u128, unknown: u128) -> Self {
+        Bits {
+            bits: Bytes::from_parts(value & Self::WIDTH_MASK, unknown & Self::WIDTH_MASK),
+        }
+    }
+    /// Turns the bits set in `high_z` into Z
+    pub fn with_high_z(self, high_z: u128) -> Self {
+        Bits {
+            bits: self.bits.with_high_z(high_z & Self::WIDTH_MASK),
+        }
+    }
+    fn from_raw(value: u128, unknown: u128, high_z: u128) -> Self {
+        Self::from_parts(value, unknown).with_high_z(high_z)
+    }
+    fn wrap(bits: Bytes<16>) -> Self {
+        Self::from_raw(
+            bits.get_value(),
+            bits.get_unknown_mask(),
+            bits.get_high_z_mask(),
+        )
+    }
+    /// Nothing driving any of the bits
+    pub fn high_z() -> Self {
+        Self::zeros().with_high_z(u128::MAX)
+    }
+    pub fn unknown() -> Self {
+        Self::from_parts(0, u128::MAX)
+    }
+    pub fn zeros() -> Self {
+        Self::from_parts(0, 0)
+    }
+    /// The known bits, unknown bits read as 0
+    pub fn get_value(&self) -> u128 {
+        self.bits.get_value()
+    }
+    pub fn get_unknown_mask(&self) -> u128 {
+        self.bits.get_unknown_mask()
+    }
+    pub fn get_high_z_mask(&self) -> u128 {
+        self.bits.get_high_z_mask()
+    }
+    /// Z counts as unknown too
+    pub fn has_unknown(&self) -> bool {
+        self.bits.has_unknown()
+    }
+    pub fn has_high_z(&self) -> bool {
+        self.bits.has_high_z()
+    }
+    /// True if none of the known bits are set
+    pub fn is_zero(&self) -> bool {
+        self.bits.is_zero()
+    }
+    pub fn is_something_nonzero(&self) -> bool {
+        self.bits.is_something_nonzero()
+    }
+    pub fn get_bit(&self, index: usize) -> Option<bool> {
+        assert!(index < N);
+        self.bits.get_bit(index)
+    }
+    pub fn set_bit(&mut self, index: usize, bit: Option<bool>) {
+        assert!(index < N);
+        self.bits.set_bit(index, bit);
+    }
+    /// Widens to `M` bits, the new bits are zeros
+    pub fn zext<const M: usize>(self) -> Bits<M> {
+        assert!(M >= N, "zext can not narrow a Bits<{}> to Bits<{}>", N, M);
+        Bits::wrap(self.bits)
+    }
+    /// Widens to `M` bits, the new bits are copies of the sign bit, unknown if the sign bit is
+    pub fn sext<const M: usize>(self) -> Bits<M> {
+        assert!(M >= N, "sext can not narrow a Bits<{}> to Bits<{}>", N, M);
+        Bits::from_raw(
+            sign_extend(self.get_value(), N) as u128,
+            sign_extend(self.get_unknown_mask(), N) as u128,
+            sign_extend(self.get_high_z_mask(), N) as u128,
+        )
+    }
+    /// Keeps the low `M` bits
+    pub fn truncate<const M: usize>(self) -> Bits<M> {
+        assert!(
+            M <= N,
+            "truncate can not widen a Bits<{}> to Bits<{}>",
+            N,
+            M
+        );
+        Bits::wrap(self.bits)
+    }
+    /// `self` in the high bits, `low` in the low bits, `M` has to be `N + L`
+    pub fn concat<const L: usize, const M: usize>(self, low: Bits<L>) -> Bits<M> {
+        assert_eq!(M, N + L, "concat of Bits<{}> and Bits<{}>", N, L);
+        Bits::from_raw(
+            (self.get_value() << L) | low.get_value(),
+            (self.get_unknown_mask() << L) | low.get_unknown_mask(),
+            (self.get_high_z_mask() << L) | low.get_high_z_mask(),
+        )
+    }
+    /// Bits `low..low + M`
+    pub fn slice<const M: usize>(self, low: usize) -> Bits<M> {
+        assert!(low + M <= N, "slice {}..{} of Bits<{}>", low, low + M, N);
+        Bits::from_raw(
+            self.get_value() >> low,
+            self.get_unknown_mask() >> low,
+            self.get_high_z_mask() >> low,
+        )
+    }
+    /// Overwrites bits `low..low + M`
+    pub fn set_slice<const M: usize>(&mut self, low: usize, slice: Bits<M>) {
+        assert!(low + M <= N, "slice {}..{} of Bits<{}>", low, low + M, N);
+        let mask = Bits::<M>::WIDTH_MASK << low;
+        *self = Self::from_raw(
+            (self.get_value() & !mask) | (slice.get_value() << low),
+            (self.get_unknown_mask() & !mask) | (slice.get_unknown_mask() << low),
+            (self.get_high_z_mask() & !mask) | (slice.get_high_z_mask() << low),
+        );
+    }
+    /// See `Bytes::tri_state`
+    pub fn tri_state(self, rhs: Self) -> (Self, bool) {
+        let (bits, contention) = self.bits.tri_state(rhs.bits);
+        (Bits { bits }, contention)
+    }
+    /// See `Bytes::wired_or`
+    pub fn wired_or(self, rhs: Self) -> Self {
+        Bits {
+            bits: self.bits.wired_or(rhs.bits),
+        }
+    }
+}
+
+impl<const N: usize> Default for Bits<N> {
+    fn default() -> Self {
+        Bits::unknown()
+    }
+}
+
+impl<const N: usize> Display for Bits<N> {
+    /// Same digits as `Bytes`, as many as it takes to hold N bits
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = self.bits.to_string();
+        write!(f, "{}", &digits[digits.len() - N.div_ceil(4)..])
+    }
+}
+
+impl<const N: usize> Debug for Bits<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self, f)
+    }
+}
+
+impl<const N: usize, const T: usize> From<Bytes<T>> for Bits<N> {
+    fn from(value: Bytes<T>) -> Self {
+        assert_eq!(N, 8 * T, "Bits<{}> from Bytes<{}>", N, T);
+        Bits::from_raw(
+            value.get_value(),
+            value.get_unknown_mask(),
+            value.get_high_z_mask(),
+        )
+    }
+}
+
+impl<const N: usize, const T: usize> From<Bits<N>> for Bytes<T> {
+    fn from(value: Bits<N>) -> Self {
+        assert_eq!(N, 8 * T, "Bytes<{}> from Bits<{}>", T, N);
+        Bytes::from_parts(value.get_value(), value.get_unknown_mask())
+            .with_high_z(value.get_high_z_mask())
+    }
+}
+
+/// The integer has to fit, bits past the width of the integer are unknown
+macro_rules! impl_from_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl<const N: usize> From<$uint> for Bits<N> {
+                fn from(value: $uint) -> Self {
+                    assert!(
+                        value as u128 & !Self::WIDTH_MASK == 0,
+                        "{:#x} does not fit in Bits<{}>",
+                        value,
+                        N
+                    );
+                    Bits::from_parts(value as u128, !u128::from(<$uint>::MAX))
+                }
+            }
+
+            impl<const N: usize> From<Bits<N>> for Option<$uint> {
+                fn from(val: Bits<N>) -> Self {
+                    val.bits.into()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_uint!(u8, u16, u32, u64, u128);
+
+/// Both sides are widened to 128 bits, everything past N is dropped again by `wrap`
+macro_rules! impl_bin_op {
+    ($($op:ident, $func:ident, $op_assign:ident, $func_assign:ident);*) => {
+        $(
+            impl<const N: usize, const M: usize> std::ops::$op<Bits<M>> for Bits<N> {
+                type Output = Bits<N>;
+
+                fn $func(self, rhs: Bits<M>) -> Self::Output {
+                    Bits::wrap(std::ops::$op::$func(self.bits, rhs.bits))
+                }
+            }
+
+            impl<const N: usize, const M: usize> std::ops::$op_assign<Bits<M>> for Bits<N> {
+                fn $func_assign(&mut self, rhs: Bits<M>) {
+                    *self = std::ops::$op::$func(*self, rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_bin_op!(
+    Add, add, AddAssign, add_assign;
+    Sub, sub, SubAssign, sub_assign;
+    BitAnd, bitand, BitAndAssign, bitand_assign;
+    BitOr, bitor, BitOrAssign, bitor_assign;
+    BitXor, bitxor, BitXorAssign, bitxor_assign;
+    Shl, shl, ShlAssign, shl_assign;
+    Shr, shr, ShrAssign, shr_assign
+);
+
+impl<const N: usize> std::ops::Not for Bits<N> {
+    type Output = Bits<N>;
+
+    fn not(self) -> Self::Output {
+        Bits::wrap(!self.bits)
+    }
+}
+
+impl<const N: usize, const M: usize> Shra<Bits<M>> for Bits<N> {
+    type Output = Bits<N>;
+
+    fn shra(self, rhs: Bits<M>) -> Bits<N> {
+        Bits::wrap(self.sext::<128>().bits.shra(rhs.bits))
+    }
+}
+
+impl<const N: usize, const M: usize> ByteOrd<Bits<M>> for Bits<N> {
+    fn byte_cmp(self, rhs: Bits<M>) -> Ordering {
+        self.bits.byte_cmp(rhs.bits)
+    }
+
+    fn byte_partial_cmp(self, rhs: Bits<M>) -> Option<Ordering> {
+        self.bits.byte_partial_cmp(rhs.bits)
+    }
+
+    fn byte_eq(self, rhs: Bits<M>) -> Option<bool> {
+        self.bits.byte_eq(rhs.bits)
+    }
+}
+
+impl<const N: usize, const M: usize> SignedOrd<Bits<M>> for Bits<N> {
+    fn signed_cmp(self, rhs: Bits<M>) -> Ordering {
+        self.signed_partial_cmp(rhs).unwrap_or(Ordering::Equal)
+    }
+
+    fn signed_partial_cmp(self, rhs: Bits<M>) -> Option<Ordering> {
+        self.sext::<128>()
+            .bits
+            .signed_partial_cmp(rhs.sext::<128>().bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::util::types::Word;
+    use rand::random;
+
+    #[test]
+    fn test_known() {
+        for _ in 0..u16::MAX {
+            let a = random::<u8>() & 0x1F;
+            let b = random::<u8>() & 0x1F;
+            let lhs = Bits::<5>::from(a);
+            let rhs = Bits::<5>::from(b);
+
+            assert_eq!(lhs + rhs, Bits::<5>::from(a.wrapping_add(b) & 0x1F));
+            assert_eq!(lhs - rhs, Bits::<5>::from(a.wrapping_sub(b) & 0x1F));
+            assert_eq!(lhs & rhs, Bits::<5>::from(a & b));
+            assert_eq!(lhs | rhs, Bits::<5>::from(a | b));
+            assert_eq!(lhs ^ rhs, Bits::<5>::from(a ^ b));
+            assert_eq!(!lhs, Bits::<5>::from(!a & 0x1F));
+            assert_eq!(lhs.byte_cmp(rhs), a.cmp(&b));
+            // Sign extending to 8 bits makes i8 a fair reference
+            let signed = |v: u8| ((v << 3) as i8) >> 3;
+            assert_eq!(lhs.signed_cmp(rhs), signed(a).cmp(&signed(b)));
+
+            let shift = Bits::<3>::from(random::<u8>() & 0x7);
+            let s = Into::<Option<u8>>::into(shift).unwrap();
+            assert_eq!(lhs << shift, Bits::<5>::from((a << s) & 0x1F));
+            assert_eq!(lhs >> shift, Bits::<5>::from(a >> s));
+            assert_eq!(
+                lhs.shra(shift),
+                Bits::<5>::from((signed(a) >> s.min(4)) as u8 & 0x1F)
+            );
+        }
+    }
+
+    #[test]
+    fn test_width() {
+        let imm = Bits::<12>::from(0x800u16);
+        assert_eq!(imm.zext::<32>(), Bits::<32>::from(0x800u32));
+        assert_eq!(imm.sext::<32>(), Bits::<32>::from(0xFFFFF800u32));
+        assert_eq!(imm.truncate::<8>(), Bits::<8>::zeros());
+
+        // An unknown sign bit is unknown in every bit it is copied to
+        let imm = Bits::<4>::from_parts(0b0101, 0b1000);
+        assert_eq!(imm.sext::<8>(), Bits::<8>::from_parts(0b0101, 0b1111_1000));
+        assert_eq!(format!("{}", imm.sext::<8>()), "xX");
+        assert_eq!(format!("{}", Bits::<5>::from(0x1Fu8)), "1f");
+    }
+
+    #[test]
+    fn test_high_z() {
+        let bus = Bits::<12>::from(0xA5u16).concat::<4, 16>(Bits::<4>::high_z());
+        assert_eq!(format!("{}", bus), "0a5z");
+        assert_eq!(bus.slice::<4>(0), Bits::<4>::high_z());
+        assert_eq!(bus.slice::<8>(4), Bits::<8>::from(0xA5u8));
+        assert_eq!(Bytes::<2>::from(bus).get_high_z_mask(), 0xF);
+        let (resolved, contention) = bus.tri_state(Bits::<16>::from(0x0A53u16));
+        assert!(!contention);
+        assert_eq!(resolved, Bits::<16>::from(0x0A53u16));
+    }
+
+    #[test]
+    fn test_slice_concat() {
+        // sw x2, 16(x3)
+        let inst = Bits::<32>::from(0x0021a823u32);
+        assert_eq!(inst.slice::<7>(0), Bits::<7>::from(0b0100011u8));
+        assert_eq!(inst.slice::<5>(15), Bits::<5>::from(3u8));
+        assert_eq!(inst.slice::<5>(20), Bits::<5>::from(2u8));
+        let s_imm = inst
+            .slice::<7>(25)
+            .concat::<5, 12>(inst.slice::<5>(7))
+            .sext::<32>();
+        assert_eq!(s_imm, Bits::<32>::from(16u32));
+
+        let mut inst = inst;
+        inst.set_slice(7, Bits::<5>::unknown());
+        assert_eq!(inst.slice::<5>(7), Bits::<5>::unknown());
+        assert_eq!(inst.slice::<7>(0), Bits::<7>::from(0b0100011u8));
+        assert_eq!(format!("{}", inst), "0021axX3");
+    }
+
+    #[test]
+    fn test_bytes_interop() {
+        let word = Word::from(0xDEADBEEFu32);
+        let bits = Bits::<32>::from(word);
+        assert_eq!(Word::from(bits), word);
+        assert_eq!(Into::<Option<u32>>::into(bits), Some(0xDEADBEEF));
+        assert_eq!(Into::<Option<u8>>::into(Bits::<5>::unknown()), None);
+        assert_eq!(Bits::<5>::from(7u8).get_bit(2), Some(true));
+    }
+}
@@ -3,13 +3,18 @@
 
 use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
-use std::ops::{Index, IndexMut};
+use std::ops::{Deref, DerefMut, Index};
 use std::option::Option;
 
-/// A generic Byte type
+/// A generic Byte type, up to 16 bytes wide
 ///
 /// Bytes[0] is the **least** significant byte
 ///
+/// Every bit is either known, unknown (X) or high impedance (Z). Operators propagate unknowns bit by bit and pessimistically:
+/// a result bit is only known if it comes out the same for every value the unknown input bits could take,
+/// e.g. `0b0000_01X0 + 0b0000_0001` is `0b0000_01X1`, and `0bX & 0b0` is `0b0`.
+/// Z only means something to bus resolution, see `tri_state` and `wired_or`, operators read it as X.
+///
 /// Due to generic limitations (pending stable generic_const_exprs),
 /// operation will return Bytes of the same size as lhs, as opposed to the bigger of the two.
 ///
@@ -31,43 +36,193 @@ use std::option::Option;
 ///
 /// Note:
 /// - DO NOT use the derived Ord for comparison, use either `byte_cmp` or `signed_cmp`
-/// - Left/Right shifting assumes the rhs is within usize, if you need to shift more than 8^64, you have better things to use than this simulator
+/// - Shifting by an amount with unknown bits gives an unknown result
 /// - Bytes are treated as **unsigned**
-/// - Operation will have funky result if unknowns exist in add/sub operands
+/// - `bytes[i]` is `None` as soon as one bit of the byte is unknown, use `get_byte` to keep the known bits
+/// - A width past 16 bytes is a compile error wherever the type is used
+/// - `data`/`data_mut` give the bytes as `[Option<u8>; T]` for code written against the old container
 ///
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Bytes<const T: usize> {
-    /// The container for the bytes, exposed for easier manipulation.
-    /// Users should prefer the implemented traits over directly manipulating the container.
-    pub data: [Option<u8>; T],
+    /// Known bits, unknown bits are always 0 here so that the derived Eq and Hash work
+    value: u128,
+    /// Set for every unknown bit, never set past the width
+    unknown: u128,
+    /// Set for every high impedance bit, these are unknown as well
+    high_z: u128,
+}
+
+static BYTE_VALUES: [Option<u8>; 256] = {
+    let mut values = [None; 256];
+    let mut i = 0;
+    while i < 256 {
+        values[i] = Some(i as u8);
+        i += 1;
+    }
+    values
+};
+
+static UNKNOWN_BYTE: Option<u8> = None;
+
+pub(crate) fn sign_extend(value: u128, bits: usize) -> i128 {
+    ((value << (128 - bits)) as i128) >> (128 - bits)
 }
 
 impl<const T: usize> Bytes<T> {
+    /// Evaluated when the type is monomorphized, so a bad width fails the build rather than a run
+    const WIDTH_CHECK: () = assert!(T >= 1 && T <= 16, "Bytes can be 1 to 16 bytes wide");
+
+    const BITS: usize = {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::WIDTH_CHECK;
+        T * 8
+    };
+
+    const WIDTH_MASK: u128 = u128::MAX >> (128 - Self::BITS);
+
+    const SIGN_BIT: u128 = 1 << (Self::BITS - 1);
+
+    /// Bits set in `unknown` are unknown, whatever `value` says for them
+    pub fn from_parts(value: u128, unknown: u128) -> Self {
+        let unknown = unknown & Self::WIDTH_MASK;
+        Bytes {
+            value: value & Self::WIDTH_MASK & !unknown,
+            unknown,
+            high_z: 0,
+        }
+    }
+    /// Turns the bits set in `high_z` into Z
+    pub fn with_high_z(self, high_z: u128) -> Self {
+        let high_z = (high_z & Self::WIDTH_MASK) | self.high_z;
+        Bytes {
+            value: self.value & !high_z,
+            unknown: self.unknown | high_z,
+            high_z,
+        }
+    }
+    /// Nothing driving any of the bits
+    pub fn high_z() -> Self {
+        Self::zeros().with_high_z(u128::MAX)
+    }
     pub fn unknown() -> Self {
-        Bytes { data: [None; T] }
+        Self::from_parts(0, u128::MAX)
     }
     pub fn zeros() -> Self {
-        Bytes { data: [Some(0); T] }
+        Self::from_parts(0, 0)
+    }
+    /// The known bits, unknown bits read as 0
+    pub fn get_value(&self) -> u128 {
+        self.value
+    }
+    pub fn get_unknown_mask(&self) -> u128 {
+        self.unknown
+    }
+    pub fn get_high_z_mask(&self) -> u128 {
+        self.high_z
     }
+    /// Z counts as unknown too
     pub fn has_unknown(&self) -> bool {
-        for byte in self.data {
-            if byte.is_none() {
-                return true;
-            }
-        }
-        false
+        self.unknown != 0
+    }
+    pub fn has_high_z(&self) -> bool {
+        self.high_z != 0
     }
+    /// True if none of the known bits are set
     pub fn is_zero(&self) -> bool {
-        for byte in self.data {
-            if byte.is_some() && byte.unwrap() != 0 {
-                return false;
-            }
-        }
-        true
+        self.value == 0
     }
     pub fn is_something_nonzero(&self) -> bool {
         !self.has_unknown() && !self.is_zero()
     }
+    pub fn get_bit(&self, index: usize) -> Option<bool> {
+        assert!(index < Self::BITS);
+        if (self.unknown >> index) & 0x1 == 0x1 {
+            None
+        } else {
+            Some((self.value >> index) & 0x1 == 0x1)
+        }
+    }
+    pub fn set_bit(&mut self, index: usize, bit: Option<bool>) {
+        assert!(index < Self::BITS);
+        let (value, unknown) = match bit {
+            Some(bit) => (bit as u128, 0),
+            None => (0, 1),
+        };
+        *self = Self::from_parts(
+            (self.value & !(1 << index)) | (value << index),
+            (self.unknown & !(1 << index)) | (unknown << index),
+        );
+    }
+    /// Byte `index`, including the known bits of a partially unknown byte
+    pub fn get_byte(&self, index: usize) -> Bytes<1> {
+        assert!(index < T);
+        Bytes::from_parts(self.value >> (8 * index), self.unknown >> (8 * index))
+            .with_high_z(self.high_z >> (8 * index))
+    }
+    pub fn set_byte(&mut self, index: usize, byte: Bytes<1>) {
+        assert!(index < T);
+        let shift = 8 * index;
+        *self = Self::from_parts(
+            (self.value & !(0xFF << shift)) | (byte.value << shift),
+            (self.unknown & !(0xFF << shift)) | (byte.unknown << shift),
+        )
+        .with_high_z((self.high_z & !(0xFF << shift)) | (byte.high_z << shift));
+    }
+    /// The bytes as the old container held them, `None` for a byte with any unknown bit
+    pub fn data(&self) -> [Option<u8>; T] {
+        std::array::from_fn(|i| self[i])
+    }
+    /// Writes through to the bytes when dropped, `bytes.data_mut()[i] = Some(0xff)` works like
+    /// indexing the old container did. Bytes left untouched keep their partially known bits
+    pub fn data_mut(&mut self) -> DataMut<'_, T> {
+        DataMut {
+            data: self.data(),
+            original: self.data(),
+            bytes: self,
+        }
+    }
+    /// Resolves two drivers of the same net, Z gives way to the other driver.
+    /// Bits both sides drive are X unless both drive the same known value, the flag is set if that happened.
+    pub fn tri_state(self, rhs: Self) -> (Self, bool) {
+        let self_drives = !self.high_z & Self::WIDTH_MASK;
+        let rhs_drives = !rhs.high_z & Self::WIDTH_MASK;
+        let only_self = self_drives & !rhs_drives;
+        let only_rhs = rhs_drives & !self_drives;
+        let both = self_drives & rhs_drives;
+        let agree = both & !self.unknown & !rhs.unknown & !(self.value ^ rhs.value);
+        let fight = both & !agree;
+        let resolved = Self::from_parts(
+            (self.value & (only_self | agree)) | (rhs.value & only_rhs),
+            (self.unknown & only_self) | (rhs.unknown & only_rhs) | fight,
+        )
+        .with_high_z(self.high_z & rhs.high_z);
+        (resolved, fight != 0)
+    }
+    /// Resolves two drivers of a wired-or net, Z does not pull a bit up and is only left where neither side drives
+    pub fn wired_or(self, rhs: Self) -> Self {
+        let pull_downs = |bytes: Self| Self::from_parts(bytes.value, bytes.unknown & !bytes.high_z);
+        (pull_downs(self) | pull_downs(rhs)).with_high_z(self.high_z & rhs.high_z)
+    }
+    /// Zero extends or truncates to `R` bytes
+    fn resize<const R: usize>(self) -> Bytes<R> {
+        Bytes::from_parts(self.value, self.unknown)
+    }
+    /// Smallest and largest value the unknown bits allow for
+    fn unsigned_range(&self) -> (u128, u128) {
+        (self.value, self.value | self.unknown)
+    }
+    fn signed_range(&self) -> (i128, i128) {
+        let min = self.value | (self.unknown & Self::SIGN_BIT);
+        let max = self.value | (self.unknown & !Self::SIGN_BIT);
+        (sign_extend(min, Self::BITS), sign_extend(max, Self::BITS))
+    }
+    fn shift_amount(&self) -> Option<usize> {
+        if self.has_unknown() {
+            None
+        } else {
+            Some(self.value.min(u128::from(u32::MAX)) as usize)
+        }
+    }
 }
 
 impl<const T: usize> Default for Bytes<T> {
@@ -77,14 +232,20 @@ impl<const T: usize> Default for Bytes<T> {
 }
 
 impl<const T: usize> Display for Bytes<T> {
+    /// Hex digits, `x`/`z` for an unknown/high impedance nibble, `X`/`Z` for a partially unknown one
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let value = self
-            .data
-            .iter()
+        let value = (0..T * 2)
             .rev()
-            .map(|x| {
-                x.map(|byte| format!("{:02x}", byte))
-                    .unwrap_or("xx".to_string())
+            .map(|nibble| {
+                let unknown = (self.unknown >> (4 * nibble)) & 0xF;
+                let high_z = (self.high_z >> (4 * nibble)) & 0xF;
+                match (unknown, high_z) {
+                    (0, _) => format!("{:x}", (self.value >> (4 * nibble)) & 0xF),
+                    (_, 0xF) => "z".to_string(),
+                    (_, 0) if unknown == 0xF => "x".to_string(),
+                    (_, 0) => "X".to_string(),
+                    _ => "Z".to_string(),
+                }
             })
             .collect::<String>();
         write!(f, "{}", value)
@@ -100,253 +261,166 @@ impl<const T: usize> Debug for Bytes<T> {
 impl<const T: usize> Index<usize> for Bytes<T> {
     type Output = Option<u8>;
 
+    /// None if any bit of the byte is unknown
     fn index(&self, index: usize) -> &Self::Output {
-        self.data.index(index)
-    }
-}
-
-impl<const T: usize> IndexMut<usize> for Bytes<T> {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        self.data.index_mut(index)
+        assert!(index < T);
+        if (self.unknown >> (8 * index)) & 0xFF != 0 {
+            &UNKNOWN_BYTE
+        } else {
+            &BYTE_VALUES[((self.value >> (8 * index)) & 0xFF) as usize]
+        }
     }
 }
 
-pub trait ByteOrd<Rhs = Self> {
-    fn byte_cmp(self, rhs: Rhs) -> Ordering;
-}
-impl<const T: usize, const R: usize> ByteOrd<Bytes<R>> for Bytes<T> {
-    fn byte_cmp(self, other: Bytes<R>) -> Ordering {
-        if self.has_unknown() || other.has_unknown() {
-            return Ordering::Equal;
-        }
-
-        // if self is longer than other
-        for i in R..T {
-            if self[i].unwrap() > 0 {
-                return Ordering::Greater;
-            }
-        }
-
-        // if other is longer than self
-        for i in T..R {
-            if other[i].unwrap() > 0 {
-                return Ordering::Less;
+impl<const T: usize> From<[Option<u8>; T]> for Bytes<T> {
+    fn from(data: [Option<u8>; T]) -> Self {
+        let mut bytes = Bytes::unknown();
+        for (i, byte) in data.into_iter().enumerate() {
+            if let Some(byte) = byte {
+                bytes.set_byte(i, Bytes::from(byte));
             }
         }
-
-        // they are the same length
-        for i in (0..T).rev() {
-            match self[i].unwrap().cmp(&other[i].unwrap()) {
-                Ordering::Greater => return Ordering::Greater,
-                Ordering::Less => return Ordering::Less,
-                _ => {}
-            }
-        }
-
-        Ordering::Equal
+        bytes
     }
 }
 
-pub trait SignedOrd<Rhs = Self> {
-    fn signed_cmp(self, rhs: Rhs) -> Ordering;
+/// See `Bytes::data_mut`
+pub struct DataMut<'a, const T: usize> {
+    bytes: &'a mut Bytes<T>,
+    data: [Option<u8>; T],
+    original: [Option<u8>; T],
 }
 
-impl<const T: usize, const R: usize> SignedOrd<Bytes<R>> for Bytes<T> {
-    fn signed_cmp(self, rhs: Bytes<R>) -> Ordering {
-        if self.has_unknown() || rhs.has_unknown() {
-            return Ordering::Equal;
-        }
-
-        let self_is_negative = (self[T - 1].unwrap() >> 7) & 0x1 == 0x1;
-        let other_is_negative = (rhs[R - 1].unwrap() >> 7) & 0x1 == 0x1;
-
-        if self_is_negative != other_is_negative {
-            return if self_is_negative && !other_is_negative {
-                Ordering::Less
-            } else {
-                Ordering::Greater
-            };
-        }
+impl<const T: usize> Deref for DataMut<'_, T> {
+    type Target = [Option<u8>; T];
 
-        let mut self_no_msb = self;
-        self_no_msb[T - 1] = Some(self_no_msb[T - 1].unwrap() & 0x7F);
-        let mut other_no_msb = rhs;
-        other_no_msb[T - 1] = Some(other_no_msb[T - 1].unwrap() & 0x7F);
-
-        // if self is longer than other
-        for i in R..T {
-            if self_no_msb[i].unwrap() > 0 {
-                return if self_is_negative {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                };
-            }
-        }
-
-        // if other is longer than self
-        for i in T..R {
-            if other_no_msb[i].unwrap() > 0 {
-                return if self_is_negative {
-                    Ordering::Greater
-                } else {
-                    Ordering::Less
-                };
-            }
-        }
-
-        self.byte_cmp(rhs)
+    fn deref(&self) -> &Self::Target {
+        &self.data
     }
 }
 
-impl<const T: usize> From<u8> for Bytes<T> {
-    fn from(value: u8) -> Self {
-        assert!(T >= 1);
-        let mut result = Bytes::unknown();
-        for i in 0..1 {
-            result.data[i] = Some(value);
-        }
-        result
+impl<const T: usize> DerefMut for DataMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
     }
 }
 
-impl<const T: usize> From<u16> for Bytes<T> {
-    fn from(value: u16) -> Self {
-        assert!(T >= 2);
-        let mut result = Bytes::unknown();
-        for i in 0..2 {
-            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+impl<const T: usize> Drop for DataMut<'_, T> {
+    fn drop(&mut self) {
+        for i in 0..T {
+            if self.data[i] != self.original[i] {
+                let byte = self.data[i].map_or_else(Bytes::unknown, Bytes::from);
+                self.bytes.set_byte(i, byte);
+            }
         }
-        result
     }
 }
 
-impl<const T: usize> From<u32> for Bytes<T> {
-    fn from(value: u32) -> Self {
-        assert!(T >= 4);
-        let mut result = Bytes::unknown();
-        for i in 0..4 {
-            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
-        }
-        result
-    }
+pub trait ByteOrd<Rhs = Self> {
+    /// `Ordering::Equal` if the unknowns make the order ambiguous
+    fn byte_cmp(self, rhs: Rhs) -> Ordering;
+    /// None if the order depends on unknown bits
+    fn byte_partial_cmp(self, rhs: Rhs) -> Option<Ordering>;
+    /// None if the known bits agree but there are unknowns
+    fn byte_eq(self, rhs: Rhs) -> Option<bool>;
 }
 
-impl<const T: usize> From<u64> for Bytes<T> {
-    fn from(value: u64) -> Self {
-        assert!(T >= 8);
-        let mut result = Bytes::unknown();
-        for i in 0..8 {
-            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+impl<const T: usize, const R: usize> ByteOrd<Bytes<R>> for Bytes<T> {
+    fn byte_cmp(self, other: Bytes<R>) -> Ordering {
+        self.byte_partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+
+    fn byte_partial_cmp(self, other: Bytes<R>) -> Option<Ordering> {
+        let (self_min, self_max) = self.unsigned_range();
+        let (other_min, other_max) = other.unsigned_range();
+        if self_max < other_min {
+            Some(Ordering::Less)
+        } else if self_min > other_max {
+            Some(Ordering::Greater)
+        } else if !self.has_unknown() && !other.has_unknown() {
+            Some(Ordering::Equal)
+        } else {
+            None
         }
-        result
     }
-}
 
-impl<const T: usize> From<u128> for Bytes<T> {
-    fn from(value: u128) -> Self {
-        assert!(T >= 16);
-        let mut result = Bytes::unknown();
-        for i in 0..16 {
-            result.data[i] = Some(((value >> (8 * i)) & 0xFF) as u8);
+    fn byte_eq(self, other: Bytes<R>) -> Option<bool> {
+        let unknown = self.unknown | other.unknown;
+        if (self.value ^ other.value) & !unknown != 0 {
+            Some(false)
+        } else if unknown == 0 {
+            Some(true)
+        } else {
+            None
         }
-        result
     }
 }
 
-impl<const T: usize> From<Bytes<T>> for Option<u8> {
-    fn from(val: Bytes<T>) -> Self {
-        assert!(T >= 1);
-        val.data[0]
-    }
+pub trait SignedOrd<Rhs = Self> {
+    /// `Ordering::Equal` if the unknowns make the order ambiguous
+    fn signed_cmp(self, rhs: Rhs) -> Ordering;
+    /// None if the order depends on unknown bits
+    fn signed_partial_cmp(self, rhs: Rhs) -> Option<Ordering>;
 }
 
-impl<const T: usize> From<Bytes<T>> for Option<u16> {
-    fn from(val: Bytes<T>) -> Self {
-        let mut ret = Some(0);
-        for i in 0..T.min(2) {
-            if val.data[i].is_none() {
-                ret = None;
-                break;
-            } else {
-                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u16) << (i * 8));
-            }
+impl<const T: usize, const R: usize> SignedOrd<Bytes<R>> for Bytes<T> {
+    fn signed_cmp(self, rhs: Bytes<R>) -> Ordering {
+        self.signed_partial_cmp(rhs).unwrap_or(Ordering::Equal)
+    }
+
+    fn signed_partial_cmp(self, rhs: Bytes<R>) -> Option<Ordering> {
+        let (self_min, self_max) = self.signed_range();
+        let (other_min, other_max) = rhs.signed_range();
+        if self_max < other_min {
+            Some(Ordering::Less)
+        } else if self_min > other_max {
+            Some(Ordering::Greater)
+        } else if !self.has_unknown() && !rhs.has_unknown() {
+            Some(Ordering::Equal)
+        } else {
+            None
         }
-        ret
     }
 }
 
-impl<const T: usize> From<Bytes<T>> for Option<u32> {
-    fn from(val: Bytes<T>) -> Self {
-        let mut ret = Some(0);
-        for i in 0..T.min(4) {
-            if val.data[i].is_none() {
-                ret = None;
-                break;
-            } else {
-                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u32) << (i * 8));
+/// The low bytes are set from the integer, the rest are unknown
+macro_rules! impl_from_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl<const T: usize> From<$uint> for Bytes<T> {
+                fn from(value: $uint) -> Self {
+                    assert!(T >= size_of::<$uint>());
+                    let known = u128::from(<$uint>::MAX);
+                    Bytes::from_parts(value as u128, !known)
+                }
             }
-        }
-        ret
-    }
-}
 
-impl<const T: usize> From<Bytes<T>> for Option<u64> {
-    fn from(val: Bytes<T>) -> Self {
-        let mut ret = Some(0);
-        for i in 0..T.min(8) {
-            if val.data[i].is_none() {
-                ret = None;
-                break;
-            } else {
-                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u64) << (i * 8));
+            impl<const T: usize> From<Bytes<T>> for Option<$uint> {
+                fn from(val: Bytes<T>) -> Self {
+                    let bits = u128::from(<$uint>::MAX);
+                    if val.unknown & bits != 0 {
+                        None
+                    } else {
+                        Some((val.value & bits) as $uint)
+                    }
+                }
             }
-        }
-        ret
-    }
+        )*
+    };
 }
 
-impl<const T: usize> From<Bytes<T>> for Option<u128> {
-    fn from(val: Bytes<T>) -> Self {
-        let mut ret = Some(0);
-        for i in 0..T.min(16) {
-            if val.data[i].is_none() {
-                ret = None;
-                break;
-            } else {
-                ret = Some(ret.unwrap() | (val.data[i].unwrap() as u128) << (i * 8));
-            }
-        }
-        ret
-    }
-}
+impl_from_uint!(u8, u16, u32, u64, u128);
 
 impl<const T: usize, const R: usize> std::ops::Add<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn add(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = self;
-
-        let mut extended_rhs = Bytes::<T>::unknown();
-        for i in 0..T {
-            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
-        }
-
-        let mut cout: u8 = 0;
-        for i in 0..T {
-            result.data[i] = if let Some(lhs_byte) = result.data[i] {
-                extended_rhs.data[i].map(|rhs_byte| {
-                    let imm_add = lhs_byte as u16 + rhs_byte as u16 + cout as u16;
-                    cout = (imm_add >> 8) as u8;
-                    imm_add as u8
-                })
-            } else {
-                cout = 0;
-                None
-            }
-        }
-
-        result
+        let rhs = rhs.resize::<T>();
+        // A sum bit is known if the carry into it is the same with all unknowns at 0 and all at 1
+        let known_sum = self.value.wrapping_add(rhs.value);
+        let max_sum = known_sum.wrapping_add(self.unknown.wrapping_add(rhs.unknown));
+        let unknown = (max_sum ^ known_sum) | self.unknown | rhs.unknown;
+        Bytes::from_parts(known_sum, unknown)
     }
 }
 
@@ -360,12 +434,13 @@ impl<const T: usize, const R: usize> std::ops::Sub<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn sub(self, rhs: Bytes<R>) -> Self::Output {
-        let mut extended_rhs = Bytes::<T>::unknown();
-        for i in 0..T {
-            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
-        }
-
-        self + !extended_rhs + Bytes::<1>::from(1u8)
+        let rhs = rhs.resize::<T>();
+        // Same as add, with the largest lhs against the smallest rhs and the other way around
+        let known_diff = self.value.wrapping_sub(rhs.value);
+        let max_diff = known_diff.wrapping_add(self.unknown);
+        let min_diff = known_diff.wrapping_sub(rhs.unknown);
+        let unknown = (max_diff ^ min_diff) | self.unknown | rhs.unknown;
+        Bytes::from_parts(known_diff, unknown)
     }
 }
 
@@ -379,25 +454,12 @@ impl<const T: usize, const R: usize> std::ops::BitAnd<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn bitand(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = self;
-
-        let mut extended_rhs = Bytes::<T>::unknown();
-        for i in 0..T {
-            extended_rhs.data[i] = if i < R { rhs.data[i] } else { Some(0x00) }
-        }
-
-        let _: Vec<_> = (0..T)
-            .map(|i| {
-                result.data[i] = match (result.data[i], extended_rhs.data[i]) {
-                    (Some(0), _) => Some(0),
-                    (_, Some(0)) => Some(0),
-                    (Some(lhs), Some(rhs)) => Some(lhs & rhs),
-                    _ => None,
-                }
-            })
-            .collect();
-
-        result
+        let rhs = rhs.resize::<T>();
+        let known_zeros = (!self.value & !self.unknown) | (!rhs.value & !rhs.unknown);
+        Bytes::from_parts(
+            self.value & rhs.value,
+            (self.unknown | rhs.unknown) & !known_zeros,
+        )
     }
 }
 
@@ -411,21 +473,9 @@ impl<const T: usize, const R: usize> std::ops::BitOr<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn bitor(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = self;
-
-        let _: Vec<_> = (0..R)
-            .filter(|i| i < &T)
-            .map(|i| {
-                result.data[i] = match (result.data[i], rhs.data[i]) {
-                    (Some(0xFF), _) => Some(0xFF),
-                    (_, Some(0xFF)) => Some(0xFF),
-                    (Some(lhs), Some(rhs)) => Some(lhs | rhs),
-                    _ => None,
-                }
-            })
-            .collect();
-
-        result
+        let rhs = rhs.resize::<T>();
+        let known_ones = self.value | rhs.value;
+        Bytes::from_parts(known_ones, (self.unknown | rhs.unknown) & !known_ones)
     }
 }
 
@@ -439,21 +489,8 @@ impl<const T: usize, const R: usize> std::ops::BitXor<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn bitxor(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = self;
-
-        let _: Vec<_> = (0..R)
-            .filter(|i| i < &T)
-            .map(|i| {
-                result.data[i] = match (result.data[i], rhs.data[i]) {
-                    (Some(0xFF), Some(v)) => Some(!v),
-                    (Some(v), Some(0xFF)) => Some(!v),
-                    (Some(lhs), Some(rhs)) => Some(lhs ^ rhs),
-                    _ => None,
-                }
-            })
-            .collect();
-
-        result
+        let rhs = rhs.resize::<T>();
+        Bytes::from_parts(self.value ^ rhs.value, self.unknown | rhs.unknown)
     }
 }
 
@@ -467,13 +504,7 @@ impl<const T: usize> std::ops::Not for Bytes<T> {
     type Output = Bytes<T>;
 
     fn not(self) -> Self::Output {
-        let mut result = self;
-
-        let _: Vec<_> = (0..T)
-            .map(|i| self.data[i].map(|byte| result.data[i] = Some(!byte)))
-            .collect();
-
-        result
+        Bytes::from_parts(!self.value, self.unknown)
     }
 }
 
@@ -481,31 +512,13 @@ impl<const T: usize, const R: usize> std::ops::Shl<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
     fn shl(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = Self::zeros();
-
-        if self.has_unknown() || rhs.has_unknown() {
-            return Self::unknown();
-        }
-
-        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
-        let byte_shift_count = shift_count / 8;
-        let bit_shift_count = shift_count % 8;
-        if byte_shift_count > T {
-            return Self::zeros();
-        }
-
-        for i in byte_shift_count..T {
-            result.data[i] = self.data[i - byte_shift_count];
-        }
-
-        let mut shift_in = 0u8;
-        for i in 0..T {
-            let byte = ((result.data[i].unwrap() as u16) << bit_shift_count) | (shift_in as u16);
-            shift_in = (byte >> 8) as u8;
-            result.data[i] = Some(byte as u8);
+        match rhs.shift_amount() {
+            None => Self::unknown(),
+            Some(shift_count) if shift_count >= Self::BITS => Self::zeros(),
+            Some(shift_count) => {
+                Bytes::from_parts(self.value << shift_count, self.unknown << shift_count)
+            }
         }
-
-        result
     }
 }
 
@@ -518,31 +531,13 @@ impl<const T: usize, const R: usize> std::ops::ShlAssign<Bytes<R>> for Bytes<T>
 impl<const T: usize, const R: usize> std::ops::Shr<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
     fn shr(self, rhs: Bytes<R>) -> Self::Output {
-        let mut result = Self::zeros();
-
-        if self.has_unknown() || rhs.has_unknown() {
-            return Self::unknown();
-        }
-
-        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
-        let byte_shift_count = shift_count / 8;
-        let bit_shift_count = shift_count % 8;
-        if byte_shift_count > T {
-            return Self::zeros();
-        }
-
-        for i in 0..T - byte_shift_count {
-            result.data[i] = self.data[i + byte_shift_count];
-        }
-
-        let mut shift_in = 0u8;
-        for i in (0..T).rev() {
-            let byte = (result.data[i].unwrap() as u16) | ((shift_in as u16) << 8);
-            shift_in = (byte & (0xFF >> (8 - bit_shift_count))) as u8;
-            result.data[i] = Some((byte >> bit_shift_count) as u8);
+        match rhs.shift_amount() {
+            None => Self::unknown(),
+            Some(shift_count) if shift_count >= Self::BITS => Self::zeros(),
+            Some(shift_count) => {
+                Bytes::from_parts(self.value >> shift_count, self.unknown >> shift_count)
+            }
         }
-
-        result
     }
 }
 
@@ -560,49 +555,16 @@ pub trait Shra<Rhs = Self> {
 impl<const T: usize, const R: usize> Shra<Bytes<R>> for Bytes<T> {
     type Output = Bytes<T>;
 
+    /// An unknown sign bit shifts in unknowns
     fn shra(self, rhs: Bytes<R>) -> Bytes<T> {
-        let mut result = Self::zeros();
-
-        if self.has_unknown() || rhs.has_unknown() {
+        let Some(shift_count) = rhs.shift_amount() else {
             return Self::unknown();
-        }
-
-        let msb_fill = if (self.data[T - 1].unwrap() >> 7) & 0x1 == 0x1 {
-            Some(0xFF)
-        } else {
-            Some(0x00)
         };
-
-        let shift_count = Into::<Option<u128>>::into(rhs).unwrap() as usize;
-        let byte_shift_count = shift_count / 8;
-        let bit_shift_count = shift_count % 8;
-        if byte_shift_count > T {
-            return Self::zeros();
-        }
-
-        for i in 0..T - byte_shift_count {
-            result.data[i] = self.data[i + byte_shift_count];
-        }
-
-        let mut shift_in = 0u8;
-        for i in (0..T).rev() {
-            let byte = (result.data[i].unwrap() as u16) | ((shift_in as u16) << 8);
-            shift_in = (byte & (0xFF >> (8 - bit_shift_count))) as u8;
-            result.data[i] = Some((byte >> bit_shift_count) as u8);
-        }
-
-        for i in T - byte_shift_count..T {
-            result.data[i] = msb_fill;
-        }
-
-        if bit_shift_count != 0 {
-            result.data[T - byte_shift_count - 1] = Some(
-                msb_fill.unwrap() << (8 - bit_shift_count)
-                    | result.data[T - byte_shift_count - 1].unwrap(),
-            );
-        }
-
-        result
+        let shift_count = shift_count.min(Self::BITS - 1);
+        Bytes::from_parts(
+            (sign_extend(self.value, Self::BITS) >> shift_count) as u128,
+            (sign_extend(self.unknown, Self::BITS) >> shift_count) as u128,
+        )
     }
 }
 
@@ -619,6 +581,7 @@ impl<const T: usize, const R: usize> ShraAssign<Bytes<R>> for Bytes<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    type Word = Bytes<4>;
     use rand::random;
 
     #[test]
@@ -680,17 +643,159 @@ mod tests {
 
             assert_eq!(lhs + rhs, Bytes::<2>::unknown());
             assert_eq!(lhs - rhs, Bytes::<2>::unknown());
-            if !format!("{}", lhs).contains("00") {
-                assert_eq!(lhs & rhs, Bytes::<2>::unknown());
-            }
-            if !format!("{}", lhs).contains("ff") {
-                assert_eq!(lhs | rhs, Bytes::<2>::unknown());
-                assert_eq!(lhs ^ rhs, Bytes::<2>::unknown());
-            }
+            // Known zeros survive an and, known ones an or
+            assert_eq!(lhs & rhs, Bytes::<2>::from_parts(0, i as u128));
+            assert_eq!(lhs | rhs, Bytes::<2>::from_parts(i as u128, !i as u128));
+            assert_eq!(lhs ^ rhs, Bytes::<2>::unknown());
             assert_eq!(!rhs, Bytes::<2>::unknown());
         }
     }
 
+    /// Every value `bytes` could be once its unknown bits are filled in
+    fn concretize(bytes: Bytes<1>) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|value| (*value as u128) & !bytes.get_unknown_mask() == bytes.get_value())
+            .collect()
+    }
+
+    fn contains(bytes: Bytes<1>, value: u8) -> bool {
+        concretize(bytes).contains(&value)
+    }
+
+    fn random_partial() -> Bytes<1> {
+        // Few unknown bits most of the time, so the concrete sets stay small
+        Bytes::from_parts(
+            random::<u8>() as u128,
+            (random::<u8>() & random::<u8>()) as u128,
+        )
+    }
+
+    #[test]
+    fn test_partial_unknown_sound() {
+        for _ in 0..2000 {
+            let lhs = random_partial();
+            let rhs = random_partial();
+            let shift = Bytes::<1>::from(random::<u8>() % 10);
+            for a in concretize(lhs) {
+                assert!(contains(!lhs, !a));
+                assert!(contains(
+                    lhs << shift,
+                    a.checked_shl(shift.get_value() as u32).unwrap_or(0)
+                ));
+                assert!(contains(
+                    lhs >> shift,
+                    a.checked_shr(shift.get_value() as u32).unwrap_or(0)
+                ));
+                assert!(contains(
+                    lhs.shra(shift),
+                    ((a as i8) >> shift.get_value().min(7)) as u8
+                ));
+                for b in concretize(rhs) {
+                    assert!(contains(lhs + rhs, a.wrapping_add(b)));
+                    assert!(contains(lhs - rhs, a.wrapping_sub(b)));
+                    assert!(contains(lhs & rhs, a & b));
+                    assert!(contains(lhs | rhs, a | b));
+                    assert!(contains(lhs ^ rhs, a ^ b));
+                    if let Some(ordering) = lhs.byte_partial_cmp(rhs) {
+                        assert_eq!(ordering, a.cmp(&b));
+                    }
+                    if let Some(ordering) = lhs.signed_partial_cmp(rhs) {
+                        assert_eq!(ordering, (a as i8).cmp(&(b as i8)));
+                    }
+                    if let Some(eq) = lhs.byte_eq(rhs) {
+                        assert_eq!(eq, a == b);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_unknown_precise() {
+        // 0b0000_01X0 + 1, only bit 1 is unknown in the sum
+        let lhs = Bytes::<1>::from_parts(0b100, 0b10);
+        assert_eq!(
+            lhs + Bytes::<1>::from(1u8),
+            Bytes::<1>::from_parts(0b101, 0b10)
+        );
+        // 6 or 8, the carry out of the unknown bit only reaches as far as it can ripple
+        assert_eq!(
+            lhs + Bytes::<1>::from(0b10u8),
+            Bytes::<1>::from_parts(0b0000, 0b1110)
+        );
+        assert_eq!(format!("{}", lhs), "0X");
+        assert_eq!(lhs[0], None);
+        assert_eq!(lhs.get_bit(2), Some(true));
+        assert_eq!(lhs.get_bit(1), None);
+
+        // Unknown low bits do not matter when the high bits already decide
+        let lhs = Bytes::<4>::from_parts(0x1000, 0xFF);
+        assert_eq!(
+            lhs.byte_partial_cmp(Word::from(0x2000u32)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(lhs.byte_partial_cmp(Word::from(0x1010u32)), None);
+        assert_eq!(lhs.byte_eq(Word::from(0x2010u32)), Some(false));
+        assert_eq!(lhs.byte_cmp(Word::from(0x1010u32)), Ordering::Equal);
+
+        // An unknown sign bit is unknown all the way down on an arithmetic shift
+        let lhs = Bytes::<1>::from_parts(0x00, 0x80);
+        assert_eq!(
+            lhs.shra(Bytes::<1>::from(4u8)),
+            Bytes::<1>::from_parts(0, 0xF8)
+        );
+        assert_eq!(lhs.signed_partial_cmp(Bytes::<1>::from(0u8)), None);
+        assert_eq!(
+            Bytes::<1>::from_parts(0x80, 0x7F).signed_partial_cmp(Bytes::<1>::from(0u8)),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn test_get_set_byte() {
+        let mut word = Word::unknown();
+        word.set_byte(1, Bytes::<1>::from(0xABu8));
+        word.set_byte(2, Bytes::<1>::from_parts(0x0F, 0xF0));
+        assert_eq!(word[0], None);
+        assert_eq!(word[1], Some(0xAB));
+        assert_eq!(word[2], None);
+        assert_eq!(word.get_byte(2), Bytes::<1>::from_parts(0x0F, 0xF0));
+        assert_eq!(format!("{}", word), "xxxfabxx");
+    }
+
+    #[test]
+    fn test_high_z() {
+        let released = Bytes::<2>::high_z();
+        let low = Bytes::<2>::from_parts(0x005A, 0).with_high_z(0xFF00);
+        let high = Bytes::<2>::from_parts(0x3C00, 0).with_high_z(0x00FF);
+        assert_eq!(format!("{}", released), "zzzz");
+        assert_eq!(format!("{}", low.with_high_z(0x0030)), "zzZa");
+        assert!(released.has_unknown());
+        assert_eq!(low.get_byte(1), Bytes::<1>::high_z());
+
+        assert_eq!(low.tri_state(released), (low, false));
+        assert_eq!(low.tri_state(high), (Bytes::<2>::from(0x3C5Au16), false));
+        assert_eq!(low.tri_state(low), (low, false));
+        let (fight, contention) = low.tri_state(Bytes::<2>::from(0x0058u16));
+        assert!(contention);
+        assert_eq!(fight, Bytes::<2>::from_parts(0x0058, 0x0002));
+        assert!(!fight.has_high_z());
+
+        assert_eq!(
+            low.wired_or(released),
+            Bytes::<2>::from_parts(0x005A, 0).with_high_z(0xFF00)
+        );
+        assert_eq!(
+            low.wired_or(Bytes::<2>::from(0x0101u16)),
+            Bytes::<2>::from(0x015Bu16)
+        );
+        // Z reads as X once it goes through an operator
+        assert_eq!(
+            low + Bytes::<2>::from(0u16),
+            Bytes::<2>::from_parts(0x005A, 0xFF00)
+        );
+    }
+
     #[test]
     fn test_left_smaller() {
         for _i in 0..u16::MAX {
@@ -821,9 +926,24 @@ mod tests {
         assert_eq!(Bytes::<2>::from(0xFF00u16)[1], Some(0xFFu8));
     }
 
+    #[test]
+    fn test_data() {
+        let mut word = Word::from_parts(0x1234_0000, 0x0000_00F0);
+        assert_eq!(word.data(), [None, Some(0x00), Some(0x34), Some(0x12)]);
+        assert_eq!(
+            Word::from(word.data()),
+            Word::from_parts(0x1234_0000, 0x0000_00FF)
+        );
+
+        word.data_mut()[3] = Some(0xAB);
+        word.data_mut()[1] = None;
+        // byte 0 was not written, its low nibble stays known
+        assert_eq!(word, Word::from_parts(0xAB34_0000, 0x0000_FFF0));
+    }
+
     #[test]
     fn test_cmp() {
-        for i in 0..u16::MAX {
+        for _ in 0..u16::MAX {
             let a = random::<u32>();
             let b = random::<u32>();
             let lhs = Bytes::<4>::from(a);
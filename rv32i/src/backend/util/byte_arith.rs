@@ -0,0 +1,430 @@
+#![allow(clippy::suspicious_arithmetic_impl)]
+#![allow(dead_code)]
+
+use crate::backend::util::byte::{sign_extend, Bytes};
+
+/// Value and unknown mask of a number some bits of which are unknown, same as what `Bytes` keeps
+type Tnum = (u128, u128);
+
+fn tnum_add((lhs_value, lhs_unknown): Tnum, (rhs_value, rhs_unknown): Tnum) -> Tnum {
+    let value = lhs_value.wrapping_add(rhs_value);
+    let carries = value.wrapping_add(lhs_unknown.wrapping_add(rhs_unknown)) ^ value;
+    let unknown = carries | lhs_unknown | rhs_unknown;
+    (value & !unknown, unknown)
+}
+
+/// Shift and add, every partial product of an unknown multiplier bit is unknown where it is not known to be 0.
+/// Wraps at 128 bits.
+fn tnum_mul(
+    (mut lhs_value, mut lhs_unknown): Tnum,
+    (mut rhs_value, mut rhs_unknown): Tnum,
+) -> Tnum {
+    let known_product = lhs_value.wrapping_mul(rhs_value);
+    let mut unknown_partials = (0, 0);
+    while lhs_value != 0 || lhs_unknown != 0 {
+        if lhs_value & 1 == 1 {
+            unknown_partials = tnum_add(unknown_partials, (0, rhs_unknown));
+        } else if lhs_unknown & 1 == 1 {
+            unknown_partials = tnum_add(unknown_partials, (0, rhs_value | rhs_unknown));
+        }
+        lhs_value >>= 1;
+        lhs_unknown >>= 1;
+        rhs_value <<= 1;
+        rhs_unknown <<= 1;
+    }
+    tnum_add((known_product, 0), unknown_partials)
+}
+
+fn not3(a: Option<bool>) -> Option<bool> {
+    a.map(|a| !a)
+}
+
+fn and3(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn or3(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    not3(and3(not3(a), not3(b)))
+}
+
+/// `lhs + rhs + carry` does not fit in `bits` bits, the operands have to fit
+fn carries(lhs: u128, rhs: u128, carry: u128, bits: usize) -> bool {
+    let (sum, overflow) = lhs.overflowing_add(rhs);
+    let (sum, overflow_carry) = sum.overflowing_add(carry);
+    if bits == 128 {
+        overflow || overflow_carry
+    } else {
+        sum >> bits != 0
+    }
+}
+
+/// `lhs < rhs + borrow`, without wrapping
+fn borrows(lhs: u128, rhs: u128, borrow: u128) -> bool {
+    rhs.checked_add(borrow)
+        .is_none_or(|subtrahend| lhs < subtrahend)
+}
+
+/// RV32M and friends, and the flags an ALU would produce.
+///
+/// Like the operators, these are pessimistic about unknown bits:
+/// a result bit, or a flag, is only known if it is the same for every value the unknown bits could take.
+/// Dividing by something with unknown bits gives an unknown result, unless the divisor is known to be zero.
+impl<const T: usize> Bytes<T> {
+    fn tnum(&self) -> Tnum {
+        (self.get_value(), self.get_unknown_mask())
+    }
+
+    fn from_tnum((value, unknown): Tnum) -> Self {
+        Bytes::from_parts(value, unknown)
+    }
+
+    fn sign(&self) -> Option<bool> {
+        self.get_bit(8 * T - 1)
+    }
+
+    /// Sign extended to 128 bits, an unknown sign bit extends as unknown
+    fn sext_tnum(&self) -> Tnum {
+        (
+            sign_extend(self.get_value(), 8 * T) as u128,
+            sign_extend(self.get_unknown_mask(), 8 * T) as u128,
+        )
+    }
+
+    fn widening_mul_tnum(lhs: Tnum, rhs: Tnum) -> (Self, Self) {
+        assert!(
+            T <= 8,
+            "the product of two Bytes<{}> does not fit 128 bits",
+            T
+        );
+        let product = tnum_mul(lhs, rhs);
+        let high = (product.0 >> (8 * T), product.1 >> (8 * T));
+        (Self::from_tnum(product), Self::from_tnum(high))
+    }
+
+    /// Full unsigned product as (low, high)
+    pub fn widening_mul(self, rhs: Self) -> (Self, Self) {
+        Self::widening_mul_tnum(self.tnum(), rhs.tnum())
+    }
+
+    /// Full product of two signed values as (low, high)
+    pub fn widening_mul_signed(self, rhs: Self) -> (Self, Self) {
+        Self::widening_mul_tnum(self.sext_tnum(), rhs.sext_tnum())
+    }
+
+    /// Full product of signed `self` and unsigned `rhs` as (low, high)
+    pub fn widening_mul_signed_unsigned(self, rhs: Self) -> (Self, Self) {
+        Self::widening_mul_tnum(self.sext_tnum(), rhs.tnum())
+    }
+
+    /// Unsigned quotient, all ones when dividing by zero
+    pub fn div_unsigned(self, rhs: Self) -> Self {
+        if rhs.is_known_zero() {
+            return Self::from_parts(u128::MAX, 0);
+        }
+        match (Option::<u128>::from(self), Option::<u128>::from(rhs)) {
+            (Some(lhs), Some(rhs)) => Self::from_parts(lhs / rhs, 0),
+            _ => Self::unknown(),
+        }
+    }
+
+    /// Unsigned remainder, the dividend when dividing by zero
+    pub fn rem_unsigned(self, rhs: Self) -> Self {
+        if rhs.is_known_zero() {
+            return Self::from_tnum(self.tnum());
+        }
+        match (Option::<u128>::from(self), Option::<u128>::from(rhs)) {
+            (Some(lhs), Some(rhs)) => Self::from_parts(lhs % rhs, 0),
+            _ => Self::unknown(),
+        }
+    }
+
+    /// Signed quotient rounded towards zero, -1 when dividing by zero and the dividend on overflow
+    pub fn div_signed(self, rhs: Self) -> Self {
+        if rhs.is_known_zero() {
+            return Self::from_parts(u128::MAX, 0);
+        }
+        match (self.known_signed(), rhs.known_signed()) {
+            (Some(lhs), Some(rhs)) => Self::from_parts(lhs.wrapping_div(rhs) as u128, 0),
+            _ => Self::unknown(),
+        }
+    }
+
+    /// Signed remainder with the sign of the dividend, the dividend when dividing by zero and 0 on overflow
+    pub fn rem_signed(self, rhs: Self) -> Self {
+        if rhs.is_known_zero() {
+            return Self::from_tnum(self.tnum());
+        }
+        match (self.known_signed(), rhs.known_signed()) {
+            (Some(lhs), Some(rhs)) => Self::from_parts(lhs.wrapping_rem(rhs) as u128, 0),
+            _ => Self::unknown(),
+        }
+    }
+
+    /// Narrower than 128 bits, `MIN / -1` does not overflow in i128 and comes out as `MIN` once truncated again
+    fn known_signed(&self) -> Option<i128> {
+        (!self.has_unknown()).then(|| sign_extend(self.get_value(), 8 * T))
+    }
+
+    fn is_known_zero(&self) -> bool {
+        !self.has_unknown() && self.get_value() == 0
+    }
+
+    /// `self + rhs + carry` and the carry out
+    pub fn add_with_carry(self, rhs: Self, carry: Option<bool>) -> (Self, Option<bool>) {
+        let carry = Self::from_parts(carry.unwrap_or(false) as u128, carry.is_none() as u128);
+        let sum = Self::from_tnum(tnum_add(tnum_add(self.tnum(), rhs.tnum()), carry.tnum()));
+        let (lhs_min, lhs_max) = min_max(self);
+        let (rhs_min, rhs_max) = min_max(rhs);
+        let (carry_min, carry_max) = min_max(carry);
+        let carry_out = match (
+            carries(lhs_min, rhs_min, carry_min, 8 * T),
+            carries(lhs_max, rhs_max, carry_max, 8 * T),
+        ) {
+            (true, true) => Some(true),
+            (false, false) => Some(false),
+            _ => None,
+        };
+        (sum, carry_out)
+    }
+
+    /// `self - rhs - borrow` and the borrow out, that is whether `self < rhs + borrow`
+    pub fn sub_with_borrow(self, rhs: Self, borrow: Option<bool>) -> (Self, Option<bool>) {
+        let borrow = Self::from_parts(borrow.unwrap_or(false) as u128, borrow.is_none() as u128);
+        let difference = self - rhs - borrow;
+        let (lhs_min, lhs_max) = min_max(self);
+        let (rhs_min, rhs_max) = min_max(rhs);
+        let (borrow_min, borrow_max) = min_max(borrow);
+        let borrow_out = match (
+            borrows(lhs_max, rhs_min, borrow_min),
+            borrows(lhs_min, rhs_max, borrow_max),
+        ) {
+            (true, true) => Some(true),
+            (false, false) => Some(false),
+            _ => None,
+        };
+        (difference, borrow_out)
+    }
+
+    /// Whether `self + rhs` overflows as a signed addition
+    pub fn add_overflows(self, rhs: Self) -> Option<bool> {
+        let sum = (self + rhs).sign();
+        let (lhs, rhs) = (self.sign(), rhs.sign());
+        or3(
+            and3(and3(lhs, rhs), not3(sum)),
+            and3(and3(not3(lhs), not3(rhs)), sum),
+        )
+    }
+
+    /// Whether `self - rhs` overflows as a signed subtraction
+    pub fn sub_overflows(self, rhs: Self) -> Option<bool> {
+        let difference = (self - rhs).sign();
+        let (lhs, rhs) = (self.sign(), rhs.sign());
+        or3(
+            and3(and3(lhs, not3(rhs)), not3(difference)),
+            and3(and3(not3(lhs), rhs), difference),
+        )
+    }
+}
+
+/// Smallest and largest unsigned value the unknown bits allow
+fn min_max<const T: usize>(bytes: Bytes<T>) -> (u128, u128) {
+    (
+        bytes.get_value(),
+        bytes.get_value() | bytes.get_unknown_mask(),
+    )
+}
+
+/// Low half of the product
+impl<const T: usize, const R: usize> std::ops::Mul<Bytes<R>> for Bytes<T> {
+    type Output = Bytes<T>;
+
+    fn mul(self, rhs: Bytes<R>) -> Self::Output {
+        let rhs = Self::from_parts(rhs.get_value(), rhs.get_unknown_mask());
+        Self::from_tnum(tnum_mul(self.tnum(), rhs.tnum()))
+    }
+}
+
+impl<const T: usize, const R: usize> std::ops::MulAssign<Bytes<R>> for Bytes<T> {
+    fn mul_assign(&mut self, rhs: Bytes<R>) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::util::types::{Byte, Word};
+    use rand::random;
+
+    /// Every value `bytes` could be
+    fn concretize(bytes: Byte) -> Vec<Byte> {
+        (0..=u8::MAX)
+            .filter(|value| *value as u128 & !bytes.get_unknown_mask() == bytes.get_value())
+            .map(Byte::from)
+            .collect()
+    }
+
+    fn random_partial() -> Byte {
+        Byte::from_parts(
+            random::<u8>() as u128,
+            (random::<u8>() & random::<u8>()) as u128,
+        )
+    }
+
+    /// The known bits of `abstracted` are the same in `concrete`
+    fn covers(abstracted: Byte, concrete: Byte) -> bool {
+        (abstracted.get_value() ^ concrete.get_value()) & !abstracted.get_unknown_mask() == 0
+    }
+
+    fn flag_covers(abstracted: Option<bool>, concrete: Option<bool>) -> bool {
+        abstracted.is_none() || abstracted == concrete
+    }
+
+    #[test]
+    fn test_known() {
+        for _ in 0..u16::MAX {
+            let (a, b) = (random::<u32>(), random::<u32>() >> random::<u8>().min(31));
+            let (lhs, rhs) = (Word::from(a), Word::from(b));
+
+            assert_eq!(lhs * rhs, Word::from(a.wrapping_mul(b)));
+            let product = a as u64 * b as u64;
+            assert_eq!(
+                lhs.widening_mul(rhs),
+                (
+                    Word::from(product as u32),
+                    Word::from((product >> 32) as u32)
+                )
+            );
+            let product = a as i32 as i64 * b as i32 as i64;
+            assert_eq!(
+                lhs.widening_mul_signed(rhs),
+                (
+                    Word::from(product as u32),
+                    Word::from((product >> 32) as u32)
+                )
+            );
+            let product = (a as i32 as i128 * b as i128) as u64;
+            assert_eq!(
+                lhs.widening_mul_signed_unsigned(rhs),
+                (
+                    Word::from(product as u32),
+                    Word::from((product >> 32) as u32)
+                )
+            );
+
+            if let (Some(quotient), Some(remainder)) = (a.checked_div(b), a.checked_rem(b)) {
+                assert_eq!(lhs.div_unsigned(rhs), Word::from(quotient));
+                assert_eq!(lhs.rem_unsigned(rhs), Word::from(remainder));
+                assert_eq!(
+                    lhs.div_signed(rhs),
+                    Word::from((a as i32).wrapping_div(b as i32) as u32)
+                );
+                assert_eq!(
+                    lhs.rem_signed(rhs),
+                    Word::from((a as i32).wrapping_rem(b as i32) as u32)
+                );
+            }
+
+            let carry = random::<bool>();
+            let (sum, carry_out) = a.carrying_add(b, carry);
+            assert_eq!(
+                lhs.add_with_carry(rhs, Some(carry)),
+                (Word::from(sum), Some(carry_out))
+            );
+            let (difference, borrow_out) = a.borrowing_sub(b, carry);
+            assert_eq!(
+                lhs.sub_with_borrow(rhs, Some(carry)),
+                (Word::from(difference), Some(borrow_out))
+            );
+            assert_eq!(
+                lhs.add_overflows(rhs),
+                Some((a as i32).checked_add(b as i32).is_none())
+            );
+            assert_eq!(
+                lhs.sub_overflows(rhs),
+                Some((a as i32).checked_sub(b as i32).is_none())
+            );
+        }
+    }
+
+    #[test]
+    fn test_riscv_corner_cases() {
+        let zero = Word::zeros();
+        let minus_one = Word::from(u32::MAX);
+        let min = Word::from(i32::MIN as u32);
+        let dividend = Word::from(1234u32);
+        assert_eq!(dividend.div_unsigned(zero), minus_one);
+        assert_eq!(dividend.rem_unsigned(zero), dividend);
+        assert_eq!(dividend.div_signed(zero), minus_one);
+        assert_eq!(dividend.rem_signed(zero), dividend);
+        assert_eq!(min.div_signed(minus_one), min);
+        assert_eq!(min.rem_signed(minus_one), zero);
+
+        // Dividing by zero does not care about the dividend
+        let unknown = Word::from_parts(0x1200, 0xFF);
+        assert_eq!(unknown.div_unsigned(zero), minus_one);
+        assert_eq!(unknown.rem_signed(zero), unknown);
+        assert_eq!(
+            dividend.div_unsigned(Word::from_parts(0, 1)),
+            Word::unknown()
+        );
+    }
+
+    #[test]
+    fn test_partial_unknown_sound() {
+        for _ in 0..u8::MAX {
+            let (lhs, rhs) = (random_partial(), random_partial());
+            let carry = [None, Some(false), Some(true)][random::<usize>() % 3];
+            for a in concretize(lhs) {
+                for b in concretize(rhs) {
+                    assert!(covers(lhs * rhs, a * b));
+                    let (low, high) = lhs.widening_mul_signed(rhs);
+                    let (a_low, a_high) = a.widening_mul_signed(b);
+                    assert!(covers(low, a_low) && covers(high, a_high));
+                    let (low, high) = lhs.widening_mul_signed_unsigned(rhs);
+                    let (a_low, a_high) = a.widening_mul_signed_unsigned(b);
+                    assert!(covers(low, a_low) && covers(high, a_high));
+                    assert!(covers(lhs.div_signed(rhs), a.div_signed(b)));
+                    assert!(covers(lhs.rem_unsigned(rhs), a.rem_unsigned(b)));
+
+                    for c in carry.map_or(vec![false, true], |carry| vec![carry]) {
+                        let (sum, carry_out) = lhs.add_with_carry(rhs, carry);
+                        let (a_sum, a_carry_out) = a.add_with_carry(b, Some(c));
+                        assert!(covers(sum, a_sum) && flag_covers(carry_out, a_carry_out));
+                        let (difference, borrow_out) = lhs.sub_with_borrow(rhs, carry);
+                        let (a_difference, a_borrow_out) = a.sub_with_borrow(b, Some(c));
+                        assert!(covers(difference, a_difference));
+                        assert!(flag_covers(borrow_out, a_borrow_out));
+                    }
+                    assert!(flag_covers(lhs.add_overflows(rhs), a.add_overflows(b)));
+                    assert!(flag_covers(lhs.sub_overflows(rhs), a.sub_overflows(b)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_unknown_precise() {
+        // Only the low bit is unknown, 0b1X * 0b10 is 0b1X0
+        let lhs = Byte::from_parts(0b10, 0b01);
+        assert_eq!(lhs * Byte::from(0b10u8), Byte::from_parts(0b100, 0b010));
+        assert_eq!(lhs * Byte::zeros(), Byte::zeros());
+        // The carry out does not depend on the unknown low bits
+        let (sum, carry) = Byte::from_parts(0xF0, 0x0F).add_with_carry(Byte::from(0x10u8), None);
+        assert_eq!(carry, Some(true));
+        assert_eq!(sum, Byte::from_parts(0x00, 0x1F));
+        let (_, borrow) = Byte::from_parts(0x00, 0x0F).sub_with_borrow(Byte::from(0x10u8), None);
+        assert_eq!(borrow, Some(true));
+        assert_eq!(
+            Byte::from_parts(0x10, 0x0F).add_overflows(Byte::from(0x10u8)),
+            Some(false)
+        );
+        assert_eq!(
+            Byte::from_parts(0x00, 0x80).add_overflows(Byte::from(0x7Fu8)),
+            None
+        );
+    }
+}
@@ -0,0 +1,343 @@
+#![allow(dead_code)]
+
+use crate::backend::util::byte::{sign_extend, Bytes};
+use serde::de::{Error as DeError, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt::{Binary, Display, Formatter, LowerHex};
+use std::str::FromStr;
+
+/// What `Bytes::format` prints the value as
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Radix {
+    /// `0b` and one digit per bit, `x`/`z` for unknown/high impedance bits
+    Binary,
+    /// `0x` and one digit per nibble, same digits as `Display`
+    Hex,
+    /// Decimal, `x` as soon as one bit is unknown, `z` if none of the bits are driven
+    Unsigned,
+    /// Two's complement decimal, unknowns as for `Unsigned`
+    Signed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ParseBytesError {
+    Empty,
+    InvalidDigit(char),
+    /// The value does not fit, or a negative value would not fit as a signed one
+    Overflow,
+}
+
+impl Display for ParseBytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseBytesError::Empty => write!(f, "no digits to parse"),
+            ParseBytesError::InvalidDigit(digit) => write!(f, "invalid digit '{}'", digit),
+            ParseBytesError::Overflow => write!(f, "value does not fit"),
+        }
+    }
+}
+
+impl Error for ParseBytesError {}
+
+impl<const T: usize> Bytes<T> {
+    fn width_mask() -> u128 {
+        u128::MAX >> (128 - 8 * T)
+    }
+
+    /// Prints the value with a radix prefix so that `FromStr` reads it back,
+    /// `group` puts an `_` between every `group` digits, counting from the right
+    pub fn format(&self, radix: Radix, group: Option<usize>) -> String {
+        let (sign, prefix, digits) = match radix {
+            Radix::Binary => ("", "0b", format!("{:b}", self)),
+            Radix::Hex => ("", "0x", self.to_string()),
+            Radix::Unsigned | Radix::Signed if self.has_unknown() => {
+                return if self.get_high_z_mask() == Self::width_mask() {
+                    "z".to_string()
+                } else {
+                    "x".to_string()
+                };
+            }
+            Radix::Unsigned => ("", "", self.get_value().to_string()),
+            Radix::Signed => {
+                let value = sign_extend(self.get_value(), 8 * T);
+                let sign = if value < 0 { "-" } else { "" };
+                (sign, "", value.unsigned_abs().to_string())
+            }
+        };
+        let digits = match group {
+            Some(group) if group > 0 => {
+                let digits: Vec<char> = digits.chars().collect();
+                let first = match digits.len() % group {
+                    0 => group,
+                    first => first,
+                };
+                let mut grouped: String = digits[..first].iter().collect();
+                for chunk in digits[first..].chunks(group) {
+                    grouped.push('_');
+                    grouped.extend(chunk);
+                }
+                grouped
+            }
+            _ => digits,
+        };
+        format!("{}{}{}", sign, prefix, digits)
+    }
+
+    /// Hex unless that would lose some of the unknown or high impedance bits
+    fn lossless_radix(&self) -> Radix {
+        let whole_nibbles =
+            |mask: u128| (0..2 * T).all(|nibble| matches!((mask >> (4 * nibble)) & 0xF, 0 | 0xF));
+        if whole_nibbles(self.get_unknown_mask()) && whole_nibbles(self.get_high_z_mask()) {
+            Radix::Hex
+        } else {
+            Radix::Binary
+        }
+    }
+}
+
+impl<const T: usize> Binary for Bytes<T> {
+    /// One digit per bit, `x` for an unknown bit and `z` for a high impedance one
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = (0..8 * T)
+            .rev()
+            .map(|bit| match self.get_bit(bit) {
+                Some(true) => '1',
+                Some(false) => '0',
+                None if (self.get_high_z_mask() >> bit) & 1 == 1 => 'z',
+                None => 'x',
+            })
+            .collect::<String>();
+        if f.alternate() {
+            write!(f, "0b")?;
+        }
+        write!(f, "{}", value)
+    }
+}
+
+impl<const T: usize> LowerHex for Bytes<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        Display::fmt(self, f)
+    }
+}
+
+impl<const T: usize> FromStr for Bytes<T> {
+    type Err = ParseBytesError;
+
+    /// Takes `0x` hex and `0b` binary literals, where `x`/`z` is an unknown/high impedance digit,
+    /// and decimal literals, which may be negative.
+    /// `_` can go anywhere between the digits. Missing high digits are zeros, as for integer literals.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let literal: String = s.trim().chars().filter(|c| *c != '_').collect();
+        let (digit_bits, digits) = if let Some(digits) = literal
+            .strip_prefix("0x")
+            .or_else(|| literal.strip_prefix("0X"))
+        {
+            (4, digits)
+        } else if let Some(digits) = literal
+            .strip_prefix("0b")
+            .or_else(|| literal.strip_prefix("0B"))
+        {
+            (1, digits)
+        } else {
+            return parse_decimal(&literal);
+        };
+        if digits.is_empty() {
+            return Err(ParseBytesError::Empty);
+        }
+
+        let digit_mask = (1u128 << digit_bits) - 1;
+        let (mut value, mut unknown, mut high_z) = (0u128, 0u128, 0u128);
+        for digit in digits.chars() {
+            if (value | unknown) >> (128 - digit_bits) != 0 {
+                return Err(ParseBytesError::Overflow);
+            }
+            let (digit_value, digit_unknown, digit_high_z) = match digit {
+                'x' | 'X' => (0, digit_mask, 0),
+                'z' | 'Z' => (0, digit_mask, digit_mask),
+                _ => match digit.to_digit(1 << digit_bits) {
+                    Some(digit_value) => (digit_value as u128, 0, 0),
+                    None => return Err(ParseBytesError::InvalidDigit(digit)),
+                },
+            };
+            value = (value << digit_bits) | digit_value;
+            unknown = (unknown << digit_bits) | digit_unknown;
+            high_z = (high_z << digit_bits) | digit_high_z;
+        }
+        if (value | unknown) & !Self::width_mask() != 0 {
+            return Err(ParseBytesError::Overflow);
+        }
+        Ok(Bytes::from_parts(value, unknown).with_high_z(high_z))
+    }
+}
+
+fn parse_decimal<const T: usize>(literal: &str) -> Result<Bytes<T>, ParseBytesError> {
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal),
+    };
+    if digits.is_empty() {
+        return Err(ParseBytesError::Empty);
+    }
+    if let Some(digit) = digits.chars().find(|digit| !digit.is_ascii_digit()) {
+        return Err(ParseBytesError::InvalidDigit(digit));
+    }
+
+    let bits = 8 * T;
+    let value = if negative {
+        let value = literal
+            .parse::<i128>()
+            .map_err(|_| ParseBytesError::Overflow)?;
+        if bits < 128 && value < -(1i128 << (bits - 1)) {
+            return Err(ParseBytesError::Overflow);
+        }
+        value as u128
+    } else {
+        let value = digits
+            .parse::<u128>()
+            .map_err(|_| ParseBytesError::Overflow)?;
+        if value & !Bytes::<T>::width_mask() != 0 {
+            return Err(ParseBytesError::Overflow);
+        }
+        value
+    };
+    Ok(Bytes::from_parts(value, 0))
+}
+
+/// Serialized as the literal `FromStr` takes, in hex when that does not lose any bits
+impl<const T: usize> Serialize for Bytes<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format(self.lossless_radix(), None))
+    }
+}
+
+struct BytesVisitor<const T: usize>;
+
+impl<const T: usize> Visitor<'_> for BytesVisitor<T> {
+    type Value = Bytes<T>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "an integer or a literal like \"0x4000_00xx\" that fits {} bytes",
+            T
+        )
+    }
+
+    fn visit_str<E: DeError>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: DeError>(self, value: u64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_i64<E: DeError>(self, value: i64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+}
+
+/// Takes the literals `FromStr` takes, as well as plain integers
+impl<'de, const T: usize> Deserialize<'de> for Bytes<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BytesVisitor::<T>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::util::types::{Byte, Word};
+    use rand::random;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "0x4000_00XX".parse::<Word>(),
+            Ok(Word::from_parts(0x4000_0000, 0xFF))
+        );
+        assert_eq!(
+            "0b1010_xxxx".parse::<Byte>(),
+            Ok(Byte::from_parts(0b1010_0000, 0xF))
+        );
+        assert_eq!("-12".parse::<Word>(), Ok(Word::from(-12i32 as u32)));
+        assert_eq!(
+            "0x1z".parse::<Byte>(),
+            Ok(Byte::from_parts(0x10, 0).with_high_z(0xF))
+        );
+        assert_eq!("0x00ff".parse::<Byte>(), Ok(Byte::from(0xFFu8)));
+        assert_eq!("255".parse::<Byte>(), Ok(Byte::from(0xFFu8)));
+        assert_eq!("-128".parse::<Byte>(), Ok(Byte::from(0x80u8)));
+
+        assert_eq!("".parse::<Byte>(), Err(ParseBytesError::Empty));
+        assert_eq!("0x".parse::<Byte>(), Err(ParseBytesError::Empty));
+        assert_eq!(
+            "0x1g".parse::<Byte>(),
+            Err(ParseBytesError::InvalidDigit('g'))
+        );
+        assert_eq!(
+            "0b12".parse::<Byte>(),
+            Err(ParseBytesError::InvalidDigit('2'))
+        );
+        assert_eq!(
+            "1x".parse::<Byte>(),
+            Err(ParseBytesError::InvalidDigit('x'))
+        );
+        assert_eq!("0x1ff".parse::<Byte>(), Err(ParseBytesError::Overflow));
+        assert_eq!("0x1xx".parse::<Byte>(), Err(ParseBytesError::Overflow));
+        assert_eq!("256".parse::<Byte>(), Err(ParseBytesError::Overflow));
+        assert_eq!("-129".parse::<Byte>(), Err(ParseBytesError::Overflow));
+    }
+
+    #[test]
+    fn test_format() {
+        let word = Word::from_parts(0x4000_0000, 0xFF);
+        assert_eq!(word.format(Radix::Hex, None), "0x400000xx");
+        assert_eq!(word.format(Radix::Hex, Some(4)), "0x4000_00xx");
+        assert_eq!(word.format(Radix::Unsigned, None), "x");
+        assert_eq!(Word::high_z().format(Radix::Signed, None), "z");
+        assert_eq!(format!("{:#x}", word), "0x400000xx");
+
+        let byte = Byte::from_parts(0b1010_0000, 0b11).with_high_z(0b10);
+        assert_eq!(format!("{:b}", byte), "101000zx");
+        assert_eq!(byte.format(Radix::Binary, Some(4)), "0b1010_00zx");
+
+        let word = Word::from(-1234567i32 as u32);
+        assert_eq!(word.format(Radix::Signed, Some(3)), "-1_234_567");
+        assert_eq!(word.format(Radix::Unsigned, Some(3)), "4_293_732_729");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for _ in 0..u8::MAX {
+            let word = Word::from_parts(random::<u32>() as u128, random::<u32>() as u128)
+                .with_high_z(random::<u32>() as u128 & random::<u32>() as u128);
+            for radix in [Radix::Binary, Radix::Hex, Radix::Unsigned, Radix::Signed] {
+                let formatted = word.format(radix, Some(3));
+                match radix {
+                    Radix::Binary => assert_eq!(formatted.parse(), Ok(word)),
+                    _ if !word.has_unknown() => assert_eq!(formatted.parse(), Ok(word)),
+                    _ => {}
+                }
+            }
+            let json = serde_json::to_string(&word).unwrap();
+            assert_eq!(serde_json::from_str::<Word>(&json).unwrap(), word);
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let word = Word::from_parts(0x4000_0000, 0xFF);
+        assert_eq!(serde_json::to_string(&word).unwrap(), "\"0x400000xx\"");
+        let byte = Byte::from_parts(0, 0b11);
+        assert_eq!(serde_json::to_string(&byte).unwrap(), "\"0b000000xx\"");
+
+        let words: Vec<Word> = serde_json::from_str("[\"0x4000_00xx\", 16, -1]").unwrap();
+        assert_eq!(words, vec![word, Word::from(16u32), Word::from(u32::MAX)]);
+        assert!(serde_json::from_str::<Byte>("256").is_err());
+        assert!(serde_json::from_str::<Byte>("\"0xfg\"").is_err());
+    }
+}
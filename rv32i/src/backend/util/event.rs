@@ -1,18 +1,21 @@
+use crate::backend::util::bits::Bits;
 use crate::backend::util::types::{Byte, Word};
+use rsim_core::bus::BusValue;
 use rsim_core::event::Event;
-use rsim_core::types::{Cycle, EventId};
+use rsim_core::types::{EventId, SimTime};
 use std::any::Any;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 #[derive(Clone, Debug)]
 pub struct WordEvent {
-    scheduled_time: Cycle,
+    scheduled_time: SimTime,
     event_id: EventId,
     data: Word,
 }
 
 impl WordEvent {
-    pub fn new(scheduled_time: Cycle, data: Word, event_id: EventId) -> Self {
+    pub fn new(scheduled_time: SimTime, data: Word, event_id: EventId) -> Self {
         Self {
             scheduled_time,
             event_id,
@@ -26,28 +29,32 @@ impl Event for WordEvent {
         self.event_id
     }
 
-    fn get_scheduled_time(&self) -> Cycle {
+    fn get_scheduled_time(&self) -> SimTime {
         self.scheduled_time
     }
 
-    fn set_scheduled_time(&mut self, scheduled_time: Cycle) {
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
         self.scheduled_time = scheduled_time;
     }
 
     fn get_inner(&self) -> Box<dyn Any> {
         Box::new(self.data)
     }
+
+    fn hash_payload(&self, mut state: &mut dyn Hasher) {
+        self.data.hash(&mut state);
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ByteEvent {
-    scheduled_time: Cycle,
+    scheduled_time: SimTime,
     event_id: EventId,
     data: Byte,
 }
 
 impl ByteEvent {
-    pub fn new(scheduled_time: Cycle, data: Byte, event_id: EventId) -> Self {
+    pub fn new(scheduled_time: SimTime, data: Byte, event_id: EventId) -> Self {
         Self {
             scheduled_time,
             event_id,
@@ -61,15 +68,85 @@ impl Event for ByteEvent {
         self.event_id
     }
 
-    fn get_scheduled_time(&self) -> Cycle {
+    fn get_scheduled_time(&self) -> SimTime {
+        self.scheduled_time
+    }
+
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
+        self.scheduled_time = scheduled_time;
+    }
+
+    fn get_inner(&self) -> Box<dyn Any> {
+        Box::new(self.data)
+    }
+
+    fn hash_payload(&self, mut state: &mut dyn Hasher) {
+        self.data.hash(&mut state);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BitsEvent<const N: usize> {
+    scheduled_time: SimTime,
+    event_id: EventId,
+    data: Bits<N>,
+}
+
+impl<const N: usize> BitsEvent<N> {
+    pub fn new(scheduled_time: SimTime, data: Bits<N>, event_id: EventId) -> Self {
+        Self {
+            scheduled_time,
+            event_id,
+            data,
+        }
+    }
+}
+
+impl<const N: usize> Event for BitsEvent<N> {
+    fn get_event_id(&self) -> EventId {
+        self.event_id
+    }
+
+    fn get_scheduled_time(&self) -> SimTime {
         self.scheduled_time
     }
 
-    fn set_scheduled_time(&mut self, scheduled_time: Cycle) {
+    fn set_scheduled_time(&mut self, scheduled_time: SimTime) {
         self.scheduled_time = scheduled_time;
     }
 
     fn get_inner(&self) -> Box<dyn Any> {
         Box::new(self.data)
     }
+
+    fn hash_payload(&self, mut state: &mut dyn Hasher) {
+        self.data.hash(&mut state);
+    }
+}
+
+/// Words, bytes and bits can go on a bus, the readers get the event they would get from a plain port
+macro_rules! impl_bus_value {
+    ($value:ty, $event:ty $(, $n:ident)?) => {
+        impl$(<const $n: usize>)? BusValue for $value {
+            fn high_z() -> Self {
+                <$value>::high_z()
+            }
+
+            fn wired_or(self, other: Self) -> Self {
+                <$value>::wired_or(self, other)
+            }
+
+            fn tri_state(self, other: Self) -> (Self, bool) {
+                <$value>::tri_state(self, other)
+            }
+
+            fn into_event(self, scheduled_time: SimTime, event_id: EventId) -> Box<dyn Event> {
+                Box::new(<$event>::new(scheduled_time, self, event_id))
+            }
+        }
+    };
 }
+
+impl_bus_value!(Word, WordEvent);
+impl_bus_value!(Byte, ByteEvent);
+impl_bus_value!(Bits<N>, BitsEvent<N>, N);
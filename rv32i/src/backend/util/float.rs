@@ -0,0 +1,511 @@
+//! RV32F arithmetic on raw single precision bit patterns, with every rounding mode and the
+//! exception flags. Each function returns the result and the `fflags` it raises.
+//!
+//! The host only rounds to nearest, so results are computed in f64, where they are either exact
+//! or come with the sign of what is left over, and then rounded to f32 here.
+use crate::backend::util::types::{fflags, rounding_mode};
+use std::cmp::Ordering;
+
+/// What every operation gives instead of a NaN operand
+pub const CANONICAL_NAN: u32 = 0x7FC00000;
+const SIGN: u32 = 1 << 31;
+/// 2^128, any f32 result at or above it in magnitude overflows
+const OVERFLOW: f64 = 340282366920938463463374607431768211456.0;
+const MIN_NORMAL: f64 = f32::MIN_POSITIVE as f64;
+
+fn to_f64(a: u32) -> f64 {
+    f32::from_bits(a) as f64
+}
+
+fn is_nan(a: u32) -> bool {
+    f32::from_bits(a).is_nan()
+}
+
+/// Signaling NaNs have the top bit of the mantissa clear
+fn is_snan(a: u32) -> bool {
+    is_nan(a) && a & (1 << 22) == 0
+}
+
+fn nan_flags(operands: &[u32]) -> u8 {
+    if operands.iter().any(|&a| is_snan(a)) {
+        fflags::NV
+    } else {
+        0
+    }
+}
+
+/// `a + b` as `(sum, error)`, the error is exact
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let error = (a - (sum - b_virtual)) + (b - b_virtual);
+    (sum, error)
+}
+
+/// The f32 right below and right above `hi + lo`, `None` if it is one.
+/// `lo` is less than an f64 ulp of `hi`, so only its sign matters when `hi` is an f32.
+fn neighbours(hi: f64, lo: f64) -> Option<(f32, f32)> {
+    let nearest = hi as f32;
+    let above = match (nearest as f64).partial_cmp(&hi)? {
+        Ordering::Equal if lo == 0.0 => return None,
+        Ordering::Equal => lo > 0.0,
+        Ordering::Less => true,
+        Ordering::Greater => false,
+    };
+    Some(if above {
+        (nearest, nearest.next_up())
+    } else {
+        (nearest.next_down(), nearest)
+    })
+}
+
+/// One of the neighbours of `hi + lo`, as `rm` asks, which is RNE when it is not a valid mode
+fn pick(hi: f64, lo: f64, (down, up): (f32, f32), rm: u8) -> f32 {
+    let finite = |x: f32| {
+        if x.is_infinite() {
+            OVERFLOW.copysign(x as f64)
+        } else {
+            x as f64
+        }
+    };
+    let (toward_zero, away_from_zero) = if hi > 0.0 { (down, up) } else { (up, down) };
+    let midpoint = (finite(down) + finite(up)) / 2.0;
+    let to_midpoint = hi
+        .partial_cmp(&midpoint)
+        .and_then(|ordering| Some(ordering.then(lo.partial_cmp(&0.0)?)))
+        .unwrap_or(Ordering::Equal);
+    match rm {
+        rounding_mode::RTZ => toward_zero,
+        rounding_mode::RDN => down,
+        rounding_mode::RUP => up,
+        _ => match to_midpoint {
+            Ordering::Less => down,
+            Ordering::Greater => up,
+            Ordering::Equal if rm == rounding_mode::RMM => away_from_zero,
+            Ordering::Equal if down.to_bits() & 1 == 0 => down,
+            Ordering::Equal => up,
+        },
+    }
+}
+
+/// Rounds the exact value `hi + lo` to an f32, `hi` is not a NaN and `lo` is less than an f64
+/// ulp of it. Tininess is detected after rounding, as RISC-V does.
+fn round(hi: f64, lo: f64, rm: u8) -> (u32, u8) {
+    let Some(around) = neighbours(hi, lo) else {
+        return ((hi as f32).to_bits(), 0);
+    };
+    let result = pick(hi, lo, around, rm);
+    let mut flags = fflags::NX;
+    if result.is_infinite() || hi.abs() > OVERFLOW || (hi.abs() == OVERFLOW && hi * lo >= 0.0) {
+        flags |= fflags::OF;
+    }
+    if hi.abs() < MIN_NORMAL {
+        // the same rounding with an unbounded exponent, scaling by a power of two is exact
+        let scale = 2f64.powi(64);
+        let (hi, lo) = (hi * scale, lo * scale);
+        let unbounded = neighbours(hi, lo).map_or(hi, |around| pick(hi, lo, around, rm) as f64);
+        if unbounded.abs() < MIN_NORMAL * scale {
+            flags |= fflags::UF;
+        }
+    }
+    (result.to_bits(), flags)
+}
+
+/// The sign of a sum that is exactly zero, that of the operands when they agree,
+/// otherwise +0, or -0 when rounding down
+fn zero_sum(a: f64, b: f64, rm: u8) -> u32 {
+    if a.is_sign_negative() == b.is_sign_negative() {
+        (a as f32).to_bits()
+    } else if rm == rounding_mode::RDN {
+        SIGN
+    } else {
+        0
+    }
+}
+
+pub fn add(a: u32, b: u32, rm: u8) -> (u32, u8) {
+    let (x, y) = (to_f64(a), to_f64(b));
+    if x.is_nan() || y.is_nan() {
+        return (CANONICAL_NAN, nan_flags(&[a, b]));
+    }
+    if x.is_infinite() && y.is_infinite() && x != y {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    if x.is_infinite() || y.is_infinite() {
+        return round(x + y, 0.0, rm);
+    }
+    let (sum, error) = two_sum(x, y);
+    if sum == 0.0 && error == 0.0 {
+        return (zero_sum(x, y, rm), 0);
+    }
+    round(sum, error, rm)
+}
+
+pub fn sub(a: u32, b: u32, rm: u8) -> (u32, u8) {
+    add(a, b ^ SIGN, rm)
+}
+
+/// The product of two f32 is exact in f64
+pub fn mul(a: u32, b: u32, rm: u8) -> (u32, u8) {
+    let (x, y) = (to_f64(a), to_f64(b));
+    if x.is_nan() || y.is_nan() {
+        return (CANONICAL_NAN, nan_flags(&[a, b]));
+    }
+    if (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite()) {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    round(x * y, 0.0, rm)
+}
+
+pub fn div(a: u32, b: u32, rm: u8) -> (u32, u8) {
+    let (x, y) = (to_f64(a), to_f64(b));
+    if x.is_nan() || y.is_nan() {
+        return (CANONICAL_NAN, nan_flags(&[a, b]));
+    }
+    if (x == 0.0 && y == 0.0) || (x.is_infinite() && y.is_infinite()) {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    if y == 0.0 && x.is_finite() {
+        return ((((a ^ b) & SIGN) | f32::INFINITY.to_bits()), fflags::DZ);
+    }
+    let quotient = x / y;
+    if x == 0.0 || x.is_infinite() || y.is_infinite() {
+        return round(quotient, 0.0, rm);
+    }
+    // x / y is quotient + remainder / y, and the remainder is exact
+    let remainder = (-quotient).mul_add(y, x);
+    round(quotient, remainder / y, rm)
+}
+
+pub fn sqrt(a: u32, rm: u8) -> (u32, u8) {
+    let x = to_f64(a);
+    if x.is_nan() {
+        return (CANONICAL_NAN, nan_flags(&[a]));
+    }
+    if x < 0.0 {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    // including -0
+    if x == 0.0 || x.is_infinite() {
+        return (a, 0);
+    }
+    let root = x.sqrt();
+    let remainder = (-root).mul_add(root, x);
+    round(root, remainder, rm)
+}
+
+/// `a * b + c` with a single rounding, the product and `c` are negated first when asked,
+/// which gives FMADD, FMSUB, FNMSUB and FNMADD
+pub fn fma(a: u32, b: u32, c: u32, negate_product: bool, negate_c: bool, rm: u8) -> (u32, u8) {
+    let (x, y, z) = (to_f64(a), to_f64(b), to_f64(c));
+    // invalid even when c is a quiet NaN
+    let invalid_product = (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite());
+    if x.is_nan() || y.is_nan() || z.is_nan() {
+        let invalid = if invalid_product { fflags::NV } else { 0 };
+        return (CANONICAL_NAN, nan_flags(&[a, b, c]) | invalid);
+    }
+    if invalid_product {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    let product = if negate_product { -(x * y) } else { x * y };
+    let z = if negate_c { -z } else { z };
+    if product.is_infinite() && z.is_infinite() && product != z {
+        return (CANONICAL_NAN, fflags::NV);
+    }
+    if product.is_infinite() || z.is_infinite() {
+        return round(product + z, 0.0, rm);
+    }
+    let (sum, error) = two_sum(product, z);
+    if sum == 0.0 && error == 0.0 {
+        return (zero_sum(product, z, rm), 0);
+    }
+    round(sum, error, rm)
+}
+
+/// FMIN and FMAX, -0 is below +0 and a single NaN operand gives the other one
+pub fn min_max(a: u32, b: u32, max: bool) -> (u32, u8) {
+    let flags = nan_flags(&[a, b]);
+    let result = match (is_nan(a), is_nan(b)) {
+        (true, true) => CANONICAL_NAN,
+        (true, false) => b,
+        (false, true) => a,
+        _ => {
+            let (x, y) = (f32::from_bits(a), f32::from_bits(b));
+            let a_below = x < y || (x == y && a & SIGN != 0);
+            if a_below != max {
+                a
+            } else {
+                b
+            }
+        }
+    };
+    (result, flags)
+}
+
+/// FEQ only raises NV for signaling NaNs
+pub fn eq(a: u32, b: u32) -> (u32, u8) {
+    let result = f32::from_bits(a) == f32::from_bits(b);
+    (result as u32, nan_flags(&[a, b]))
+}
+
+/// FLT and FLE raise NV for any NaN
+pub fn lt(a: u32, b: u32, or_equal: bool) -> (u32, u8) {
+    let (x, y) = (f32::from_bits(a), f32::from_bits(b));
+    if x.is_nan() || y.is_nan() {
+        return (0, fflags::NV);
+    }
+    let result = if or_equal { x <= y } else { x < y };
+    (result as u32, 0)
+}
+
+/// FCVT.W.S and FCVT.WU.S, NaN and out of range values saturate and raise NV
+pub fn to_int(a: u32, signed: bool, rm: u8) -> (u32, u8) {
+    let x = to_f64(a);
+    let (min, max) = if signed {
+        (i32::MIN as f64, i32::MAX as f64)
+    } else {
+        (0.0, u32::MAX as f64)
+    };
+    if x.is_nan() {
+        return (max as i64 as u32, fflags::NV);
+    }
+    let rounded = match rm {
+        rounding_mode::RTZ => x.trunc(),
+        rounding_mode::RDN => x.floor(),
+        rounding_mode::RUP => x.ceil(),
+        rounding_mode::RMM => x.round(),
+        _ => x.round_ties_even(),
+    };
+    if rounded < min {
+        return (min as i64 as u32, fflags::NV);
+    }
+    if rounded > max {
+        return (max as i64 as u32, fflags::NV);
+    }
+    let flags = if rounded != x { fflags::NX } else { 0 };
+    (rounded as i64 as u32, flags)
+}
+
+/// FCVT.S.W and FCVT.S.WU
+pub fn from_int(a: u32, signed: bool, rm: u8) -> (u32, u8) {
+    let x = if signed { a as i32 as f64 } else { a as f64 };
+    round(x, 0.0, rm)
+}
+
+/// FSGNJ, FSGNJN and FSGNJX, the sign comes from `b`, its inverse, or both signs xored
+pub fn sign_inject(a: u32, b: u32, negate: bool, xor: bool) -> u32 {
+    let sign = if xor {
+        a ^ b
+    } else if negate {
+        !b
+    } else {
+        b
+    };
+    (a & !SIGN) | (sign & SIGN)
+}
+
+/// FCLASS, a single bit is set, from -inf at bit 0 to quiet NaN at bit 9
+pub fn class(a: u32) -> u32 {
+    let x = f32::from_bits(a);
+    let negative = a & SIGN != 0;
+    let bit = if x.is_nan() {
+        if is_snan(a) {
+            8
+        } else {
+            9
+        }
+    } else if x.is_infinite() {
+        if negative {
+            0
+        } else {
+            7
+        }
+    } else if x == 0.0 {
+        if negative {
+            3
+        } else {
+            4
+        }
+    } else if x.is_subnormal() {
+        if negative {
+            2
+        } else {
+            5
+        }
+    } else if negative {
+        1
+    } else {
+        6
+    };
+    1 << bit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rounding_mode::*;
+
+    fn bits(x: f32) -> u32 {
+        x.to_bits()
+    }
+
+    #[test]
+    fn test_round_to_nearest() {
+        // the results match the host for plain values
+        for (x, y) in [(1.5f32, 2.25f32), (1e30, 3e-7), (-7.0, 0.1), (1.0, -1.0)] {
+            assert_eq!(add(bits(x), bits(y), RNE).0, bits(x + y));
+            assert_eq!(sub(bits(x), bits(y), RNE).0, bits(x - y));
+            assert_eq!(mul(bits(x), bits(y), RNE).0, bits(x * y));
+            assert_eq!(div(bits(x), bits(y), RNE).0, bits(x / y));
+            assert_eq!(
+                fma(bits(x), bits(y), bits(x), false, false, RNE).0,
+                bits(x.mul_add(y, x))
+            );
+        }
+        assert_eq!(sqrt(bits(2.0), RNE), (bits(2f32.sqrt()), fflags::NX));
+        assert_eq!(sqrt(bits(4.0), RNE), (bits(2.0), 0));
+        assert_eq!(add(bits(1.0), bits(2.0), RNE), (bits(3.0), 0));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // 1 + 2^-24 is halfway between 1 and the next f32, ties go to even or away from zero
+        let (one, half_ulp) = (bits(1.0), bits(2f32.powi(-24)));
+        let next = bits(1.0f32.next_up());
+        assert_eq!(add(one, half_ulp, RNE), (one, fflags::NX));
+        assert_eq!(add(one, half_ulp, RMM), (next, fflags::NX));
+        assert_eq!(add(one, half_ulp, RUP), (next, fflags::NX));
+        assert_eq!(add(one, half_ulp, RDN), (one, fflags::NX));
+        assert_eq!(add(one, half_ulp, RTZ), (one, fflags::NX));
+        assert_eq!(
+            add(bits(-1.0), bits(-(2f32.powi(-24))), RUP),
+            (bits(-1.0), fflags::NX)
+        );
+        // a bit more than the tie, only seen in the error of the f64 sum
+        let (sum, error) = two_sum(1.0 + 2f64.powi(-24), 2f64.powi(-149));
+        assert_eq!(error, 2f64.powi(-149));
+        assert_eq!(round(sum, error, RNE), (next, fflags::NX));
+        assert_eq!(
+            fma(one, one, bits(2f32.powi(-60)), false, false, RNE),
+            (one, fflags::NX)
+        );
+        assert_eq!(
+            fma(
+                one,
+                one,
+                bits(2f32.powi(-24) + 2f32.powi(-47)),
+                false,
+                false,
+                RNE
+            ),
+            (next, fflags::NX)
+        );
+
+        // 1/3 is inexact, up and down are one ulp apart
+        let (up, _) = div(one, bits(3.0), RUP);
+        let (down, _) = div(one, bits(3.0), RDN);
+        assert_eq!(up, down + 1);
+        assert_eq!(div(one, bits(3.0), RTZ).0, down);
+        assert_eq!(div(bits(-1.0), bits(3.0), RTZ).0, down | SIGN);
+        let (up, _) = sqrt(bits(2.0), RUP);
+        let (down, _) = sqrt(bits(2.0), RDN);
+        assert_eq!(up, down + 1);
+    }
+
+    #[test]
+    fn test_flags() {
+        let max = bits(f32::MAX);
+        assert_eq!(
+            mul(max, bits(2.0), RNE),
+            (bits(f32::INFINITY), fflags::OF | fflags::NX)
+        );
+        assert_eq!(mul(max, bits(2.0), RTZ), (max, fflags::OF | fflags::NX));
+        assert_eq!(
+            div(bits(1.0), bits(0.0), RNE),
+            (bits(f32::INFINITY), fflags::DZ)
+        );
+        assert_eq!(div(bits(0.0), bits(0.0), RNE), (CANONICAL_NAN, fflags::NV));
+        assert_eq!(sqrt(bits(-1.0), RNE), (CANONICAL_NAN, fflags::NV));
+        assert_eq!(sqrt(bits(-0.0), RNE), (bits(-0.0), 0));
+        let inf = bits(f32::INFINITY);
+        assert_eq!(add(inf, inf | SIGN, RNE), (CANONICAL_NAN, fflags::NV));
+        // inf * 0 is invalid even with a quiet NaN to add
+        assert_eq!(
+            fma(inf, 0, CANONICAL_NAN, false, false, RNE),
+            (CANONICAL_NAN, fflags::NV)
+        );
+        let min_normal = bits(f32::MIN_POSITIVE);
+        // half of the smallest subnormal, a tie that goes to the even zero
+        assert_eq!(mul(1, bits(0.5), RNE), (0, fflags::UF | fflags::NX));
+        assert_eq!(mul(1, bits(0.5), RUP), (1, fflags::UF | fflags::NX));
+        // exact subnormals do not underflow
+        assert_eq!(
+            mul(min_normal, bits(0.75), RNE),
+            (bits(f32::MIN_POSITIVE * 0.75), 0)
+        );
+        // rounds up to the smallest normal, which is not tiny after rounding
+        let below = bits(f32::MIN_POSITIVE.next_down());
+        assert_eq!(
+            mul(below, bits(1.0f32.next_up()), RUP),
+            (min_normal, fflags::NX)
+        );
+        let snan = 0x7F800001;
+        assert_eq!(add(snan, bits(1.0), RNE), (CANONICAL_NAN, fflags::NV));
+        assert_eq!(add(CANONICAL_NAN, bits(1.0), RNE), (CANONICAL_NAN, 0));
+    }
+
+    #[test]
+    fn test_signed_zero() {
+        assert_eq!(add(bits(1.0), bits(-1.0), RNE), (0, 0));
+        assert_eq!(add(bits(1.0), bits(-1.0), RDN), (SIGN, 0));
+        assert_eq!(add(bits(-0.0), bits(-0.0), RNE), (SIGN, 0));
+        assert_eq!(mul(bits(-1.0), 0, RNE), (SIGN, 0));
+        assert_eq!(
+            fma(bits(1.0), bits(1.0), bits(1.0), true, false, RDN),
+            (SIGN, 0)
+        );
+    }
+
+    #[test]
+    fn test_compare_convert() {
+        let snan = 0x7F800001;
+        assert_eq!(min_max(bits(-0.0), 0, false), (bits(-0.0), 0));
+        assert_eq!(min_max(bits(-0.0), 0, true), (0, 0));
+        assert_eq!(min_max(CANONICAL_NAN, bits(2.0), false), (bits(2.0), 0));
+        assert_eq!(min_max(snan, snan, true), (CANONICAL_NAN, fflags::NV));
+        assert_eq!(eq(CANONICAL_NAN, 0), (0, 0));
+        assert_eq!(eq(snan, 0), (0, fflags::NV));
+        assert_eq!(lt(CANONICAL_NAN, 0, true), (0, fflags::NV));
+        assert_eq!(lt(bits(1.0), bits(2.0), false), (1, 0));
+
+        assert_eq!(to_int(bits(-2.5), true, RNE), (-2i32 as u32, fflags::NX));
+        assert_eq!(to_int(bits(-2.5), true, RMM), (-3i32 as u32, fflags::NX));
+        assert_eq!(to_int(bits(-2.5), true, RUP), (-2i32 as u32, fflags::NX));
+        assert_eq!(to_int(bits(3e9), true, RNE), (i32::MAX as u32, fflags::NV));
+        assert_eq!(to_int(bits(3e9), false, RNE), (3000000000, 0));
+        assert_eq!(to_int(bits(-0.4), false, RNE), (0, fflags::NX));
+        assert_eq!(to_int(bits(-1.0), false, RNE), (0, fflags::NV));
+        assert_eq!(
+            to_int(CANONICAL_NAN, true, RNE),
+            (i32::MAX as u32, fflags::NV)
+        );
+        assert_eq!(from_int(-3i32 as u32, true, RNE), (bits(-3.0), 0));
+        assert_eq!(
+            from_int(u32::MAX, false, RTZ),
+            (bits(4294967040.0), fflags::NX)
+        );
+        assert_eq!(
+            from_int(u32::MAX, false, RNE),
+            (bits(4294967296.0), fflags::NX)
+        );
+
+        assert_eq!(sign_inject(bits(1.0), bits(-2.0), false, false), bits(-1.0));
+        assert_eq!(sign_inject(bits(1.0), bits(-2.0), true, false), bits(1.0));
+        assert_eq!(sign_inject(bits(-1.0), bits(-2.0), false, true), bits(1.0));
+        assert_eq!(class(bits(f32::NEG_INFINITY)), 1 << 0);
+        assert_eq!(class(bits(-0.0)), 1 << 3);
+        assert_eq!(class(1), 1 << 5);
+        assert_eq!(class(snan), 1 << 8);
+        assert_eq!(class(CANONICAL_NAN), 1 << 9);
+    }
+}
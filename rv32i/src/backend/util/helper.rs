@@ -17,7 +17,7 @@ pub fn sign_extend(val: u32, upper_idx: u8) -> Word {
 macro_rules! send_word {
     ($self:ident, $output:expr, $word:expr) => {
         let out_event = WordEvent::new(
-            $self.sim_manager.get_curr_cycle(),
+            $self.sim_manager.get_curr_time(),
             $word,
             $self.sim_manager.request_new_event_id(),
         );
@@ -29,10 +29,22 @@ macro_rules! send_word {
 macro_rules! send_byte {
     ($self:ident, $output:expr, $byte:expr) => {
         let out_event = ByteEvent::new(
-            $self.sim_manager.get_curr_cycle(),
+            $self.sim_manager.get_curr_time(),
             $byte,
             $self.sim_manager.request_new_event_id(),
         );
         send!($self, $output, out_event);
     };
 }
+
+#[macro_export]
+macro_rules! send_bits {
+    ($self:ident, $output:expr, $bits:expr) => {
+        let out_event = BitsEvent::new(
+            $self.sim_manager.get_curr_time(),
+            $bits,
+            $self.sim_manager.request_new_event_id(),
+        );
+        send!($self, $output, out_event);
+    };
+}
@@ -1,5 +1,9 @@
+pub mod bits;
 pub mod blackhole;
 pub mod byte;
+pub mod byte_arith;
+pub mod byte_fmt;
 pub mod event;
+pub mod float;
 pub mod helper;
 pub mod types;
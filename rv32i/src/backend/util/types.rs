@@ -1,18 +1,29 @@
+use crate::backend::util::bits::Bits;
 use crate::backend::util::byte::Bytes;
 
 pub type Word = Bytes<4>;
 pub type Byte = Bytes<1>;
+pub type RegIdx = Bits<5>;
+pub type Opcode = Bits<7>;
+pub type Funct3 = Bits<3>;
+pub type Funct7 = Bits<7>;
+pub type CsrAddr = Bits<12>;
 
 pub mod mux_sel {
     pub mod pc {
         pub const PC_PLUS4: u8 = 0x00;
         pub const ALU_OUT: u8 = 0x01;
         pub const ALU_MOD2: u8 = 0x02;
+        pub const MTVEC: u8 = 0x03;
+        pub const MEPC: u8 = 0x04;
+        pub const PC_PLUS2: u8 = 0x05;
     }
 
     pub mod mar {
         pub const PC_OUT: u8 = 0x00;
         pub const ALU_OUT: u8 = 0x01;
+        /// The next word when the PC points at the upper half of one
+        pub const PC_PLUS2: u8 = 0x02;
     }
 
     pub mod cmp {
@@ -32,6 +43,7 @@ pub mod mux_sel {
         pub const S_IMM: u8 = 0x03;
         pub const J_IMM: u8 = 0x04;
         pub const RS2_OUT: u8 = 0x05;
+        pub const ZERO: u8 = 0x06;
     }
 
     pub mod regfile {
@@ -44,6 +56,39 @@ pub mod mux_sel {
         pub const LBU: u8 = 0x06;
         pub const LH: u8 = 0x07;
         pub const LHU: u8 = 0x08;
+        pub const CSR_OUT: u8 = 0x09;
+        pub const MULDIV_OUT: u8 = 0x0A;
+        pub const PC_PLUS2: u8 = 0x0B;
+        pub const FPU_OUT: u8 = 0x0C;
+    }
+
+    pub mod fp_regfile {
+        pub const MDR_OUT: u8 = 0x00;
+        pub const FPU_OUT: u8 = 0x01;
+    }
+
+    pub mod data_out {
+        pub const RS2_OUT: u8 = 0x00;
+        pub const FP_RS2_OUT: u8 = 0x01;
+    }
+
+    pub mod ir {
+        /// The instruction is within the word in MDR
+        pub const MDR_OUT: u8 = 0x00;
+        /// The low half was kept from the previous word, the high half is in MDR
+        pub const STRADDLE: u8 = 0x01;
+    }
+
+    pub mod csr {
+        pub const RS1_OUT: u8 = 0x00;
+        pub const ZIMM: u8 = 0x01;
+    }
+
+    pub mod tval {
+        pub const ZERO: u8 = 0x00;
+        pub const PC_OUT: u8 = 0x01;
+        pub const IR_OUT: u8 = 0x02;
+        pub const MAR_OUT: u8 = 0x03;
     }
 }
 
@@ -57,6 +102,16 @@ pub mod opcode {
     pub const STORE: u8 = 0b00100011;
     pub const IMM: u8 = 0b00010011;
     pub const REG: u8 = 0b00110011;
+    pub const SYSTEM: u8 = 0b01110011;
+    pub const MISC_MEM: u8 = 0b00001111;
+    pub const AMO: u8 = 0b00101111;
+    pub const LOAD_FP: u8 = 0b00000111;
+    pub const STORE_FP: u8 = 0b00100111;
+    pub const MADD: u8 = 0b01000011;
+    pub const MSUB: u8 = 0b01000111;
+    pub const NMSUB: u8 = 0b01001011;
+    pub const NMADD: u8 = 0b01001111;
+    pub const OP_FP: u8 = 0b01010011;
 }
 
 pub mod funct3 {
@@ -85,12 +140,164 @@ pub mod funct3 {
 
     pub mod arith {
         pub const ADD: u8 = 0b000;
+        pub const SLL: u8 = 0b001;
         pub const SLT: u8 = 0b010;
         pub const SLTU: u8 = 0b011;
+        pub const XOR: u8 = 0b100;
         pub const SR: u8 = 0b101;
+        pub const OR: u8 = 0b110;
+        pub const AND: u8 = 0b111;
+    }
+
+    pub mod misc_mem {
+        pub const FENCE: u8 = 0b000;
+        pub const FENCE_I: u8 = 0b001;
+    }
+
+    pub mod system {
+        pub const PRIV: u8 = 0b000;
+        pub const CSRRW: u8 = 0b001;
+        pub const CSRRS: u8 = 0b010;
+        pub const CSRRC: u8 = 0b011;
+        pub const CSRRWI: u8 = 0b101;
+        pub const CSRRSI: u8 = 0b110;
+        pub const CSRRCI: u8 = 0b111;
+    }
+
+    /// Only the word wide AMOs exist on RV32
+    pub mod amo {
+        pub const W: u8 = 0b010;
+    }
+
+    pub mod muldiv {
+        pub const MUL: u8 = 0b000;
+        pub const MULH: u8 = 0b001;
+        pub const MULHSU: u8 = 0b010;
+        pub const MULHU: u8 = 0b011;
+        pub const DIV: u8 = 0b100;
+        pub const DIVU: u8 = 0b101;
+        pub const REM: u8 = 0b110;
+        pub const REMU: u8 = 0b111;
     }
 }
 
+/// The top 5 bits of funct7 of an AMO, the other two are aq and rl
+pub mod amo {
+    pub const ADD: u8 = 0b00000;
+    pub const SWAP: u8 = 0b00001;
+    pub const LR: u8 = 0b00010;
+    pub const SC: u8 = 0b00011;
+    pub const XOR: u8 = 0b00100;
+    pub const OR: u8 = 0b01000;
+    pub const AND: u8 = 0b01100;
+    pub const MIN: u8 = 0b10000;
+    pub const MAX: u8 = 0b10100;
+    pub const MINU: u8 = 0b11000;
+    pub const MAXU: u8 = 0b11100;
+}
+
+pub mod funct7 {
+    pub const BASE: u8 = 0b0000000;
+    /// SUB and SRA
+    pub const ALT: u8 = 0b0100000;
+    /// RV32M under `opcode::REG`
+    pub const MULDIV: u8 = 0b0000001;
+
+    /// Single precision instructions under `opcode::OP_FP`
+    pub mod fp {
+        pub const ADD: u8 = 0b0000000;
+        pub const SUB: u8 = 0b0000100;
+        pub const MUL: u8 = 0b0001000;
+        pub const DIV: u8 = 0b0001100;
+        pub const SQRT: u8 = 0b0101100;
+        pub const SGNJ: u8 = 0b0010000;
+        pub const MIN_MAX: u8 = 0b0010100;
+        pub const CVT_W_S: u8 = 0b1100000;
+        /// FMV.X.W and FCLASS
+        pub const MV_X_W: u8 = 0b1110000;
+        pub const CMP: u8 = 0b1010000;
+        pub const CVT_S_W: u8 = 0b1101000;
+        pub const MV_W_X: u8 = 0b1111000;
+    }
+}
+
+/// The rm field of the F instructions, and frm
+pub mod rounding_mode {
+    pub const RNE: u8 = 0b000;
+    pub const RTZ: u8 = 0b001;
+    pub const RDN: u8 = 0b010;
+    pub const RUP: u8 = 0b011;
+    pub const RMM: u8 = 0b100;
+    /// Only valid in rm, takes frm
+    pub const DYN: u8 = 0b111;
+}
+
+/// The accrued exception flags, the low bits of fcsr
+pub mod fflags {
+    pub const NX: u8 = 1 << 0;
+    pub const UF: u8 = 1 << 1;
+    pub const OF: u8 = 1 << 2;
+    pub const DZ: u8 = 1 << 3;
+    pub const NV: u8 = 1 << 4;
+}
+
+/// What `Fpu` does, decoded by `Control` from the opcode, funct7, funct3 and rs2
+pub mod fpu_op {
+    pub const ADD: u8 = 0;
+    pub const SUB: u8 = 1;
+    pub const MUL: u8 = 2;
+    pub const DIV: u8 = 3;
+    pub const SQRT: u8 = 4;
+    pub const MADD: u8 = 5;
+    pub const MSUB: u8 = 6;
+    pub const NMSUB: u8 = 7;
+    pub const NMADD: u8 = 8;
+    pub const SGNJ: u8 = 9;
+    pub const SGNJN: u8 = 10;
+    pub const SGNJX: u8 = 11;
+    pub const MIN: u8 = 12;
+    pub const MAX: u8 = 13;
+    pub const CVT_W_S: u8 = 14;
+    pub const CVT_WU_S: u8 = 15;
+    pub const MV_X_W: u8 = 16;
+    pub const EQ: u8 = 17;
+    pub const LT: u8 = 18;
+    pub const LE: u8 = 19;
+    pub const CLASS: u8 = 20;
+    pub const CVT_S_W: u8 = 21;
+    pub const CVT_S_WU: u8 = 22;
+    pub const MV_W_X: u8 = 23;
+}
+
+/// The SYSTEM instructions with funct3 of `funct3::system::PRIV`, they are matched as a whole
+pub mod priv_inst {
+    pub const ECALL: u32 = 0x00000073;
+    pub const EBREAK: u32 = 0x00100073;
+    pub const MRET: u32 = 0x30200073;
+    pub const WFI: u32 = 0x10500073;
+}
+
+/// mcause values of the synchronous exceptions
+pub mod exception {
+    pub const INST_ADDR_MISALIGNED: u32 = 0;
+    pub const ILLEGAL_INST: u32 = 2;
+    pub const BREAKPOINT: u32 = 3;
+    pub const LOAD_ADDR_MISALIGNED: u32 = 4;
+    pub const LOAD_ACCESS_FAULT: u32 = 5;
+    pub const STORE_ADDR_MISALIGNED: u32 = 6;
+    pub const STORE_ACCESS_FAULT: u32 = 7;
+    pub const ECALL_M: u32 = 11;
+}
+
+/// mcause values of the interrupts, without `FLAG`
+pub mod interrupt {
+    /// Set in mcause for interrupts
+    pub const FLAG: u32 = 1 << 31;
+    pub const SOFTWARE_M: u32 = 3;
+    pub const TIMER_M: u32 = 7;
+    pub const EXTERNAL_M: u32 = 11;
+}
+
 pub mod alu_op {
     pub const ADD: u8 = 0b000;
     pub const SLL: u8 = 0b001;
@@ -102,11 +309,51 @@ pub mod alu_op {
     pub const AND: u8 = 0b111;
 }
 
+/// Same as the low bits of funct3 of the CSR instructions
+pub mod csr_op {
+    /// CSRRS and CSRRC with rs1 of x0, or their immediate forms with a zimm of 0: no write
+    pub const READ: u8 = 0b00;
+    pub const RW: u8 = 0b01;
+    pub const RS: u8 = 0b10;
+    pub const RC: u8 = 0b11;
+}
+
+/// CSR addresses
+pub mod csr {
+    pub const FFLAGS: u16 = 0x001;
+    pub const FRM: u16 = 0x002;
+    pub const FCSR: u16 = 0x003;
+    pub const MSTATUS: u16 = 0x300;
+    pub const MISA: u16 = 0x301;
+    pub const MIE: u16 = 0x304;
+    pub const MTVEC: u16 = 0x305;
+    pub const MSCRATCH: u16 = 0x340;
+    pub const MEPC: u16 = 0x341;
+    pub const MCAUSE: u16 = 0x342;
+    pub const MTVAL: u16 = 0x343;
+    pub const MIP: u16 = 0x344;
+    pub const MCYCLE: u16 = 0xB00;
+    pub const MINSTRET: u16 = 0xB02;
+    pub const MCYCLEH: u16 = 0xB80;
+    pub const MINSTRETH: u16 = 0xB82;
+    pub const CYCLE: u16 = 0xC00;
+    pub const INSTRET: u16 = 0xC02;
+    pub const CYCLEH: u16 = 0xC80;
+    pub const INSTRETH: u16 = 0xC82;
+    pub const MVENDORID: u16 = 0xF11;
+    pub const MARCHID: u16 = 0xF12;
+    pub const MIMPID: u16 = 0xF13;
+    pub const MHARTID: u16 = 0xF14;
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum States {
     Fetch1,
     Fetch2,
     Fetch3,
+    /// Only for an instruction that straddles two words, reads the second one
+    Fetch4,
+    Fetch5,
     Decode,
     Imm,
     Lui,
@@ -120,4 +367,19 @@ pub enum States {
     Jal,
     Jalr,
     Reg,
+    /// Waits for `MulDiv` to be done
+    MulDiv1,
+    MulDiv2,
+    /// The read, the write and the reservation are done in a single `MemCtl` request
+    Amo1,
+    Amo2,
+    /// Waits for `Fpu` to be done
+    Fp1,
+    Fp2,
+    Csr,
+    /// FENCE, FENCE.I and WFI, nothing to order or wait for, only the PC moves on
+    Nop,
+    /// Takes an exception with the given mcause
+    Trap(u32),
+    Mret,
 }
@@ -0,0 +1,241 @@
+use crate::backend::plugins::CommitFormat;
+use crate::frontend::debugger::parse_u32;
+
+/// A limit stopped the program before it ended, like timeout(1)
+pub const EXIT_LIMIT: i32 = 124;
+pub const EXIT_USAGE: i32 = 2;
+
+/// Only the low byte of a status reaches the parent, a failing code that is a multiple of 256
+/// still has to read as a failure
+pub fn exit_status(exit_code: u32) -> i32 {
+    match exit_code {
+        code if code != 0 && code & 0xFF == 0 => 1,
+        code => (code & 0xFF) as i32,
+    }
+}
+
+pub const USAGE: &str = "\
+usage: rsim_rv32i [options] <program>
+
+<program> is an ELF file or a flat binary, BIN_FILE is used when it is not given
+
+options:
+  --load-addr <addr>       where a flat binary goes, 0x40000000 by default
+  --reset-pc <addr>        the first pc, the ELF entry point or the load address by default
+  --threads <n>            dispatcher threads, 4 by default
+  --commit-log <path>      log every committed instruction to <path>, LOG_FILE when not given
+  --commit-format <fmt>    spike, the default, or pc to leave out the register and memory writes
+  --max-cycles <n>         stop after <n> cycles
+  --max-instructions <n>   stop after <n> instructions
+  --stats <path>           write the stats report to <path>, CSV if it ends in .csv, JSON otherwise,
+                           STATS_JSON and STATS_CSV when not given
+  --syscalls               serve the newlib syscalls on ECALL from the host
+  --quiet                  only print what the program prints
+  --debug                  start the interactive debugger
+  --gdb <addr>             wait for gdb on host:port or unix:<path>
+  --check-determinism      compare the event streams of 1 and <threads> threads
+  --help                   print this message
+
+A limit of 0 stops before the first instruction.
+
+MUL_LATENCY, DIV_LATENCY and FPU_{ADD,MUL,FMA,DIV,SQRT}_LATENCY set the latencies in cycles.
+
+The exit status is the code the program exited with through HTIF or the exit syscall, 1 if that
+code is not 0 but its low byte is, 0 if it ran into an end instruction, 124 if a limit stopped it
+and 2 on bad arguments.
+";
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub program: Option<String>,
+    pub load_addr: u32,
+    pub reset_pc: Option<u32>,
+    pub threads: usize,
+    pub commit_log: Option<String>,
+    pub commit_format: CommitFormat,
+    pub max_cycles: Option<u128>,
+    pub max_instructions: Option<u128>,
+    pub stats: Option<String>,
+    pub syscalls: bool,
+    pub quiet: bool,
+    pub debug: bool,
+    pub gdb: Option<String>,
+    pub check_determinism: bool,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            program: None,
+            load_addr: 0x40000000,
+            reset_pc: None,
+            threads: 4,
+            commit_log: None,
+            commit_format: CommitFormat::Spike,
+            max_cycles: None,
+            max_instructions: None,
+            stats: None,
+            syscalls: false,
+            quiet: false,
+            debug: false,
+            gdb: None,
+            check_determinism: false,
+            help: false,
+        }
+    }
+}
+
+/// Same as `parse_u32`, limits go past 32 bits
+fn parse_u128(input: &str) -> Option<u128> {
+    let input = input.replace('_', "");
+    if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        u128::from_str_radix(hex, 16).ok()
+    } else {
+        input.parse().ok()
+    }
+}
+
+/// `args` without the binary name, the error says which argument is wrong
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        let number = |value: String| {
+            parse_u32(&value).ok_or_else(|| format!("{} is not a number: {}", arg, value))
+        };
+        let limit = |value: String| {
+            parse_u128(&value).ok_or_else(|| format!("{} is not a number: {}", arg, value))
+        };
+        match arg.as_str() {
+            "--load-addr" => options.load_addr = number(value()?)?,
+            "--reset-pc" => options.reset_pc = Some(number(value()?)?),
+            "--threads" => {
+                options.threads = match number(value()?)? {
+                    0 => return Err("--threads needs at least 1".to_string()),
+                    threads => threads as usize,
+                }
+            }
+            "--commit-log" => options.commit_log = Some(value()?),
+            "--commit-format" => {
+                options.commit_format = match value()?.as_str() {
+                    "spike" => CommitFormat::Spike,
+                    "pc" => CommitFormat::Pc,
+                    format => return Err(format!("unknown commit format: {}", format)),
+                }
+            }
+            "--max-cycles" => options.max_cycles = Some(limit(value()?)?),
+            "--max-instructions" => options.max_instructions = Some(limit(value()?)?),
+            "--stats" => options.stats = Some(value()?),
+            "--syscalls" => options.syscalls = true,
+            "--quiet" | "-q" => options.quiet = true,
+            "--debug" => options.debug = true,
+            "--gdb" => options.gdb = Some(value()?),
+            "--check-determinism" => options.check_determinism = true,
+            "--help" | "-h" => options.help = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if options.program.is_some() => {
+                return Err(format!("only one program can be given: {}", arg))
+            }
+            _ => options.program = Some(arg),
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+
+        let options = parse(&[
+            "--load-addr",
+            "0x8000_0000",
+            "--reset-pc",
+            "0x80000100",
+            "--threads",
+            "1",
+            "--commit-log",
+            "commits.log",
+            "--commit-format",
+            "pc",
+            "--max-cycles",
+            "10_000_000_000",
+            "--max-instructions",
+            "0x1000",
+            "--stats",
+            "stats.csv",
+            "--syscalls",
+            "-q",
+            "hello.elf",
+        ])
+        .unwrap();
+        assert_eq!(
+            options,
+            Options {
+                program: Some("hello.elf".to_string()),
+                load_addr: 0x80000000,
+                reset_pc: Some(0x80000100),
+                threads: 1,
+                commit_log: Some("commits.log".to_string()),
+                commit_format: CommitFormat::Pc,
+                max_cycles: Some(10_000_000_000),
+                max_instructions: Some(0x1000),
+                stats: Some("stats.csv".to_string()),
+                syscalls: true,
+                quiet: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_exit_status() {
+        assert_eq!(exit_status(0), 0);
+        assert_eq!(exit_status(13), 13);
+        assert_eq!(exit_status(255), 255);
+        assert_eq!(exit_status(256), 1);
+        assert_eq!(exit_status(0x1_0000), 1);
+        assert_eq!(exit_status(257), 1);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert_eq!(
+            parse(&["--threads"]).unwrap_err(),
+            "--threads needs a value"
+        );
+        assert_eq!(
+            parse(&["--threads", "0"]).unwrap_err(),
+            "--threads needs at least 1"
+        );
+        assert_eq!(
+            parse(&["--max-cycles", "lots"]).unwrap_err(),
+            "--max-cycles is not a number: lots"
+        );
+        assert_eq!(
+            parse(&["--max-instructions", "-1"]).unwrap_err(),
+            "--max-instructions is not a number: -1"
+        );
+        assert_eq!(
+            parse(&["--commit-format", "json"]).unwrap_err(),
+            "unknown commit format: json"
+        );
+        assert_eq!(parse(&["--fast"]).unwrap_err(), "unknown option: --fast");
+        assert_eq!(
+            parse(&["a.elf", "b.elf"]).unwrap_err(),
+            "only one program can be given: b.elf"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::backend::core::{Core, StopReason};
+use crate::backend::util::types::{Byte, CsrAddr, Funct3, Opcode, RegIdx, Word};
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+step                  run one cycle
+stepi                 run one instruction
+continue              run until a breakpoint, a watchpoint or the end of the program
+break <pc>            set a breakpoint at <pc>
+delete <pc>           remove the breakpoint at <pc>
+info regs             print the register file
+info break            print the breakpoints
+info csr <addr>       print the CSR at <addr>, e.g. info csr 0x300
+x/<n> <addr>          print <n> words of memory starting at <addr>
+print <comp>.<port>   print the value of an input port, e.g. print regfile.rd_idx
+state                 print the control FSM state
+quit                  exit the debugger
+
+<pc> and <addr> can also be a symbol when the program is an ELF file, e.g. break main
+";
+
+/// Accepts `0x` prefixed hex or decimal, underscores are ignored
+pub fn parse_u32(input: &str) -> Option<u32> {
+    let input = input.replace('_', "");
+    if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        u32::from_str_radix(hex, 16).ok()
+    } else {
+        input.parse().ok()
+    }
+}
+
+/// A gdb-like command prompt on top of `Core`
+pub struct Debugger {
+    core: Core,
+}
+
+impl Debugger {
+    pub fn new(core: Core) -> Self {
+        Debugger { core }
+    }
+
+    /// Reads commands from `input` until `quit` or EOF
+    pub fn run(&mut self, input: impl BufRead, out: &mut impl Write) -> std::io::Result<()> {
+        write!(out, "(rsim) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            if !self.execute(&line?, out)? {
+                break;
+            }
+            write!(out, "(rsim) ")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command, returns false if the debugger should exit
+    pub fn execute(&mut self, command: &str, out: &mut impl Write) -> std::io::Result<bool> {
+        let args: Vec<_> = command.split_whitespace().collect();
+        match args.as_slice() {
+            [] => {}
+            ["step"] | ["s"] => {
+                self.core.run_cycle();
+                self.print_location(out)?;
+            }
+            ["stepi"] | ["si"] => {
+                if self.core.can_end() {
+                    writeln!(out, "program has ended")?;
+                } else {
+                    self.core.run_instruction();
+                    self.print_location(out)?;
+                }
+            }
+            ["continue"] | ["c"] => self.cont(out)?,
+            ["break", addr] | ["b", addr] => match self.parse_addr(addr) {
+                Some(addr) => {
+                    self.core.add_breakpoint(addr);
+                    writeln!(out, "breakpoint at 0x{:08x}", addr)?;
+                }
+                None => writeln!(out, "invalid address: {}", addr)?,
+            },
+            ["delete", addr] | ["d", addr] => match self.parse_addr(addr) {
+                Some(addr) if self.core.remove_breakpoint(addr) => {
+                    writeln!(out, "deleted breakpoint at 0x{:08x}", addr)?;
+                }
+                _ => writeln!(out, "no breakpoint at {}", addr)?,
+            },
+            ["info", "regs"] | ["info", "registers"] => self.print_regs(out)?,
+            ["info", "break"] => {
+                for addr in self.core.get_breakpoints() {
+                    writeln!(out, "0x{:08x}", addr)?;
+                }
+            }
+            ["info", "csr", addr] => match parse_u32(addr) {
+                Some(addr) if addr < 0x1000 => writeln!(
+                    out,
+                    "csr 0x{:03x} = 0x{}",
+                    addr,
+                    self.core.get_csr(addr as u16)
+                )?,
+                _ => writeln!(out, "invalid csr: {}", addr)?,
+            },
+            [examine, addr] if examine.starts_with("x/") => {
+                match (examine[2..].parse::<u32>(), self.parse_addr(addr)) {
+                    (Ok(count), Some(addr)) => self.print_mem(addr, count, out)?,
+                    _ => writeln!(out, "usage: x/<n> <addr>")?,
+                }
+            }
+            ["x", addr] => match self.parse_addr(addr) {
+                Some(addr) => self.print_mem(addr, 1, out)?,
+                None => writeln!(out, "usage: x/<n> <addr>")?,
+            },
+            ["print", path] | ["p", path] => self.print_port(path, out)?,
+            ["state"] => {
+                let (state, next_state) = self.core.get_state();
+                writeln!(out, "state: {:?}, next_state: {:?}", state, next_state)?;
+            }
+            ["help"] | ["h"] => write!(out, "{}", HELP)?,
+            ["quit"] | ["q"] => return Ok(false),
+            _ => writeln!(out, "unknown command: {}, try help", command.trim())?,
+        }
+        Ok(true)
+    }
+
+    /// A number, or the address of a symbol
+    fn parse_addr(&self, input: &str) -> Option<u32> {
+        parse_u32(input).or_else(|| self.core.get_symbols().lookup(input))
+    }
+
+    fn cont(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        self.core.sim_manager.resume();
+        let reason = self.core.run_end();
+        out.write_all(&self.core.take_console_output())?;
+        match reason {
+            StopReason::Ended => match self.core.get_exit_code() {
+                Some(exit_code) => writeln!(out, "program exited with code {}", exit_code)?,
+                None => writeln!(out, "program has ended")?,
+            },
+            StopReason::Breakpoint => writeln!(out, "breakpoint hit")?,
+            StopReason::Interrupted => writeln!(out, "interrupted")?,
+            StopReason::Watchpoint => {
+                for hit in self.core.sim_manager.take_watchpoint_hits() {
+                    writeln!(
+                        out,
+                        "watchpoint {} hit: component {} port {} @ cycle {}",
+                        hit.watchpoint_id, hit.component_id, hit.port_name, hit.cycle
+                    )?;
+                }
+            }
+        }
+        self.print_location(out)
+    }
+
+    fn print_location(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let (state, _) = self.core.get_state();
+        let function = Into::<Option<u32>>::into(self.core.get_pc())
+            .and_then(|pc| self.core.get_symbols().function_at(pc))
+            .map_or(String::new(), |function| format!(" <{}>", function.name));
+        writeln!(
+            out,
+            "pc: 0x{}{} ir: 0x{} state: {:?} cycle: {} instret: {}",
+            self.core.get_pc(),
+            function,
+            self.core.get_ir(),
+            state,
+            self.core.sim_manager.get_curr_cycle(),
+            self.core.get_instructions_ran()
+        )
+    }
+
+    fn print_regs(&self, out: &mut impl Write) -> std::io::Result<()> {
+        for row in 0..8u8 {
+            let line = (0..4u8)
+                .map(|col| {
+                    let idx = row + col * 8;
+                    format!("x{:<2} 0x{}", idx, self.core.get_register(idx))
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(out, "{}", line)?;
+        }
+        writeln!(out, "pc  0x{}", self.core.get_pc())
+    }
+
+    fn print_mem(&self, addr: u32, count: u32, out: &mut impl Write) -> std::io::Result<()> {
+        for i in 0..count {
+            let word_addr = addr.wrapping_add(i * 4);
+            if i % 4 == 0 {
+                if i != 0 {
+                    writeln!(out)?;
+                }
+                write!(out, "0x{:08x}:", word_addr)?;
+            }
+            write!(out, " 0x{}", self.core.read_mem(Word::from(word_addr)))?;
+        }
+        writeln!(out)
+    }
+
+    fn print_port(&self, path: &str, out: &mut impl Write) -> std::io::Result<()> {
+        let Some((component_name, port_name)) = path.split_once('.') else {
+            return writeln!(out, "usage: print <component>.<port>");
+        };
+        let Some(component) = self.core.get_component(component_name) else {
+            return writeln!(out, "no such component: {}", component_name);
+        };
+        let component = component.lock().unwrap();
+        match component.get_port(port_name) {
+            Some(value) => {
+                if let Some(word) = value.downcast_ref::<Word>() {
+                    writeln!(out, "{} = 0x{}", path, word)
+                } else if let Some(byte) = value.downcast_ref::<Byte>() {
+                    writeln!(out, "{} = 0x{}", path, byte)
+                } else if let Some(idx) = value.downcast_ref::<RegIdx>() {
+                    writeln!(out, "{} = 0x{}", path, idx)
+                } else if let Some(addr) = value.downcast_ref::<CsrAddr>() {
+                    writeln!(out, "{} = 0x{}", path, addr)
+                } else if let Some(funct3) = value.downcast_ref::<Funct3>() {
+                    writeln!(out, "{} = 0x{}", path, funct3)
+                } else if let Some(opcode) = value.downcast_ref::<Opcode>() {
+                    // Funct7 is the same type
+                    writeln!(out, "{} = 0x{}", path, opcode)
+                } else {
+                    writeln!(out, "{} has an unsupported type", path)
+                }
+            }
+            None => writeln!(out, "no such port: {}", path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::core::{core_with_program, TEST_PROGRAM};
+
+    fn execute(debugger: &mut Debugger, command: &str) -> String {
+        let mut out = Vec::new();
+        assert!(debugger.execute(command, &mut out).unwrap());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_debugger() {
+        let mut debugger = Debugger::new(core_with_program(4, &TEST_PROGRAM));
+
+        assert!(execute(&mut debugger, "state").contains("state: Fetch1"));
+        execute(&mut debugger, "step");
+        assert!(execute(&mut debugger, "state").contains("state: Fetch2"));
+
+        execute(&mut debugger, "stepi");
+        assert_eq!(debugger.core.get_pc(), Word::from(0x40000004u32));
+        assert!(execute(&mut debugger, "info regs").contains("x1  0x00000005"));
+
+        assert!(execute(&mut debugger, "break 0x4000_000c").contains("0x4000000c"));
+        assert!(execute(&mut debugger, "continue").contains("breakpoint hit"));
+        assert_eq!(debugger.core.get_pc(), Word::from(0x4000000cu32));
+        assert!(execute(&mut debugger, "info regs").contains("x2  0x00000008"));
+        assert!(execute(&mut debugger, "print regfile.rd_idx").contains("= 0x03"));
+        assert!(execute(&mut debugger, "print regfile.nope").contains("no such port"));
+        assert!(execute(&mut debugger, "print nope.rs2_idx").contains("no such component"));
+
+        assert!(execute(&mut debugger, "continue").contains("program has ended"));
+        assert_eq!(
+            execute(&mut debugger, "info csr 0x301"),
+            "csr 0x301 = 0x40001125\n"
+        );
+        assert!(execute(&mut debugger, "info csr 0x1000").contains("invalid csr"));
+        assert_eq!(
+            execute(&mut debugger, "x/5 0x4000000c"),
+            "0x4000000c: 0x0021a823 0x00000063 0xxxxxxxxx 0x00000008\n0x4000001c: 0xxxxxxxxx\n"
+        );
+        assert_eq!(
+            execute(&mut debugger, "x/1 0x50000000"),
+            "0x50000000: 0xxxxxxxxx\n"
+        );
+
+        let mut out = Vec::new();
+        assert!(!debugger.execute("quit", &mut out).unwrap());
+    }
+
+    #[test]
+    fn test_stepi_jump_to_itself() {
+        // j . never moves the PC, stepi still comes back after one pass through the FSM
+        let mut debugger = Debugger::new(core_with_program(1, &[0x0000006f]));
+        for instret in 1..=2 {
+            let location = execute(&mut debugger, "stepi");
+            assert!(location.contains("pc: 0x40000000"), "{}", location);
+            assert!(location.contains("state: Fetch1"), "{}", location);
+            assert!(
+                location.contains(&format!("instret: {}", instret)),
+                "{}",
+                location
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_u32() {
+        assert_eq!(parse_u32("0x4000_0000"), Some(0x40000000));
+        assert_eq!(parse_u32("16"), Some(16));
+        assert_eq!(parse_u32("0xZZ"), None);
+    }
+}
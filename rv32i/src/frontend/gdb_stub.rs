@@ -0,0 +1,530 @@
+use crate::backend::core::{Core, StopReason};
+use crate::backend::util::types::{Byte, Word};
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// GDB numbers x0-x31 as 0-31 and the pc as 32
+const PC_REGNUM: usize = 32;
+const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+/// The largest packet we take or send, an `m` reply has two hex digits per byte
+const PACKET_SIZE: usize = 0x1000;
+/// How many `run_until` checks, one after every cycle, to skip between looking for a ctrl-c
+const INTERRUPT_POLL_INTERVAL: u32 = 1024;
+const INTERRUPT: u8 = 0x03;
+
+/// A stream gdb can talk to us over, needs to support non-blocking reads to detect ctrl-c
+pub trait GdbConnection: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl GdbConnection for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+impl GdbConnection for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+fn target_xml() -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?>\
+         <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+         <target version=\"1.0\">\
+         <architecture>riscv:rv32</architecture>\
+         <feature name=\"org.gnu.gdb.riscv.cpu\">",
+    );
+    for (regnum, name) in REG_NAMES.iter().enumerate() {
+        xml.push_str(&format!(
+            "<reg name=\"{}\" bitsize=\"32\" type=\"int\" regnum=\"{}\"/>",
+            name, regnum
+        ));
+    }
+    xml.push_str(&format!(
+        "<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"{}\"/>",
+        PC_REGNUM
+    ));
+    xml.push_str("</feature></target>");
+    xml
+}
+
+fn checksum(data: &str) -> u8 {
+    data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte))
+}
+
+/// Registers go over the wire in target byte order, unknown bytes are sent as `xx`
+fn encode_word(word: Word) -> String {
+    (0..4)
+        .map(|i| {
+            word[i]
+                .map(|byte| format!("{:02x}", byte))
+                .unwrap_or("xx".to_string())
+        })
+        .collect()
+}
+
+/// The hex digits are sliced by byte, anything but ASCII is rejected first
+fn decode_word(hex: &str) -> Option<Word> {
+    if hex.len() != 8 || !hex.is_ascii() {
+        return None;
+    }
+    let mut word = Word::unknown();
+    for i in 0..4 {
+        let byte = &hex[i * 2..i * 2 + 2];
+        if !byte.eq_ignore_ascii_case("xx") {
+            word.set_byte(i, Byte::from(u8::from_str_radix(byte, 16).ok()?));
+        }
+    }
+    Some(word)
+}
+
+fn decode_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+/// Parses the `addr,len` part of `m`, `M`, `Z` and `z` packets
+fn parse_addr_len(args: &str) -> Option<(u32, usize)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((
+        u32::from_str_radix(addr, 16).ok()?,
+        usize::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+/// A GDB remote serial protocol server, exposing the core as a bare metal rv32 target.
+///
+/// Registers map to `RegFile` and `Pc`, memory to `MemCtl::backend_mem`.
+/// Breakpoints do not patch memory, they are checked against the PC after every instruction.
+pub struct GdbStub {
+    core: Core,
+    no_ack: bool,
+}
+
+impl GdbStub {
+    pub fn new(core: Core) -> Self {
+        GdbStub {
+            core,
+            no_ack: false,
+        }
+    }
+
+    /// Waits for gdb on `addr` and serves a single session.
+    /// `addr` is either `host:port` or `unix:<path>`.
+    pub fn listen(&mut self, addr: &str) -> io::Result<()> {
+        println!("Waiting for gdb on {}", addr);
+        if let Some(path) = addr.strip_prefix("unix:") {
+            let _ = std::fs::remove_file(path);
+            let (mut stream, _) = UnixListener::bind(path)?.accept()?;
+            self.serve(&mut stream)
+        } else {
+            let (mut stream, _) = TcpListener::bind(addr)?.accept()?;
+            self.serve(&mut stream)
+        }
+    }
+
+    /// Serves packets until gdb detaches, kills the target or closes the connection
+    pub fn serve(&mut self, conn: &mut impl GdbConnection) -> io::Result<()> {
+        self.no_ack = false;
+        while let Some(packet) = self.read_packet(conn)? {
+            match self.handle_packet(&packet, conn)? {
+                Some(reply) => self.write_packet(conn, &reply)?,
+                None => break,
+            }
+            if packet == "D" {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_byte(conn: &mut impl GdbConnection) -> io::Result<Option<u8>> {
+        let mut buf = [0u8];
+        loop {
+            match conn.read(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns `None` once the connection is closed
+    fn read_packet(&mut self, conn: &mut impl GdbConnection) -> io::Result<Option<String>> {
+        loop {
+            // skip acks and stray interrupts until the start of a packet
+            loop {
+                match Self::read_byte(conn)? {
+                    Some(b'$') => break,
+                    Some(_) => {}
+                    None => return Ok(None),
+                }
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match Self::read_byte(conn)? {
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                    None => return Ok(None),
+                }
+            }
+            let mut sum = [0u8; 2];
+            for byte in sum.iter_mut() {
+                match Self::read_byte(conn)? {
+                    Some(digit) => *byte = digit,
+                    None => return Ok(None),
+                }
+            }
+
+            let data = String::from_utf8_lossy(&data).to_string();
+            let sum_ok = std::str::from_utf8(&sum)
+                .ok()
+                .and_then(|sum| u8::from_str_radix(sum, 16).ok())
+                == Some(checksum(&data));
+            if !self.no_ack {
+                conn.write_all(if sum_ok { b"+" } else { b"-" })?;
+            }
+            if sum_ok || self.no_ack {
+                return Ok(Some(data));
+            }
+        }
+    }
+
+    fn write_packet(&mut self, conn: &mut impl GdbConnection, data: &str) -> io::Result<()> {
+        loop {
+            write!(conn, "${}#{:02x}", data, checksum(data))?;
+            conn.flush()?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match Self::read_byte(conn)? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// An exit through HTIF is reported with its code, the low byte of it as gdb only takes that
+    fn stop_reply(&self, reason: StopReason) -> String {
+        match reason {
+            StopReason::Ended => format!("W{:02x}", self.core.get_exit_code().unwrap_or(0) as u8),
+            StopReason::Breakpoint => "T05swbreak:;".to_string(),
+            StopReason::Watchpoint => "S05".to_string(),
+            StopReason::Interrupted => "S02".to_string(),
+        }
+    }
+
+    fn read_register(&self, regnum: usize) -> Option<Word> {
+        match regnum {
+            0..=31 => Some(self.core.get_register(regnum as u8)),
+            PC_REGNUM => Some(self.core.get_pc()),
+            _ => None,
+        }
+    }
+
+    fn write_register(&mut self, regnum: usize, value: Word) -> bool {
+        match regnum {
+            0..=31 => self.core.set_register(regnum as u8, value),
+            PC_REGNUM => self.core.set_pc(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn read_memory(&self, addr: u32, len: usize) -> String {
+        let mut reply = String::new();
+        for i in 0..len as u32 {
+            // stop at the first unknown byte, gdb accepts partial reads
+            match self.core.read_mem(Word::from(addr.wrapping_add(i)))[0] {
+                Some(byte) => reply.push_str(&format!("{:02x}", byte)),
+                None => break,
+            }
+        }
+        if reply.is_empty() && len != 0 {
+            "E14".to_string()
+        } else {
+            reply
+        }
+    }
+
+    fn cont(&mut self, conn: &mut impl GdbConnection) -> io::Result<String> {
+        self.core.sim_manager.resume();
+        conn.set_nonblocking(true)?;
+        let mut checks = 0u32;
+        let reason = self.core.run_until(|_| {
+            checks = checks.wrapping_add(1);
+            if !checks.is_multiple_of(INTERRUPT_POLL_INTERVAL) {
+                return false;
+            }
+            let mut buf = [0u8];
+            matches!(conn.read(&mut buf), Ok(1) if buf[0] == INTERRUPT)
+        });
+        conn.set_nonblocking(false)?;
+        Ok(self.stop_reply(reason))
+    }
+
+    /// Returns the reply to send, or `None` if the connection should be closed without replying
+    fn handle_packet(
+        &mut self,
+        packet: &str,
+        conn: &mut impl GdbConnection,
+    ) -> io::Result<Option<String>> {
+        let reply = if packet == "?" {
+            "S05".to_string()
+        } else if packet.starts_with("qSupported") {
+            format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;swbreak+;hwbreak+",
+                PACKET_SIZE
+            )
+        } else if packet == "QStartNoAckMode" {
+            // the OK still gets acked
+            self.write_packet(conn, "OK")?;
+            self.no_ack = true;
+            return self.read_packet(conn).and_then(|packet| match packet {
+                Some(packet) => self.handle_packet(&packet, conn),
+                None => Ok(None),
+            });
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            match parse_addr_len(args) {
+                Some((offset, len)) => {
+                    let xml = target_xml();
+                    let offset = (offset as usize).min(xml.len());
+                    let end = (offset + len).min(xml.len());
+                    let prefix = if end == xml.len() { "l" } else { "m" };
+                    format!("{}{}", prefix, &xml[offset..end])
+                }
+                None => "E01".to_string(),
+            }
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qfThreadInfo" {
+            "m1".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if packet.starts_with('H') {
+            "OK".to_string()
+        } else if packet == "g" {
+            (0..=PC_REGNUM)
+                .filter_map(|regnum| self.read_register(regnum))
+                .map(encode_word)
+                .collect()
+        } else if let Some(regs) = packet.strip_prefix('G') {
+            if regs.len() < (PC_REGNUM + 1) * 8 || !regs.is_ascii() {
+                "E01".to_string()
+            } else {
+                for regnum in 0..=PC_REGNUM {
+                    if let Some(value) = decode_word(&regs[regnum * 8..regnum * 8 + 8]) {
+                        self.write_register(regnum, value);
+                    }
+                }
+                "OK".to_string()
+            }
+        } else if let Some(regnum) = packet.strip_prefix('p') {
+            usize::from_str_radix(regnum, 16)
+                .ok()
+                .and_then(|regnum| self.read_register(regnum))
+                .map(encode_word)
+                .unwrap_or("E01".to_string())
+        } else if let Some(args) = packet.strip_prefix('P') {
+            let written = args.split_once('=').and_then(|(regnum, value)| {
+                let regnum = usize::from_str_radix(regnum, 16).ok()?;
+                let value = decode_word(value)?;
+                self.write_register(regnum, value).then_some(())
+            });
+            if written.is_some() { "OK" } else { "E01" }.to_string()
+        } else if let Some(args) = packet.strip_prefix('m') {
+            match parse_addr_len(args) {
+                Some((addr, len)) if len <= PACKET_SIZE / 2 => self.read_memory(addr, len),
+                _ => "E01".to_string(),
+            }
+        } else if let Some(args) = packet.strip_prefix('M') {
+            let written = args.split_once(':').and_then(|(addr_len, data)| {
+                let (addr, len) = parse_addr_len(addr_len)?;
+                let data = decode_bytes(data)?;
+                (data.len() == len).then(|| self.core.write_mem(Word::from(addr), &data))
+            });
+            if written.is_some() { "OK" } else { "E01" }.to_string()
+        } else if packet == "s" {
+            if self.core.can_end() {
+                self.stop_reply(StopReason::Ended)
+            } else {
+                self.core.run_instruction();
+                "S05".to_string()
+            }
+        } else if packet == "c" {
+            self.cont(conn)?
+        } else if let Some(args) = packet
+            .strip_prefix("Z0,")
+            .or_else(|| packet.strip_prefix("Z1,"))
+        {
+            match parse_addr_len(args) {
+                Some((addr, _)) => {
+                    self.core.add_breakpoint(addr);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        } else if let Some(args) = packet
+            .strip_prefix("z0,")
+            .or_else(|| packet.strip_prefix("z1,"))
+        {
+            match parse_addr_len(args) {
+                Some((addr, _)) => {
+                    self.core.remove_breakpoint(addr);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            }
+        } else if packet == "D" {
+            "OK".to_string()
+        } else if packet == "k" {
+            return Ok(None);
+        } else {
+            // empty reply means unsupported
+            String::new()
+        };
+
+        Ok(Some(reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::core::{core_with_program, TEST_PROGRAM};
+    use std::thread;
+
+    /// A scripted gdb, sends a packet and returns the reply
+    fn send(stream: &mut TcpStream, packet: &str) -> String {
+        write!(stream, "${}#{:02x}", packet, checksum(packet)).unwrap();
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'+');
+
+        stream.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'$');
+        let mut reply = Vec::new();
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            reply.push(byte[0]);
+        }
+        let mut sum = [0u8; 2];
+        stream.read_exact(&mut sum).unwrap();
+        let reply = String::from_utf8(reply).unwrap();
+        assert_eq!(
+            u8::from_str_radix(std::str::from_utf8(&sum).unwrap(), 16).unwrap(),
+            checksum(&reply)
+        );
+        stream.write_all(b"+").unwrap();
+        reply
+    }
+
+    #[test]
+    fn test_gdb_stub() {
+        let core = core_with_program(4, &TEST_PROGRAM);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stub = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            GdbStub::new(core).serve(&mut stream).unwrap();
+        });
+
+        let mut gdb = TcpStream::connect(addr).unwrap();
+        assert!(send(&mut gdb, "qSupported:swbreak+").contains("qXfer:features:read+"));
+        let xml = send(&mut gdb, "qXfer:features:read:target.xml:0,1000");
+        assert!(xml.starts_with("l<?xml"));
+        assert!(xml.contains("<architecture>riscv:rv32</architecture>"));
+        assert_eq!(send(&mut gdb, "?"), "S05");
+        assert_eq!(
+            send(&mut gdb, "g"),
+            format!("{}{}", "00000000".repeat(32), "00000040")
+        );
+        assert_eq!(send(&mut gdb, "m40000000,4"), "93005000");
+        assert_eq!(send(&mut gdb, "m50000000,4"), "E14");
+        // the reply would not fit in a packet
+        assert_eq!(send(&mut gdb, "m40000000,801"), "E01");
+        assert_eq!(send(&mut gdb, "m40000000,ffffffffffffffff"), "E01");
+
+        assert_eq!(send(&mut gdb, "Z0,40000008,4"), "OK");
+        assert_eq!(send(&mut gdb, "c"), "T05swbreak:;");
+        assert_eq!(send(&mut gdb, "p20"), "08000040");
+        assert_eq!(send(&mut gdb, "p2"), "08000000");
+
+        // rerun addi x2, x1, 3 with a different x1
+        assert_eq!(send(&mut gdb, "P1=64000000"), "OK");
+        assert_eq!(send(&mut gdb, "P20=04000040"), "OK");
+        assert_eq!(send(&mut gdb, "s"), "S05");
+        assert_eq!(send(&mut gdb, "p20"), "08000040");
+        assert_eq!(send(&mut gdb, "p2"), "67000000");
+
+        // hex fields are split by byte, a multi-byte character must not land on a boundary
+        assert_eq!(send(&mut gdb, "P1=0\u{e9}00000"), "E01");
+        assert_eq!(send(&mut gdb, "M40001000,4:0\u{e9}00000"), "E01");
+        assert_eq!(
+            send(&mut gdb, &format!("G0\u{e9}{}", "0".repeat(32 * 8 + 6))),
+            "E01"
+        );
+        assert_eq!(send(&mut gdb, "p2"), "67000000");
+
+        assert_eq!(send(&mut gdb, "M40001000,4:78563412"), "OK");
+        assert_eq!(send(&mut gdb, "m40001000,4"), "78563412");
+
+        assert_eq!(send(&mut gdb, "z0,40000008,4"), "OK");
+        assert_eq!(send(&mut gdb, "c"), "W00");
+        assert_eq!(send(&mut gdb, "m40000018,4"), "67000000");
+        assert_eq!(send(&mut gdb, "vMustReplyEmpty"), "");
+        assert_eq!(send(&mut gdb, "D"), "OK");
+
+        stub.join().unwrap();
+    }
+
+    #[test]
+    fn test_gdb_stub_jump_to_itself() {
+        // j . never moves the PC or ends, s and a ctrl-c during c still have to come back
+        let core = core_with_program(1, &[0x0000006f]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stub = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            GdbStub::new(core).serve(&mut stream).unwrap();
+        });
+
+        let mut gdb = TcpStream::connect(addr).unwrap();
+        assert_eq!(send(&mut gdb, "s"), "S05");
+        assert_eq!(send(&mut gdb, "p20"), "00000040");
+
+        write!(gdb, "$c#{:02x}", checksum("c")).unwrap();
+        let mut byte = [0u8];
+        gdb.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'+');
+        gdb.write_all(&[INTERRUPT]).unwrap();
+        let mut reply = [0u8; 7];
+        gdb.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"$S02#b5");
+        gdb.write_all(b"+").unwrap();
+
+        assert_eq!(send(&mut gdb, "p20"), "00000040");
+        assert_eq!(send(&mut gdb, "D"), "OK");
+        stub.join().unwrap();
+    }
+}
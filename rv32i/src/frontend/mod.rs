@@ -0,0 +1,4 @@
+pub mod cli;
+pub mod debugger;
+pub mod gdb_stub;
+pub mod syscall;
@@ -0,0 +1,292 @@
+use crate::backend::core::Core;
+use crate::backend::syscall::{errno, number, SyscallHandler, SyscallResult};
+use rsim_core::elf::Elf;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// newlib's open flags, the access mode is in the low 2 bits
+const O_WRONLY: u32 = 0x1;
+const O_ACCMODE: u32 = 0x3;
+const O_APPEND: u32 = 0x8;
+const O_CREAT: u32 = 0x200;
+const O_TRUNC: u32 = 0x400;
+const O_EXCL: u32 = 0x800;
+const S_IFCHR: u32 = 0o020000;
+const S_IFREG: u32 = 0o100000;
+/// The size of the kernel `struct stat` libgloss passes to fstat
+const STAT_SIZE: usize = 128;
+const PATH_MAX: u32 = 4096;
+/// Longer reads and writes are cut short, the program asks again for the rest
+const MAX_TRANSFER: u32 = 1 << 20;
+
+fn host_errno(err: io::Error) -> u32 {
+    err.raw_os_error().map_or(errno::EIO, |errno| errno as u32)
+}
+
+/// Serves the newlib syscalls from the host, like the proxy kernel does: fd 0 to 2 are the
+/// simulator's stdio and the rest are host files, paths are relative to the working directory.
+/// brk only moves the break as the backing memory is sparse anyway.
+pub struct HostSyscalls {
+    stdin: Box<dyn Read + Send>,
+    stdout: Box<dyn Write + Send>,
+    stderr: Box<dyn Write + Send>,
+    files: HashMap<u32, File>,
+    next_fd: u32,
+    heap_start: u32,
+    brk: u32,
+}
+
+impl HostSyscalls {
+    pub fn new(heap_start: u32) -> Self {
+        HostSyscalls {
+            stdin: Box::new(io::stdin()),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+            files: HashMap::new(),
+            next_fd: 3,
+            heap_start,
+            brk: heap_start,
+        }
+    }
+
+    /// The heap starts at `_end` like newlib expects, past the last segment without it
+    pub fn for_elf(elf: &Elf) -> Self {
+        let heap_start = elf
+            .symbols
+            .lookup("_end")
+            .or_else(|| elf.symbols.lookup("end"))
+            .unwrap_or_else(|| {
+                elf.segments
+                    .iter()
+                    .map(|segment| segment.addr.wrapping_add(segment.mem_size()))
+                    .max()
+                    .unwrap_or(0)
+            });
+        Self::new(heap_start)
+    }
+
+    fn write(&mut self, core: &Core, fd: u32, buf: u32, len: u32) -> Result<u32, u32> {
+        let data = core.read_bytes(buf, len.min(MAX_TRANSFER));
+        let written = match fd {
+            1 => self
+                .stdout
+                .write(&data)
+                .and_then(|n| self.stdout.flush().map(|_| n)),
+            2 => self.stderr.write(&data),
+            _ => self.files.get_mut(&fd).ok_or(errno::EBADF)?.write(&data),
+        };
+        written.map(|n| n as u32).map_err(host_errno)
+    }
+
+    fn read(&mut self, core: &mut Core, fd: u32, buf: u32, len: u32) -> Result<u32, u32> {
+        let mut data = vec![0; len.min(MAX_TRANSFER) as usize];
+        let read = match fd {
+            0 => self.stdin.read(&mut data),
+            _ => self.files.get_mut(&fd).ok_or(errno::EBADF)?.read(&mut data),
+        }
+        .map_err(host_errno)?;
+        core.write_mem(buf.into(), &data[..read]);
+        Ok(read as u32)
+    }
+
+    fn open(&mut self, core: &Core, path: u32, flags: u32) -> Result<u32, u32> {
+        let bytes = core.read_bytes(path, PATH_MAX);
+        let len = bytes.iter().position(|&b| b == 0).ok_or(errno::EINVAL)?;
+        let path = String::from_utf8_lossy(&bytes[..len]).into_owned();
+
+        let access = flags & O_ACCMODE;
+        let mut options = OpenOptions::new();
+        options
+            .read(access != O_WRONLY)
+            .write(access != 0)
+            .append(flags & O_APPEND != 0)
+            .truncate(flags & O_TRUNC != 0);
+        if flags & O_CREAT != 0 {
+            if flags & O_EXCL != 0 {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+        }
+        let file = options.open(path).map_err(host_errno)?;
+        let fd = self.next_fd;
+        self.next_fd += 1;
+        self.files.insert(fd, file);
+        Ok(fd)
+    }
+
+    /// stdio is never closed on the host
+    fn close(&mut self, fd: u32) -> Result<u32, u32> {
+        match fd {
+            0..=2 => Ok(0),
+            _ => self.files.remove(&fd).map(|_| 0).ok_or(errno::EBADF),
+        }
+    }
+
+    fn lseek(&mut self, fd: u32, offset: u32, whence: u32) -> Result<u32, u32> {
+        let offset = offset as i32 as i64;
+        let pos = match whence {
+            0 if offset >= 0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(errno::EINVAL),
+        };
+        match fd {
+            0..=2 => Err(errno::ESPIPE),
+            _ => self
+                .files
+                .get_mut(&fd)
+                .ok_or(errno::EBADF)?
+                .seek(pos)
+                .map(|pos| pos as u32)
+                .map_err(host_errno),
+        }
+    }
+
+    /// Only the mode, the size and the block size are filled in, newlib looks at little else
+    fn fstat(&mut self, core: &mut Core, fd: u32, buf: u32) -> Result<u32, u32> {
+        let (mode, size) = match fd {
+            0..=2 => (S_IFCHR | 0o620, 0),
+            _ => {
+                let metadata = self
+                    .files
+                    .get(&fd)
+                    .ok_or(errno::EBADF)?
+                    .metadata()
+                    .map_err(host_errno)?;
+                (S_IFREG | 0o644, metadata.len())
+            }
+        };
+        let mut stat = [0u8; STAT_SIZE];
+        stat[16..20].copy_from_slice(&mode.to_le_bytes());
+        stat[20..24].copy_from_slice(&1u32.to_le_bytes());
+        stat[48..56].copy_from_slice(&size.to_le_bytes());
+        stat[56..60].copy_from_slice(&4096u32.to_le_bytes());
+        core.write_mem(buf.into(), &stat);
+        Ok(0)
+    }
+
+    /// Asking for a break below the heap start returns the current one, like Linux
+    fn brk(&mut self, addr: u32) -> u32 {
+        if addr >= self.heap_start {
+            self.brk = addr;
+        }
+        self.brk
+    }
+}
+
+impl SyscallHandler for HostSyscalls {
+    fn syscall(&mut self, core: &mut Core, number: u32, args: [u32; 6]) -> SyscallResult {
+        let result = match number {
+            number::WRITE => self.write(core, args[0], args[1], args[2]),
+            number::READ => self.read(core, args[0], args[1], args[2]),
+            // the dirfd of openat is always AT_FDCWD in newlib
+            number::OPENAT => self.open(core, args[1], args[2]),
+            number::OPEN => self.open(core, args[0], args[1]),
+            number::CLOSE => self.close(args[0]),
+            number::LSEEK => self.lseek(args[0], args[1], args[2]),
+            number::FSTAT => self.fstat(core, args[0], args[1]),
+            number::BRK => Ok(self.brk(args[0])),
+            number::EXIT | number::EXIT_GROUP => return SyscallResult::Exit(args[0]),
+            _ => Err(errno::ENOSYS),
+        };
+        SyscallResult::Return(result.unwrap_or_else(|errno| errno.wrapping_neg()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::core::{core_with_program, StopReason};
+    use crate::backend::util::types::Word;
+
+    #[test]
+    fn test_host_syscalls() {
+        // unique per process, tests of several checkouts can run at once
+        let temp_path = |name: &str| {
+            std::env::temp_dir().join(format!(
+                "rsim_test_host_syscalls_{}_{}.txt",
+                std::process::id(),
+                name
+            ))
+        };
+        let stdout_path = temp_path("stdout");
+        let file_path = temp_path("file");
+        let program: [u32; 42] = [
+            0x400014b7, // lui s1, 0x40001
+            0x00100513, // li a0, 1
+            0x00048593, // mv a1, s1
+            0x00600613, // li a2, 6
+            0x04000893, // li a7, 64
+            0x00000073, // ecall
+            0x00050913, // mv s2, a0
+            0x10048513, // addi a0, s1, 0x100
+            0x60100593, // li a1, 0x601
+            0x40000893, // li a7, 1024
+            0x00000073, // ecall
+            0x00050993, // mv s3, a0
+            0x00048593, // mv a1, s1
+            0x00600613, // li a2, 6
+            0x04000893, // li a7, 64
+            0x00000073, // ecall
+            0x00098513, // mv a0, s3
+            0x03900893, // li a7, 57
+            0x00000073, // ecall
+            0xf9c00513, // li a0, -100
+            0x10048593, // addi a1, s1, 0x100
+            0x00000613, // li a2, 0
+            0x03800893, // li a7, 56
+            0x00000073, // ecall
+            0x00050a13, // mv s4, a0
+            0x20048593, // addi a1, s1, 0x200
+            0x01000613, // li a2, 16
+            0x03f00893, // li a7, 63
+            0x00000073, // ecall
+            0x00050a93, // mv s5, a0
+            0x00000513, // li a0, 0
+            0x0d600893, // li a7, 214
+            0x00000073, // ecall
+            0x00050b13, // mv s6, a0
+            0x3e700893, // li a7, 999
+            0x00000073, // ecall
+            0x00050b93, // mv s7, a0
+            0x00700513, // li a0, 7
+            0x05d00893, // li a7, 93
+            0x00000073, // ecall
+            0x00100c13, // li s8, 1
+            0x00000063, // beq x0, x0, 0
+        ];
+        let mut core = core_with_program(1, &program);
+        core.load_bin(b"hello\n", Word::from(0x40001000u32));
+        let mut path = file_path.to_str().unwrap().as_bytes().to_vec();
+        path.push(0);
+        core.load_bin(&path, Word::from(0x40001100u32));
+        core.set_syscall_handler(HostSyscalls {
+            stdout: Box::new(File::create(&stdout_path).unwrap()),
+            ..HostSyscalls::new(0x40002000)
+        });
+
+        assert_eq!(core.run_end(), StopReason::Ended);
+        assert_eq!(core.get_exit_code(), Some(7));
+        let stdout = std::fs::read(&stdout_path).unwrap();
+        let file = std::fs::read(&file_path).unwrap();
+        std::fs::remove_file(&stdout_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(stdout, b"hello\n");
+        assert_eq!(file, b"hello\n");
+        assert_eq!(core.get_register(18), Word::from(6u32));
+        assert_eq!(core.get_register(19), Word::from(3u32));
+        assert_eq!(core.get_register(20), Word::from(4u32));
+        assert_eq!(core.get_register(21), Word::from(6u32));
+        assert_eq!(core.read_bytes(0x40001200, 6), b"hello\n");
+        assert_eq!(core.get_register(22), Word::from(0x40002000u32));
+        assert_eq!(
+            core.get_register(23),
+            Word::from(errno::ENOSYS.wrapping_neg())
+        );
+        // nothing runs after exit
+        assert_eq!(core.get_register(24), Word::zeros());
+    }
+}
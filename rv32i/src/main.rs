@@ -1,32 +1,208 @@
-use crate::backend::core::Core;
+use crate::backend::component::fpu::FpuLatency;
+use crate::backend::core::{Core, StopReason};
+use crate::backend::plugins;
 use crate::backend::util::types::Word;
+use crate::frontend::cli;
+use crate::frontend::cli::{Options, EXIT_LIMIT, EXIT_USAGE, USAGE};
+use crate::frontend::debugger::Debugger;
+use crate::frontend::gdb_stub::GdbStub;
+use crate::frontend::syscall::HostSyscalls;
+use rsim_core::elf::{Elf, ElfError};
+use rsim_core::hooks::HookPoint;
 use std::fs;
 use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::process::exit;
 use std::time::SystemTime;
 
 mod backend;
+mod frontend;
+
+fn usage_error(message: String) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    exit(EXIT_USAGE);
+}
+
+/// The unit latencies come from the env, in cycles. A mul and a div take 1 and 32 when not set,
+/// the FPU ones default to `FpuLatency`
+fn new_core(threads: usize) -> Core {
+    let mut core = Core::new(threads, None);
+    let latency = |var: &str, default: u64| {
+        std::env::var(var)
+            .map(|latency| {
+                latency
+                    .parse()
+                    .unwrap_or_else(|_| usage_error(format!("{} is not a number", var)))
+            })
+            .unwrap_or(default)
+    };
+    core.set_muldiv_latency(latency("MUL_LATENCY", 1), latency("DIV_LATENCY", 32));
+    let fpu_latency = FpuLatency::default();
+    core.set_fpu_latency(FpuLatency {
+        add: latency("FPU_ADD_LATENCY", fpu_latency.add),
+        mul: latency("FPU_MUL_LATENCY", fpu_latency.mul),
+        fma: latency("FPU_FMA_LATENCY", fpu_latency.fma),
+        div: latency("FPU_DIV_LATENCY", fpu_latency.div),
+        sqrt: latency("FPU_SQRT_LATENCY", fpu_latency.sqrt),
+    });
+    core
+}
+
+/// ELF files are loaded where their segments ask, flat images at the load address.
+/// With `--syscalls` ECALLs are served from the host, the heap starts past the program
+fn load_program(core: &mut Core, data: &[u8], options: &Options) -> Result<(), ElfError> {
+    let host = if Elf::is_elf(data) {
+        let elf = Elf::parse(data)?;
+        core.load_elf(&elf);
+        HostSyscalls::for_elf(&elf)
+    } else {
+        core.load_bin(data, Word::from(options.load_addr));
+        core.set_pc(Word::from(options.load_addr));
+        HostSyscalls::new(options.load_addr.wrapping_add(data.len() as u32))
+    };
+    if let Some(reset_pc) = options.reset_pc {
+        core.set_pc(Word::from(reset_pc));
+    }
+    if options.syscalls {
+        core.set_syscall_handler(host);
+    }
+    Ok(())
+}
 
 fn main() {
-    let commit_file = std::env::var("LOG_FILE")
-        .map(|log_file| File::create(log_file).ok())
-        .ok()
-        .unwrap_or(None);
-    let mut core = Core::new(4, commit_file);
-
-    let data = fs::read(
-        std::env::var("BIN_FILE").expect("Provide the path to the binary file in BIN_FILE env var"),
-    )
-    .unwrap();
-    core.load_bin(&data, Word::from(0x40000000u32));
+    let options = cli::parse_args(std::env::args().skip(1)).unwrap_or_else(|err| usage_error(err));
+    if options.help {
+        print!("{}", USAGE);
+        return;
+    }
+    let Some(program) = options
+        .program
+        .clone()
+        .or_else(|| std::env::var("BIN_FILE").ok())
+    else {
+        usage_error("no program given".to_string());
+    };
+    let data = fs::read(&program)
+        .unwrap_or_else(|err| usage_error(format!("cannot read {}: {}", program, err)));
+
+    let mut core = new_core(options.threads);
+    if let Some(commit_log) = options
+        .commit_log
+        .clone()
+        .or_else(|| std::env::var("LOG_FILE").ok())
+    {
+        let commit_file = File::create(&commit_log)
+            .unwrap_or_else(|err| usage_error(format!("cannot create {}: {}", commit_log, err)));
+        core.get_hooks().add(
+            HookPoint::CycleEnd,
+            plugins::commit_logger(commit_file, options.commit_format),
+        );
+    }
+    load_program(&mut core, &data, &options)
+        .unwrap_or_else(|err| usage_error(format!("cannot load {}: {}", program, err)));
+
+    if options.check_determinism {
+        // Same program on a single dispatcher thread, the event streams should hash the same
+        let mut reference = new_core(1);
+        load_program(&mut reference, &data, &options).unwrap();
+        reference.sim_manager.enable_trace_hash();
+        core.sim_manager.enable_trace_hash();
+        reference.run_end();
+        core.run_end();
+        let expected = reference.sim_manager.get_trace_hash().unwrap();
+        let actual = core.sim_manager.get_trace_hash().unwrap();
+        println!(
+            "trace hash: 1 thread {:016x}, {} threads {:016x}",
+            expected, options.threads, actual
+        );
+        if expected != actual {
+            eprintln!("event streams differ between thread counts");
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(gdb_addr) = &options.gdb {
+        GdbStub::new(core).listen(gdb_addr).unwrap();
+        return;
+    }
+
+    if options.debug {
+        Debugger::new(core)
+            .run(io::stdin().lock(), &mut io::stdout())
+            .unwrap();
+        return;
+    }
 
     let start = SystemTime::now();
-    core.run_end();
+    core.sim_manager.get_stats().restart_wall_clock();
+    // checked after every cycle, a jump to itself never retires anything
+    let limit_reached = |core: &Core| {
+        options
+            .max_cycles
+            .is_some_and(|max_cycles| core.sim_manager.get_curr_cycle() >= max_cycles)
+            || options
+                .max_instructions
+                .is_some_and(|max_instructions| core.get_instructions_ran() >= max_instructions)
+    };
+    // a limit of 0 stops before the first instruction
+    let reason = if limit_reached(&core) {
+        StopReason::Interrupted
+    } else {
+        core.run_until(limit_reached)
+    };
+    io::stdout().write_all(&core.take_console_output()).unwrap();
     let processing_time = start.elapsed().unwrap().as_secs_f64();
     let event_processed = core.sim_manager.get_event_processed();
-    println!(
-        "Finished processing {} events in {} seconds @ {} events/second",
-        event_processed,
-        processing_time,
-        event_processed as f64 / processing_time
-    );
+    if !options.quiet {
+        println!(
+            "Finished processing {} events in {} seconds @ {} events/second",
+            event_processed,
+            processing_time,
+            event_processed as f64 / processing_time
+        );
+    }
+
+    // without --stats, the report goes where STATS_JSON and STATS_CSV say
+    let stats: Vec<(String, bool)> = match &options.stats {
+        Some(stats) => vec![(stats.clone(), stats.ends_with(".csv"))],
+        None => [("STATS_JSON", false), ("STATS_CSV", true)]
+            .into_iter()
+            .filter_map(|(var, csv)| std::env::var(var).ok().map(|path| (path, csv)))
+            .collect(),
+    };
+    if !stats.is_empty() {
+        let report = core.sim_manager.stats_report();
+        for (path, csv) in stats {
+            let report = if csv {
+                report.to_csv()
+            } else {
+                report.to_json()
+            };
+            fs::write(path, report).unwrap();
+        }
+    }
+
+    // riscv-tests and riscv-arch-test report pass or fail through HTIF, newlib programs with exit
+    let status = match (reason, core.get_exit_code()) {
+        (StopReason::Ended, Some(exit_code)) => {
+            if exit_code != 0 && !options.quiet {
+                eprintln!("program exited with code {}", exit_code);
+            }
+            cli::exit_status(exit_code)
+        }
+        (StopReason::Ended, None) => 0,
+        _ => {
+            if !options.quiet {
+                eprintln!(
+                    "stopped after {} cycles and {} instructions",
+                    core.sim_manager.get_curr_cycle(),
+                    core.get_instructions_ran()
+                );
+            }
+            EXIT_LIMIT
+        }
+    };
+    exit(status);
 }
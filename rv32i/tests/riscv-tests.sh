@@ -0,0 +1,21 @@
+#!/bin/bash
+# Runs riscv-tests binaries through HTIF and reports PASS or FAIL with the failing TESTNUM.
+# usage: riscv-tests.sh <riscv-tests/isa dir> [test...], the rv32mi exception tests by default
+isa=${1:?usage: $0 <riscv-tests/isa dir> [test...]}
+shift
+tests=${@:-rv32mi-p-ma_fetch rv32mi-p-illegal rv32mi-p-scall rv32mi-p-sbreak rv32mi-p-csr}
+
+cargo build --release -q -p rsim_rv32i || exit 1
+sim=$(dirname "$0")/../../target/release/rsim_rv32i
+failed=0
+for test in $tests; do
+  "$sim" --quiet --max-cycles 1000000 "$isa/$test"
+  status=$?
+  if [ $status -eq 0 ]; then
+    echo "PASS $test"
+  else
+    echo "FAIL $test (exit $status)"
+    failed=1
+  fi
+done
+exit $failed
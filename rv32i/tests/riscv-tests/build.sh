@@ -0,0 +1,10 @@
+#!/bin/sh
+# Rebuilds the rv32mi and rv32um test ELFs from their sources. llvm-mc assembles each test into one
+# section without relocations and mkelf.py places it at 0x80000000 like riscv-tests' link.ld
+set -e
+cd "$(dirname "$0")"
+for test in rv32mi-p-*.s rv32um-p-*.s; do
+    llvm-mc -triple=riscv32 -mattr=+m,+a,+f,+c,-relax -filetype=obj "$test" -o "${test%.s}.o"
+    python3 mkelf.py "${test%.s}.o" "${test%.s}"
+    rm "${test%.s}.o"
+done
@@ -0,0 +1,76 @@
+#!/usr/bin/env python3
+"""Turns the relocatable object llvm-mc made of a test into the executable a linker would have:
+the .text section loaded at 0x80000000 and its symbols moved along with it.
+
+usage: mkelf.py <test.o> <test>
+"""
+
+import struct
+import sys
+
+LOAD_ADDR = 0x80000000
+CODE_OFFSET = 0x100
+
+
+def sections(obj):
+    shoff, = struct.unpack_from("<I", obj, 32)
+    shentsize, shnum, shstrndx = struct.unpack_from("<HHH", obj, 46)
+    headers = [struct.unpack_from("<10I", obj, shoff + i * shentsize) for i in range(shnum)]
+    names = headers[shstrndx]
+    for index, header in enumerate(headers):
+        end = obj.index(b"\0", names[4] + header[0])
+        yield index, obj[names[4] + header[0]:end].decode(), header
+
+
+def main(src, dst):
+    obj = open(src, "rb").read()
+    found = {name: (index, header) for index, name, header in sections(obj)}
+    if any(name.startswith(".rel") for name in found):
+        sys.exit(f"{src} has relocations, there is no linker to resolve them")
+
+    text_index, text = found[".text"]
+    code = obj[text[4]:text[4] + text[5]]
+    _, symtab = found[".symtab"]
+    strtab = obj[found[".strtab"][1][4]:][:found[".strtab"][1][5]]
+
+    # The symbols in .text, the constants and the null symbol are left out
+    names = b"\0"
+    symbols = bytes(16)
+    entry = None
+    for offset in range(symtab[4], symtab[4] + symtab[5], 16):
+        name, value, size, info, other, shndx = struct.unpack_from("<IIIBBH", obj, offset)
+        if shndx != text_index:
+            continue
+        raw = strtab[name:strtab.index(b"\0", name)]
+        if raw == b"_start":
+            entry = LOAD_ADDR + value
+        symbols += struct.pack("<IIIBBH", len(names), LOAD_ADDR + value, size, 0x10 | (info & 0xF), other, 1)
+        names += raw + b"\0"
+
+    shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0"
+    file = bytearray(CODE_OFFSET)
+    file += code
+    file += bytes(-len(file) % 4)
+    symtab_offset = len(file)
+    file += symbols
+    strtab_offset = len(file)
+    file += names
+    shstrtab_offset = len(file)
+    file += shstrtab
+    file += bytes(-len(file) % 4)
+    shdrs_offset = len(file)
+    file += bytes(40)
+    file += struct.pack("<10I", 1, 1, 7, LOAD_ADDR, CODE_OFFSET, len(code), 0, 0, 4, 0)
+    file += struct.pack("<10I", 7, 2, 0, 0, symtab_offset, len(symbols), 3, 1, 4, 16)
+    file += struct.pack("<10I", 15, 3, 0, 0, strtab_offset, len(names), 0, 0, 1, 0)
+    file += struct.pack("<10I", 23, 3, 0, 0, shstrtab_offset, len(shstrtab), 0, 0, 1, 0)
+
+    # e_flags: EF_RISCV_RVC
+    struct.pack_into("<16sHHIIIIIHHHHHH", file, 0, b"\x7fELF\x01\x01\x01" + bytes(9), 2, 243, 1, entry,
+                     52, shdrs_offset, 1, 52, 32, 1, 40, 5, 4)
+    struct.pack_into("<8I", file, 52, 1, CODE_OFFSET, LOAD_ADDR, LOAD_ADDR, len(code), len(code), 7, CODE_OFFSET)
+    open(dst, "wb").write(file)
+
+
+if __name__ == "__main__":
+    main(*sys.argv[1:])
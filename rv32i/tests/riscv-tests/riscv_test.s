@@ -0,0 +1,158 @@
+# riscv-test-env's "p" environment for llvm-mc, which has no linker to go with it: the whole
+# test is one .text section with only local symbols, so there is nothing to relocate, and
+# mkelf.py turns it into an executable at 0x80000000, see build.sh.
+#
+# Differences with riscv_test.h:
+# - every test defines mtvec_handler, a weak undefined one would need a linker
+# - no stvec_handler delegation, the core only has M-mode
+
+.option norelax
+
+.equ MSTATUS_MIE, 0x00000008
+.equ MSTATUS_MPP, 0x00001800
+.equ MSTATUS_FS, 0x00006000
+.equ MIP_SSIP, 0x00000002
+
+.equ CAUSE_MISALIGNED_FETCH, 0x0
+.equ CAUSE_ILLEGAL_INSTRUCTION, 0x2
+.equ CAUSE_BREAKPOINT, 0x3
+.equ CAUSE_USER_ECALL, 0x8
+.equ CAUSE_SUPERVISOR_ECALL, 0x9
+.equ CAUSE_MACHINE_ECALL, 0xb
+
+.equ PRV_S, 1
+.equ PMP_R, 0x01
+.equ PMP_W, 0x02
+.equ PMP_X, 0x04
+.equ PMP_NAPOT, 0x18
+
+# TESTNUM is gp, there is no preprocessor to name it
+
+.macro RVTEST_PASS
+        fence
+        li gp, 1
+        li a7, 93
+        li a0, 0
+        ecall
+.endm
+
+.macro RVTEST_FAIL
+        fence
+1:      beqz gp, 1b
+        sll gp, gp, 1
+        ori gp, gp, 1
+        li a7, 93
+        addi a0, gp, 0
+        ecall
+.endm
+
+.macro TEST_PASSFAIL
+        bne x0, gp, pass
+fail:
+        RVTEST_FAIL
+pass:
+        RVTEST_PASS
+.endm
+
+.macro init
+        li a0, MSTATUS_MPP
+        csrs mstatus, a0
+.endm
+
+.macro RVTEST_CODE_BEGIN
+        .text
+        .align 6
+_start:
+        j reset_vector
+        .align 2
+trap_vector:
+        # test whether the test came from pass/fail
+        csrr t5, mcause
+        li t6, CAUSE_USER_ECALL
+        beq t5, t6, write_tohost
+        li t6, CAUSE_SUPERVISOR_ECALL
+        beq t5, t6, write_tohost
+        li t6, CAUSE_MACHINE_ECALL
+        beq t5, t6, write_tohost
+        # jump to the test's mtvec_handler
+        la t5, mtvec_handler
+        beqz t5, 1f
+        jr t5
+        # was it an interrupt or an exception?
+1:      csrr t5, mcause
+        bgez t5, handle_exception
+        j other_exception
+handle_exception:
+        # we don't know how to handle whatever the exception was
+other_exception:
+        # some unhandlable exception occurred
+1:      ori gp, gp, 1337
+write_tohost:
+        sw gp, tohost, t5
+        sw zero, tohost + 4, t5
+        j write_tohost
+reset_vector:
+        .irp reg, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31
+        li x\reg, 0
+        .endr
+        # RISCV_MULTICORE_DISABLE
+        csrr a0, mhartid
+1:      bnez a0, 1b
+        # INIT_SATP, skipped with a trap when there is no satp
+        la t0, 1f
+        csrw mtvec, t0
+        csrwi satp, 0
+        .align 2
+1:
+        # INIT_PMP
+        la t0, 1f
+        csrw mtvec, t0
+        li t0, (1 << 31) - 1
+        csrw pmpaddr0, t0
+        li t0, PMP_NAPOT | PMP_R | PMP_W | PMP_X
+        csrw pmpcfg0, t0
+        .align 2
+1:
+        # DELEGATE_NO_TRAPS
+        csrwi mie, 0
+        la t0, 1f
+        csrw mtvec, t0
+        csrwi medeleg, 0
+        csrwi mideleg, 0
+        .align 2
+1:
+        li gp, 0
+        la t0, trap_vector
+        csrw mtvec, t0
+        # CHECK_XLEN
+        li a0, 1
+        slli a0, a0, 31
+        bltz a0, 1f
+        RVTEST_PASS
+1:
+        csrwi mstatus, 0
+        init
+        la t0, 1f
+        csrw mepc, t0
+        csrr a0, mhartid
+        mret
+1:
+.endm
+
+.macro RVTEST_CODE_END
+        unimp
+.endm
+
+.macro RVTEST_DATA_BEGIN
+        .align 6
+tohost: .dword 0
+        .align 6
+fromhost: .dword 0
+        .align 4
+begin_signature:
+.endm
+
+.macro RVTEST_DATA_END
+        .align 4
+end_signature:
+.endm
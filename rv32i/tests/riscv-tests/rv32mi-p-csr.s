@@ -0,0 +1,118 @@
+# rv32mi csr: reads, writes, sets and clears of mscratch, then FP stores with mstatus.FS off.
+# There is no U-mode, the user-level part of the upstream test is skipped as it is there
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        li gp, 20
+        csrw mscratch, zero
+        csrr a0, mscratch
+        TEST_CHECK a0, 0
+        li gp, 21
+        csrrwi a0, mscratch, 0
+        csrrwi a0, mscratch, 0xF
+        TEST_CHECK a0, 0
+        li gp, 22
+        csrrsi x0, mscratch, 0x10
+        csrr a0, mscratch
+        TEST_CHECK a0, 0x1f
+
+        csrwi mscratch, 3
+        li gp, 2
+        csrr a0, mscratch
+        TEST_CHECK a0, 3
+        li gp, 3
+        csrrci a1, mscratch, 1
+        TEST_CHECK a1, 3
+        li gp, 4
+        csrrsi a2, mscratch, 4
+        TEST_CHECK a2, 2
+        li gp, 5
+        csrrwi a3, mscratch, 2
+        TEST_CHECK a3, 6
+        li gp, 6
+        li a0, 0xbad1dea
+        csrrw a1, mscratch, a0
+        TEST_CHECK a1, 2
+        li gp, 7
+        li a0, 0x0001dea
+        csrrc a1, mscratch, a0
+        TEST_CHECK a1, 0xbad1dea
+        li gp, 8
+        li a0, 0x000beef
+        csrrs a1, mscratch, a0
+        TEST_CHECK a1, 0xbad0000
+        li gp, 9
+        li a0, 0xbad1dea
+        csrrw a0, mscratch, a0
+        TEST_CHECK a0, 0xbadbeef
+        li gp, 10
+        li a0, 0x0001dea
+        csrrc a0, mscratch, a0
+        TEST_CHECK a0, 0xbad1dea
+        li gp, 11
+        li a0, 0x000beef
+        csrrs a0, mscratch, a0
+        TEST_CHECK a0, 0xbad0000
+        li gp, 12
+        csrr a0, mscratch
+        TEST_CHECK a0, 0xbadbeef
+
+        # Is F extension present?
+        csrr a0, misa
+        andi a0, a0, (1 << ('F' - 'A'))
+        beqz a0, 1f
+        # If so, make sure FP stores have no effect when mstatus.FS is off.
+        li a1, MSTATUS_FS
+        csrs mstatus, a1
+        fmv.s.x f0, x0
+        csrc mstatus, a1
+        la a1, fsw_data
+        li gp, 13
+        fsw f0, (a1)
+        lw a0, (a1)
+        TEST_CHECK a0, 1
+1:
+
+        # Figure out if 'U' is set in misa
+        csrr a0, misa
+        srli a0, a0, 20
+        andi a0, a0, 1
+        beqz a0, finish
+        j fail
+
+finish:
+        RVTEST_PASS
+
+        # We should only fall through to this if scall failed.
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        # Trapping on tests 13-15 is good news.
+        # Note that since the test didn't complete, TESTNUM is smaller by 1.
+        li t0, 12
+        bltu gp, t0, 1f
+        li t0, 14
+        bleu gp, t0, privileged
+1:
+        j fail
+
+privileged:
+        # Make sure mcause indicates a lack of privilege.
+        csrr t0, mcause
+        li t1, CAUSE_ILLEGAL_INSTRUCTION
+        bne t0, t1, fail
+        # Skip the trapping instruction.
+        csrr t0, mepc
+        addi t0, t0, 4
+        csrw mepc, t0
+        mret
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+fsw_data: .word 1
+RVTEST_DATA_END
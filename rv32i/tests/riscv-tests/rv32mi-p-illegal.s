@@ -0,0 +1,59 @@
+# rv32mi illegal: an all-zero word is an illegal instruction, mtval holds 0 or the instruction.
+# The rest of the upstream test needs S-mode and is skipped the same way it is there
+
+.include "riscv_test.s"
+
+RVTEST_CODE_BEGIN
+
+        .align 2
+        .option norvc
+
+        li gp, 2
+bad2:
+        .word 0
+        j fail
+
+        # Skip the rest of the test if S-mode is not present.
+        li t0, MSTATUS_MPP
+        csrc mstatus, t0
+        li t1, (MSTATUS_MPP & -MSTATUS_MPP) * PRV_S
+        csrs mstatus, t1
+        csrr t2, mstatus
+        and t2, t2, t0
+        bne t1, t2, pass
+        j fail
+
+        TEST_PASSFAIL
+
+        .align 8
+mtvec_handler:
+        j synchronous_exception
+        j fail
+
+synchronous_exception:
+        li t1, CAUSE_ILLEGAL_INSTRUCTION
+        csrr t0, mcause
+        bne t0, t1, fail
+        csrr t0, mepc
+
+        # Make sure mtval contains either 0 or the instruction word.
+        csrr t2, mtval
+        beqz t2, 1f
+        lhu t1, 0(t0)
+        xor t2, t2, t1
+        lhu t1, 2(t0)
+        slli t1, t1, 16
+        xor t2, t2, t1
+        bnez t2, fail
+1:
+        la t1, bad2
+        bne t0, t1, fail
+
+        addi t0, t0, 8
+        csrw mepc, t0
+        mret
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
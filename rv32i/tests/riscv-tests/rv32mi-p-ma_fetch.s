@@ -0,0 +1,172 @@
+# rv32mi ma_fetch: jumps to a 2-byte aligned target trap as misaligned fetches without C, and
+# go through with it. The core has C, so none of them trap, and misa is not writable so the
+# test of turning C off is skipped the way upstream skips it
+
+.include "riscv_test.s"
+
+RVTEST_CODE_BEGIN
+
+        .align 2
+        .option norvc
+
+        # Without RVC, the jalr should trap, and the handler will skip ahead.
+        # With RVC, the jalr should not trap, and "j fail" should get skipped.
+        li gp, 2
+        li t1, 0
+        la t0, 1f
+        jalr t1, t0, 2
+1:
+        .option rvc
+        c.j 1f
+        c.j 2f
+        .option norvc
+1:
+        j fail
+2:
+
+        # This test should pass, since JALR ignores the target LSB
+        li gp, 3
+        la t0, 1f
+        jalr t1, t0, 1
+1:
+        j 1f
+        j fail
+1:
+
+        li gp, 4
+        li t1, 0
+        la t0, 1f
+        jalr t1, t0, 3
+1:
+        .option rvc
+        c.j 1f
+        c.j 2f
+        .option norvc
+1:
+        j fail
+2:
+
+        # Like test 2, but with jal instead of jalr.
+        li gp, 5
+        li t1, 0
+        la t0, 1f
+        jal t1, 2f
+1:
+        .option rvc
+        c.j 1f
+2:
+        c.j 2f
+        .option norvc
+1:
+        j fail
+2:
+
+        # Like test 2, but with a taken branch instead of jalr.
+        li gp, 6
+        li t1, 0
+        la t0, 1f
+        beqz x0, 2f
+1:
+        .option rvc
+        c.j 1f
+2:
+        c.j 2f
+        .option norvc
+1:
+        j fail
+2:
+
+        # Not-taken branches should not trap, even without RVC.
+        li gp, 7
+        bnez x0, 1f
+        j 2f
+        .option rvc
+        c.j 1f
+1:
+        c.j 1f
+        .option norvc
+1:
+        j fail
+2:
+
+        # RVC cannot be disabled if doing so would cause a misaligned instruction exception on
+        # the next instruction fetch.
+        li gp, 8
+        csrr t2, misa
+        andi t2, t2, 1 << ('c' - 'a')
+        beqz t2, 2f
+
+        .option rvc
+        c.nop
+        csrci misa, 1 << ('c' - 'a')
+1:
+        c.nop
+        .option norvc
+
+        csrr t2, misa
+        andi t2, t2, 1 << ('c' - 'a')
+        beqz t2, fail
+
+        # When RVC is disabled, mret to a misaligned mepc should succeed, masking off mepc[1].
+        la t0, 1f
+        addi t0, t0, -2
+        csrw mepc, t0
+
+        # Try to disable RVC; if it can't be disabled, skip the test.
+        csrci misa, 1 << ('c' - 'a')
+        csrr t2, misa
+        andi t2, t2, 1 << ('c' - 'a')
+        bnez t2, 2f
+
+        li t2, MSTATUS_MPP
+        csrs mstatus, t2
+        mret
+
+        # mret should transfer control to this branch, since mepc[1] = 0
+        j 2f
+1:
+        j fail
+2:
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        # tests 2, 4, 5 and 6 should trap
+        li a0, 2
+        beq gp, a0, 1f
+        li a0, 4
+        beq gp, a0, 1f
+        li a0, 5
+        beq gp, a0, 1f
+        li a0, 6
+        beq gp, a0, 1f
+        j fail
+1:
+        # verify that return address was not written
+        bnez t1, fail
+
+        # verify trap cause
+        li a1, CAUSE_MISALIGNED_FETCH
+        csrr a0, mcause
+        bne a0, a1, fail
+
+        # verify that epc == &jalr (== t0 - 4)
+        csrr a1, mepc
+        addi a1, a1, 4
+        bne t0, a1, fail
+
+        # verify that mtval == 0 or mtval == t0+2.
+        csrr a0, mtval
+        beqz a0, 1f
+        addi a0, a0, -2
+        bne a0, t0, fail
+1:
+        addi a1, a1, 12
+        csrw mepc, a1
+        mret
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
@@ -0,0 +1,28 @@
+# rv32mi sbreak: EBREAK traps with a breakpoint cause and the address of the EBREAK in mepc
+
+.include "riscv_test.s"
+
+RVTEST_CODE_BEGIN
+
+        li gp, 2
+
+do_break:
+        ebreak
+        j fail
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        li t1, CAUSE_BREAKPOINT
+        csrr t0, mcause
+        bne t0, t1, fail
+        la t1, do_break
+        csrr t0, mepc
+        bne t0, t1, fail
+        j pass
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
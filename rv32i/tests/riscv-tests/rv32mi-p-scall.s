@@ -0,0 +1,51 @@
+# rv32mi scall: ECALL traps with the cause of the mode it came from, M here as there is no U-mode
+
+.include "riscv_test.s"
+
+RVTEST_CODE_BEGIN
+
+        li gp, 2
+
+        # This is the expected trap code.
+        li t1, CAUSE_USER_ECALL
+
+        # If running in M mode, use mstatus.MPP to check existence of U mode.
+        li t0, MSTATUS_MPP
+        csrc mstatus, t0
+        csrr t2, mstatus
+        and t0, t0, t2
+        beqz t0, 1f
+
+        # If U mode doesn't exist, mcause should indicate ECALL from M mode.
+        li t1, CAUSE_MACHINE_ECALL
+
+1:
+        li t0, MSTATUS_MPP
+        csrc mstatus, t0
+        la t0, 1f
+        csrw mepc, t0
+        mret
+1:
+
+        li gp, 1
+do_scall:
+        ecall
+        j fail
+
+        TEST_PASSFAIL
+
+        # The ECALL is caught by trap_vector, which passes with TESTNUM 1. Whatever gets here
+        # has to check mcause and TESTNUM the same way
+        .align 2
+mtvec_handler:
+        csrr t0, mcause
+        bne t0, t1, fail
+        la t2, do_scall
+        csrr t0, mepc
+        bne t0, t2, fail
+        j pass
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
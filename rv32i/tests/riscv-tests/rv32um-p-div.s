@@ -0,0 +1,29 @@
+# rv32um div: signed division, rounding towards zero
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        TEST_RR_OP 2, div, 0x00000003, 20, 6
+        TEST_RR_OP 3, div, 0xfffffffd, -20, 6
+        TEST_RR_OP 4, div, 0xfffffffd, 20, -6
+        TEST_RR_OP 5, div, 0x00000003, -20, -6
+
+        TEST_RR_OP 6, div, 0x80000000, -1<<31, 1
+        TEST_RR_OP 7, div, 0x80000000, -1<<31, -1
+
+        TEST_RR_OP 8, div, 0xffffffff, -1<<31, 0
+        TEST_RR_OP 9, div, 0xffffffff, 1, 0
+        TEST_RR_OP 10, div, 0xffffffff, 0, 0
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        j fail
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
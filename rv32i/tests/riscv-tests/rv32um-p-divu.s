@@ -0,0 +1,29 @@
+# rv32um divu: unsigned division
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        TEST_RR_OP 2, divu, 0x00000003, 20, 6
+        TEST_RR_OP 3, divu, 0x2aaaaaa7, -20, 6
+        TEST_RR_OP 4, divu, 0x00000000, 20, -6
+        TEST_RR_OP 5, divu, 0x00000000, -20, -6
+
+        TEST_RR_OP 6, divu, 0x80000000, -1<<31, 1
+        TEST_RR_OP 7, divu, 0x00000000, -1<<31, -1
+
+        TEST_RR_OP 8, divu, 0xffffffff, -1<<31, 0
+        TEST_RR_OP 9, divu, 0xffffffff, 1, 0
+        TEST_RR_OP 10, divu, 0xffffffff, 0, 0
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        j fail
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
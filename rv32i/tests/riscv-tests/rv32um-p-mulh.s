@@ -0,0 +1,61 @@
+# rv32um mulh: the high 32 bits of the signed product
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        TEST_RR_OP 2, mulh, 0x00000000, 0x00000000, 0x00000000
+        TEST_RR_OP 3, mulh, 0x00000000, 0x00000001, 0x00000001
+        TEST_RR_OP 4, mulh, 0x00000000, 0x00000003, 0x00000007
+
+        TEST_RR_OP 5, mulh, 0x00000000, 0x00000000, 0xffff8000
+        TEST_RR_OP 6, mulh, 0x00000000, 0x80000000, 0x00000000
+        TEST_RR_OP 7, mulh, 0x00004000, 0x80000000, 0xffff8000
+
+        TEST_RR_OP 30, mulh, 0xffff0081, 0xaaaaaaab, 0x0002fe7d
+        TEST_RR_OP 31, mulh, 0xffff0081, 0x0002fe7d, 0xaaaaaaab
+
+        TEST_RR_OP 32, mulh, 0x00010000, 0xff000000, 0xff000000
+
+        TEST_RR_OP 33, mulh, 0x00000000, 0xffffffff, 0xffffffff
+        TEST_RR_OP 34, mulh, 0xffffffff, 0xffffffff, 0x00000001
+        TEST_RR_OP 35, mulh, 0xffffffff, 0x00000001, 0xffffffff
+
+        TEST_RR_SRC1_EQ_DEST 8, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_SRC2_EQ_DEST 9, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_SRC12_EQ_DEST 10, mulh, 0x0000a900, 13<<20
+
+        TEST_RR_DEST_BYPASS 11, 0, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_DEST_BYPASS 12, 1, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_DEST_BYPASS 13, 2, mulh, 0x0000a500, 15<<20, 11<<20
+
+        TEST_RR_SRC12_BYPASS 14, 0, 0, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_SRC12_BYPASS 15, 0, 1, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_SRC12_BYPASS 16, 0, 2, mulh, 0x0000a500, 15<<20, 11<<20
+        TEST_RR_SRC12_BYPASS 17, 1, 0, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_SRC12_BYPASS 18, 1, 1, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_SRC12_BYPASS 19, 2, 0, mulh, 0x0000a500, 15<<20, 11<<20
+
+        TEST_RR_SRC21_BYPASS 20, 0, 0, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_SRC21_BYPASS 21, 0, 1, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_SRC21_BYPASS 22, 0, 2, mulh, 0x0000a500, 15<<20, 11<<20
+        TEST_RR_SRC21_BYPASS 23, 1, 0, mulh, 0x00008f00, 13<<20, 11<<20
+        TEST_RR_SRC21_BYPASS 24, 1, 1, mulh, 0x00009a00, 14<<20, 11<<20
+        TEST_RR_SRC21_BYPASS 25, 2, 0, mulh, 0x0000a500, 15<<20, 11<<20
+
+        TEST_RR_ZEROSRC1 26, mulh, 0x00000000, 31<<26
+        TEST_RR_ZEROSRC2 27, mulh, 0x00000000, 32<<26
+        TEST_RR_ZEROSRC12 28, mulh, 0x00000000
+        TEST_RR_ZERODEST 29, mulh, 33<<20, 34<<20
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        j fail
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
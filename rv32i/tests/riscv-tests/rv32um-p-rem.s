@@ -0,0 +1,29 @@
+# rv32um rem: the remainder of signed division, with the sign of the dividend
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        TEST_RR_OP 2, rem, 0x00000002, 20, 6
+        TEST_RR_OP 3, rem, 0xfffffffe, -20, 6
+        TEST_RR_OP 4, rem, 0x00000002, 20, -6
+        TEST_RR_OP 5, rem, 0xfffffffe, -20, -6
+
+        TEST_RR_OP 6, rem, 0x00000000, -1<<31, 1
+        TEST_RR_OP 7, rem, 0x00000000, -1<<31, -1
+
+        TEST_RR_OP 8, rem, 0x80000000, -1<<31, 0
+        TEST_RR_OP 9, rem, 0x00000001, 1, 0
+        TEST_RR_OP 10, rem, 0x00000000, 0, 0
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        j fail
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
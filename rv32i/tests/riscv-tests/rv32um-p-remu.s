@@ -0,0 +1,29 @@
+# rv32um remu: the remainder of unsigned division
+
+.include "riscv_test.s"
+.include "test_macros.s"
+
+RVTEST_CODE_BEGIN
+
+        TEST_RR_OP 2, remu, 0x00000002, 20, 6
+        TEST_RR_OP 3, remu, 0x00000002, -20, 6
+        TEST_RR_OP 4, remu, 0x00000014, 20, -6
+        TEST_RR_OP 5, remu, 0xffffffec, -20, -6
+
+        TEST_RR_OP 6, remu, 0x00000000, -1<<31, 1
+        TEST_RR_OP 7, remu, 0x80000000, -1<<31, -1
+
+        TEST_RR_OP 8, remu, 0x80000000, -1<<31, 0
+        TEST_RR_OP 9, remu, 0x00000001, 1, 0
+        TEST_RR_OP 10, remu, 0x00000000, 0, 0
+
+        TEST_PASSFAIL
+
+        .align 2
+mtvec_handler:
+        j fail
+
+RVTEST_CODE_END
+
+RVTEST_DATA_BEGIN
+RVTEST_DATA_END
@@ -0,0 +1,127 @@
+# The register-register part of riscv-tests' test_macros.h as llvm-mc macros. The arguments are
+# split on commas and spaces, so an expression like -1<<31 must not have spaces in it.
+# Values are 32 bits already, there is no MASK_XLEN
+
+.macro TEST_INSERT_NOPS n
+        .rept \n
+        nop
+        .endr
+.endm
+
+# TEST_CASE without the code argument, the code goes between `li gp, n` and this
+.macro TEST_CHECK reg, val
+        li x7, \val
+        bne \reg, x7, fail
+.endm
+
+.macro TEST_RR_OP testnum, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x1, \val1
+        li x2, \val2
+        \inst x14, x1, x2
+        TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_SRC1_EQ_DEST testnum, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x1, \val1
+        li x2, \val2
+        \inst x1, x1, x2
+        TEST_CHECK x1, \result
+.endm
+
+.macro TEST_RR_SRC2_EQ_DEST testnum, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x1, \val1
+        li x2, \val2
+        \inst x2, x1, x2
+        TEST_CHECK x2, \result
+.endm
+
+.macro TEST_RR_SRC12_EQ_DEST testnum, inst, result, val1
+test_\testnum:
+        li gp, \testnum
+        li x1, \val1
+        \inst x1, x1, x1
+        TEST_CHECK x1, \result
+.endm
+
+.macro TEST_RR_DEST_BYPASS testnum, nop_cycles, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x4, 0
+1:      li x1, \val1
+        li x2, \val2
+        \inst x14, x1, x2
+        TEST_INSERT_NOPS \nop_cycles
+        addi x6, x14, 0
+        addi x4, x4, 1
+        li x5, 2
+        bne x4, x5, 1b
+        TEST_CHECK x6, \result
+.endm
+
+.macro TEST_RR_SRC12_BYPASS testnum, src1_nops, src2_nops, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x4, 0
+1:      li x1, \val1
+        TEST_INSERT_NOPS \src1_nops
+        li x2, \val2
+        TEST_INSERT_NOPS \src2_nops
+        \inst x14, x1, x2
+        addi x4, x4, 1
+        li x5, 2
+        bne x4, x5, 1b
+        TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_SRC21_BYPASS testnum, src1_nops, src2_nops, inst, result, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x4, 0
+1:      li x2, \val2
+        TEST_INSERT_NOPS \src1_nops
+        li x1, \val1
+        TEST_INSERT_NOPS \src2_nops
+        \inst x14, x1, x2
+        addi x4, x4, 1
+        li x5, 2
+        bne x4, x5, 1b
+        TEST_CHECK x14, \result
+.endm
+
+.macro TEST_RR_ZEROSRC1 testnum, inst, result, val
+test_\testnum:
+        li gp, \testnum
+        li x1, \val
+        \inst x2, x0, x1
+        TEST_CHECK x2, \result
+.endm
+
+.macro TEST_RR_ZEROSRC2 testnum, inst, result, val
+test_\testnum:
+        li gp, \testnum
+        li x1, \val
+        \inst x2, x1, x0
+        TEST_CHECK x2, \result
+.endm
+
+.macro TEST_RR_ZEROSRC12 testnum, inst, result
+test_\testnum:
+        li gp, \testnum
+        \inst x1, x0, x0
+        TEST_CHECK x1, \result
+.endm
+
+.macro TEST_RR_ZERODEST testnum, inst, val1, val2
+test_\testnum:
+        li gp, \testnum
+        li x1, \val1
+        li x2, \val2
+        \inst x0, x1, x2
+        TEST_CHECK x0, 0
+.endm
@@ -193,9 +193,9 @@ impl<const T: usize, const R: usize> SignedOrd<Bytes<R>> for Bytes<T> {
             };
         }
 
-        let mut self_no_msb = self.clone();
+        let mut self_no_msb = self;
         self_no_msb[T - 1] = Some(self_no_msb[T - 1].unwrap() & 0x7F);
-        let mut other_no_msb = rhs.clone();
+        let mut other_no_msb = rhs;
         other_no_msb[T - 1] = Some(other_no_msb[T - 1].unwrap() & 0x7F);
 
         // if self is longer than other
@@ -848,7 +848,7 @@ mod tests {
 
     #[test]
     fn test_cmp() {
-        for i in 0..u16::MAX {
+        for _ in 0..u16::MAX {
             let a = random::<u32>();
             let b = random::<u32>();
             let lhs = Bytes::<4>::from(a);
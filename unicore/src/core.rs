@@ -8,9 +8,12 @@ use crate::regfile::RegFile;
 use crate::types::Word;
 use crate::types::*;
 use crate::util::sign_extend;
+use rsim_core::elf::{Elf, SymbolTable};
+use rsim_core::logging::{Level, LogRecord, Logger, WriterSink};
 use std::cmp::Ordering::Less;
 use std::collections::HashMap;
 use std::process::exit;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Core {
@@ -29,6 +32,9 @@ pub struct Core {
     next_state: States,
     regfile: RegFile,
     mem_ctl: MemCtl,
+    logger: Logger,
+    cycle: u128,
+    symbols: SymbolTable,
 }
 
 impl Core {
@@ -409,7 +415,7 @@ impl Core {
             mux_sel::regfile::LB => {
                 let val = self.mdr[mdr_idx]
                     .map(|byte| Word::from(byte as u32))
-                    .unwrap_or(Word::unknown());
+                    .unwrap_or_default();
                 if !val.has_unknown() {
                     sign_extend(Into::<Option<u32>>::into(val).unwrap(), 7)
                 } else {
@@ -418,15 +424,15 @@ impl Core {
             }
             mux_sel::regfile::LBU => self.mdr[mdr_idx]
                 .map(|byte| Word::from(byte as u32))
-                .unwrap_or(Word::unknown()),
+                .unwrap_or_default(),
             mux_sel::regfile::LH => {
                 let val = self.mdr[mdr_idx]
                     .map(|lsb| {
                         self.mdr[mdr_idx + 1]
                             .map(|msb| Word::from((((msb as u16) << 8) | lsb as u16) as u32))
-                            .unwrap_or(Word::unknown())
+                            .unwrap_or_default()
                     })
-                    .unwrap_or(Word::unknown());
+                    .unwrap_or_default();
                 if !val.has_unknown() {
                     sign_extend(Into::<Option<u32>>::into(val).unwrap(), 15)
                 } else {
@@ -437,9 +443,9 @@ impl Core {
                 .map(|lsb| {
                     self.mdr[mdr_idx + 1]
                         .map(|msb| Word::from((((msb as u16) << 8) | lsb as u16) as u32))
-                        .unwrap_or(Word::unknown())
+                        .unwrap_or_default()
                 })
-                .unwrap_or(Word::unknown()),
+                .unwrap_or_default(),
             _ => Word::unknown(),
         }
     }
@@ -498,7 +504,7 @@ impl Core {
     }
 
     fn set_next_state(&mut self) {
-        self.next_state = self.state.clone();
+        self.next_state = self.state;
 
         self.next_state = match self.state {
             States::Fetch1 => States::Fetch2,
@@ -554,6 +560,8 @@ impl Core {
             || self.state == States::Store2
             || self.state == States::Load1
             || self.state == States::AddrCalc
+            // the store is logged once, in the cycle it is written
+            || (self.state == States::Store1 && !self.mem_ctl.is_resp())
         {
             return;
         }
@@ -589,7 +597,7 @@ impl Core {
             }
             line.push_str(&format!(
                 " mem 0x{}",
-                self.mar & Word::from(0xFFFFFFFCu32) + Byte::from(byte_shift)
+                (self.mar & Word::from(0xFFFFFFFCu32)) + Byte::from(byte_shift)
             ));
         }
 
@@ -611,7 +619,7 @@ impl Core {
 
             line.push_str(&format!(
                 " mem 0x{}",
-                self.mar & Word::from(0xFFFFFFFCu32) + Byte::from(byte_shift)
+                (self.mar & Word::from(0xFFFFFFFCu32)) + Byte::from(byte_shift)
             ));
             if let Some(data_out) = Into::<Option<u32>>::into(self.data_out) {
                 let shifted_data = data_out >> (8 * byte_shift);
@@ -631,8 +639,13 @@ impl Core {
             }
         }
 
-        line.push_str("\n");
-        print!("{}", line);
+        self.logger.log(&LogRecord {
+            level: Level::Info,
+            cycle: Some(self.cycle),
+            component_id: None,
+            component_name: "Core",
+            message: format_args!("{}", line),
+        });
         if self.regfile_din.has_unknown() {
             exit(0);
         }
@@ -645,12 +658,36 @@ impl Core {
         self.write_spike_log();
         self.update_datapath();
         self.set_next_state();
+        self.cycle += 1;
     }
 
-    pub fn load_bin(&mut self, data: &Vec<u8>, addr: Word) {
+    /// The spike log goes through here, e.g. swap the sinks to redirect it
+    pub fn get_logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn load_bin(&mut self, data: &[u8], addr: Word) {
         self.mem_ctl.load_bin(data, addr)
     }
 
+    /// Places every segment and starts at the entry point
+    pub fn load_elf(&mut self, elf: &Elf) {
+        for segment in &elf.segments {
+            self.load_bin(&segment.data, Word::from(segment.addr));
+            if segment.zeros > 0 {
+                let bss = segment.addr.wrapping_add(segment.data.len() as u32);
+                self.mem_ctl.load_zeros(Word::from(bss), segment.zeros);
+            }
+        }
+        self.pc = Word::from(elf.entry);
+        self.symbols = elf.symbols.clone();
+    }
+
+    /// Empty unless the program was loaded with `load_elf`
+    pub fn get_symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
     pub fn should_halt(&self) -> bool {
         (self.state == States::Imm && self.ir.data == Word::from(0xF0002013u32))
             || (self.state == States::Br && self.ir.data == Word::from(0x00000063u32))
@@ -675,6 +712,60 @@ impl Default for Core {
             next_state: States::Fetch1,
             regfile: RegFile::default(),
             mem_ctl: MemCtl::default(),
+            logger: {
+                // The spike log has a format of its own, no context prefix
+                let logger = Logger::default();
+                logger.set_sinks(vec![Arc::new(WriterSink::plain(std::io::stdout()))]);
+                logger
+            },
+            cycle: 0,
+            symbols: Default::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsim_core::elf::{Segment, Symbol};
+
+    #[test]
+    fn test_load_elf() {
+        let mut symbols = SymbolTable::default();
+        symbols.insert(Symbol {
+            name: "_start".to_string(),
+            addr: 0x80000000,
+            size: 8,
+            is_function: true,
+        });
+        let elf = Elf {
+            entry: 0x80000004,
+            segments: vec![
+                Segment {
+                    addr: 0x80000000,
+                    data: 0x00500093u32.to_le_bytes().to_vec(),
+                    zeros: 0,
+                },
+                Segment {
+                    addr: 0x80001000,
+                    data: vec![1, 2],
+                    zeros: 6,
+                },
+            ],
+            symbols,
+        };
+        let mut core = Core::default();
+        // .bss is cleared even where something was loaded before
+        core.load_bin(&[0xFF; 4], Word::from(0x80001004u32));
+        core.load_elf(&elf);
+
+        assert_eq!(core.pc, Word::from(0x80000004u32));
+        assert_eq!(core.get_symbols().lookup("_start"), Some(0x80000000));
+        let read = |addr: u32| core.mem_ctl.read(&Word::from(addr), Byte::from(0xFu8));
+        assert_eq!(read(0x80000000), Word::from(0x00500093u32));
+        assert_eq!(read(0x80001000), Word::from(0x0201u32));
+        assert_eq!(read(0x80001004), Word::zeros());
+        // past p_memsz nothing was loaded
+        assert_eq!(read(0x80001008), Word::unknown());
+    }
+}
@@ -2,7 +2,7 @@ use crate::types::Byte;
 use crate::types::Word;
 use crate::util::sign_extend;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct IR {
     pub data: Word,
     pub funct3: Byte,
@@ -38,8 +38,7 @@ impl IR {
                 (((inst >> 31) & 0b1) << 12)
                     | (((inst >> 7) & 0b1) << 11)
                     | (((inst >> 25) & 0b111111) << 5)
-                    | (((inst >> 8) & 0b1111) << 1)
-                    | 0b0,
+                    | (((inst >> 8) & 0b1111) << 1),
                 12,
             );
             self.u_imm = Word::from(((inst >> 12) & 0xFFFFF) << 12);
@@ -47,8 +46,7 @@ impl IR {
                 (((inst >> 31) & 0b1) << 20)
                     | (((inst >> 12) & 0xFF) << 12)
                     | (((inst >> 20) & 0b1) << 11)
-                    | (((inst >> 21) & 0x3FF) << 1)
-                    | 0b0,
+                    | (((inst >> 21) & 0x3FF) << 1),
                 20,
             );
             self.rs1 = Byte::from(((inst >> 15) & 0x1F) as u8);
@@ -58,25 +56,6 @@ impl IR {
     }
 }
 
-impl Default for IR {
-    fn default() -> Self {
-        Self {
-            data: Default::default(),
-            funct3: Default::default(),
-            funct7: Default::default(),
-            opcode: Default::default(),
-            i_imm: Default::default(),
-            s_imm: Default::default(),
-            b_imm: Default::default(),
-            u_imm: Default::default(),
-            j_imm: Default::default(),
-            rs1: Default::default(),
-            rs2: Default::default(),
-            rd: Default::default(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,6 @@
 use crate::core::Core;
 use crate::types::Word;
+use rsim_core::elf::Elf;
 use std::fs;
 
 pub mod byte;
@@ -15,9 +16,21 @@ mod util;
 
 fn main() {
     let mut core = Core::default();
+    if std::env::var("QUIET").is_ok() {
+        core.get_logger().set_sinks(vec![]);
+    }
 
-    let data = fs::read("/home/frank/dev/sim_poc/unicore/coremark_rv32i.bin").unwrap();
-    core.load_bin(&data, Word::from(0x40000000u32));
+    let data = fs::read(
+        std::env::var("BIN_FILE")
+            .expect("Provide the path to the ELF or binary file in BIN_FILE env var"),
+    )
+    .unwrap();
+    // flat images go to 0x40000000
+    if Elf::is_elf(&data) {
+        core.load_elf(&Elf::parse(&data).expect("BIN_FILE should be a RISC-V ELF32 file"));
+    } else {
+        core.load_bin(&data, Word::from(0x40000000u32));
+    }
 
     while !core.should_halt() {
         core.next_cycle();
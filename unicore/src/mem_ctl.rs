@@ -8,6 +8,8 @@ use std::fmt::Debug;
 #[derive(Debug)]
 pub struct MemCtl {
     backend_mem: BTreeMap<Word, Byte>,
+    /// `(start, len)` ranges of `.bss`, the bytes in them never written to read as 0
+    zero_fill: Vec<(u32, u32)>,
     mmio_addr: BTreeSet<Word>,
     mmio_ctl: MMIOCtl,
     mem_access_latency: u128,
@@ -26,7 +28,7 @@ impl MemCtl {
     }
 
     pub fn tick(&mut self) {
-        self.mem_wait_left = (self.mem_wait_left - 1).min(0);
+        self.mem_wait_left = self.mem_wait_left.saturating_sub(1);
     }
 
     pub fn read(&self, addr: &Word, rmask: Byte) -> Word {
@@ -39,6 +41,8 @@ impl MemCtl {
                         self.mmio_ctl.read(&addr_idx).into()
                     } else if self.backend_mem.contains_key(&addr_idx) {
                         self.backend_mem[&addr_idx].into()
+                    } else if self.is_zero_filled(&addr_idx) {
+                        Some(0)
                     } else {
                         None
                     }
@@ -49,6 +53,14 @@ impl MemCtl {
         ret
     }
 
+    fn is_zero_filled(&self, addr: &Word) -> bool {
+        Into::<Option<u32>>::into(*addr).is_some_and(|addr| {
+            self.zero_fill
+                .iter()
+                .any(|&(start, len)| addr.wrapping_sub(start) < len)
+        })
+    }
+
     // pub fn read(&self, addr: &Word) -> Word {
     //     let mut ret = Word::unknown();
     //     for i in 0..4 {
@@ -70,9 +82,7 @@ impl MemCtl {
             for i in 0..4 {
                 if wmask >> i & 0x1 == 0x1 {
                     let addr_idx = *addr + Word::from(i as u32);
-                    let data = data[i]
-                        .map(|byte| Byte::from(byte))
-                        .unwrap_or(Byte::unknown());
+                    let data = data[i].map(Byte::from).unwrap_or_default();
                     if self.mmio_addr.contains(&addr_idx) {
                         self.mmio_ctl.write(&addr_idx, data);
                     } else {
@@ -88,18 +98,30 @@ impl MemCtl {
         self.mmio_ctl.insert_mmio_driver(addr, driver);
     }
 
-    pub fn load_bin(&mut self, data: &Vec<u8>, addr: Word) {
+    pub fn load_bin(&mut self, data: &[u8], addr: Word) {
         for i in 0..data.len() as u32 {
             self.backend_mem
                 .insert(addr + Word::from(i), data[i as usize].into());
         }
     }
+
+    /// `len` zero bytes from `addr`, kept as a range so a large `.bss` costs nothing
+    pub fn load_zeros(&mut self, addr: Word, len: u32) {
+        let Some(start) = Into::<Option<u32>>::into(addr) else {
+            return;
+        };
+        self.backend_mem.retain(|addr, _| {
+            Into::<Option<u32>>::into(*addr).is_none_or(|addr| addr.wrapping_sub(start) >= len)
+        });
+        self.zero_fill.push((start, len));
+    }
 }
 
 impl Default for MemCtl {
     fn default() -> Self {
         Self {
             backend_mem: Default::default(),
+            zero_fill: vec![],
             mmio_addr: Default::default(),
             mmio_ctl: MMIOCtl::default(),
             mem_access_latency: 2,
@@ -113,3 +135,22 @@ impl Default for MemCtl {
 //         write!(f, "mem_wait_left: {}", self.mem_wait_left)
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick() {
+        let mut mem_ctl = MemCtl::default();
+        assert!(mem_ctl.is_resp());
+        mem_ctl.req_access();
+        mem_ctl.tick();
+        assert!(!mem_ctl.is_resp());
+        mem_ctl.tick();
+        assert!(mem_ctl.is_resp());
+        // stays answered until the next request
+        mem_ctl.tick();
+        assert!(mem_ctl.is_resp());
+    }
+}
@@ -4,22 +4,23 @@ use crate::types::Word;
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 
+#[derive(Default)]
 pub struct MMIOCtl {
     mmio_drivers: BTreeMap<Word, Box<dyn MMIODriver>>,
 }
 
 impl MMIOCtl {
     pub fn read(&self, addr: &Word) -> Byte {
-        if self.mmio_drivers.contains_key(&addr) {
-            self.mmio_drivers[&addr].read()
+        if self.mmio_drivers.contains_key(addr) {
+            self.mmio_drivers[addr].read()
         } else {
             Byte::unknown()
         }
     }
 
     pub fn write(&self, addr: &Word, data: Byte) {
-        if self.mmio_drivers.contains_key(&addr) {
-            self.mmio_drivers[&addr].write(data);
+        if self.mmio_drivers.contains_key(addr) {
+            self.mmio_drivers[addr].write(data);
         }
     }
 
@@ -28,16 +29,8 @@ impl MMIOCtl {
     }
 }
 
-impl Default for MMIOCtl {
-    fn default() -> Self {
-        Self {
-            mmio_drivers: BTreeMap::new(),
-        }
-    }
-}
-
 impl Debug for MMIOCtl {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "")
     }
-}
\ No newline at end of file
+}